@@ -0,0 +1,163 @@
+//! Binary searches the history for the commit that introduced a bug.
+//!
+//! Once a bad commit and at least one good commit are known, the candidates are the commits
+//! reachable from the bad one but from none of the good ones. The commit midway through them is
+//! checked out to be tested, and marking it good or bad halves the candidates, until only the bad
+//! commit itself remains: the first bad commit.
+//!
+//! A bisection in progress is recorded in `.gitlet/BISECT_LOG`, whose first line, `start <HEAD>`,
+//! holds what the HEAD file held when it started, followed by a `good <hash>` or `bad <hash>` line
+//! for each commit marked. `.gitlet/BISECT_HEAD` holds the hash of the commit being tested.
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+use crate::commit::{self, Commit};
+use crate::repo;
+
+/// Dispatches for `gitlet bisect start`, which begins a bisection from the HEAD.
+///
+/// # Panics
+///
+/// Returns an error if a bisection is already in progress or there are no commits yet.
+pub fn start() -> Result<()> {
+    let log_path = state_path("BISECT_LOG")?;
+    anyhow::ensure!(
+        !log_path.exists(),
+        "A bisect is already in progress. Use 'gitlet bisect reset' to end it"
+    );
+    anyhow::ensure!(
+        !repo::read_head_hash()?.is_empty(),
+        "You do not have the initial commit yet"
+    );
+
+    let head = repo::read_head_file()?;
+    fs::write(log_path, format!("start {head}\n")).context("Write .gitlet/BISECT_LOG")?;
+
+    println!("Mark a bad commit with 'gitlet bisect bad' and a good one with 'gitlet bisect good'");
+
+    Ok(())
+}
+
+/// Dispatches for `gitlet bisect good` and `gitlet bisect bad`, marking `commit`, by default the
+/// HEAD, as good or bad. Once both a good and a bad commit are known, checks out the next commit
+/// to test, or prints the first bad commit if it has been found.
+///
+/// # Panics
+///
+/// Returns an error if no bisection is in progress, or if the bad commit is reachable from a good
+/// one.
+pub fn mark(commit: Option<&str>, good: bool) -> Result<()> {
+    let log_path = state_path("BISECT_LOG")?;
+    anyhow::ensure!(
+        log_path.exists(),
+        "You need to start by running 'gitlet bisect start'"
+    );
+
+    let hash = repo::resolve_ref(commit.unwrap_or("HEAD"))?;
+    let term = if good { "good" } else { "bad" };
+    let mut log = fs::read_to_string(&log_path).context("Read .gitlet/BISECT_LOG")?;
+    log.push_str(&format!("{term} {hash}\n"));
+    fs::write(&log_path, &log).context("Write .gitlet/BISECT_LOG")?;
+
+    next(&log)
+}
+
+/// Dispatches for `gitlet bisect reset`, ending the bisection and checking out what was checked
+/// out when it started.
+pub fn reset() -> Result<()> {
+    let log_path = state_path("BISECT_LOG")?;
+    if !log_path.exists() {
+        println!("Not bisecting");
+        return Ok(());
+    }
+
+    let log = fs::read_to_string(&log_path).context("Read .gitlet/BISECT_LOG")?;
+    let Some(original) = log.lines().find_map(|line| line.strip_prefix("start ")) else {
+        anyhow::bail!("Could not find the original HEAD in .gitlet/BISECT_LOG");
+    };
+    repo::switch(original, None, false, false, false)?;
+
+    let bisect_head = state_path("BISECT_HEAD")?;
+    if bisect_head.exists() {
+        fs::remove_file(bisect_head).context("Delete .gitlet/BISECT_HEAD")?;
+    }
+    fs::remove_file(log_path).context("Delete .gitlet/BISECT_LOG")?;
+
+    Ok(())
+}
+
+/// Checks out the commit midway through the remaining candidates, as recorded by `log`, or prints
+/// the first bad commit if it is the only candidate left.
+fn next(log: &str) -> Result<()> {
+    let mut bad = None;
+    let mut good = Vec::new();
+    for line in log.lines() {
+        match line.split_once(' ') {
+            // Only the most recently marked bad commit matters, since it is the earliest known.
+            Some(("bad", hash)) => bad = Some(hash),
+            Some(("good", hash)) => good.push(hash),
+            _ => {}
+        }
+    }
+
+    let (Some(bad), false) = (bad, good.is_empty()) else {
+        println!(
+            "Waiting for both a good and a bad commit. Mark them with 'gitlet bisect good' and \
+             'gitlet bisect bad'"
+        );
+        return Ok(());
+    };
+
+    let candidates = candidates(bad, &good)?;
+    anyhow::ensure!(
+        candidates.first().is_some_and(|c| c == bad),
+        "The bad commit {} is reachable from a good commit",
+        &bad[..7]
+    );
+
+    // Every candidate but the bad commit itself still needs testing, most recent first.
+    let untested = &candidates[1..];
+    if untested.is_empty() {
+        fs::write(state_path("BISECT_HEAD")?, bad).context("Write .gitlet/BISECT_HEAD")?;
+        println!("{bad} is the first bad commit");
+        println!("{}", Commit::load(bad)?);
+        return Ok(());
+    }
+
+    let midpoint = &untested[untested.len() / 2];
+    let left = untested.len() / 2;
+    let steps = (left + 1).ilog2();
+    println!(
+        "Bisecting: {left} revision{} left to test after this (roughly {steps} step{})",
+        if left == 1 { "" } else { "s" },
+        if steps == 1 { "" } else { "s" },
+    );
+    fs::write(state_path("BISECT_HEAD")?, midpoint).context("Write .gitlet/BISECT_HEAD")?;
+    repo::checkout_detached(midpoint)?;
+    println!("Test it, then mark it with 'gitlet bisect good' or 'gitlet bisect bad'");
+
+    Ok(())
+}
+
+/// Returns the hashes of the commits reachable from `bad` but from none of `good`, most recent
+/// first, in the order `gitlet log` would show them.
+fn candidates(bad: &str, good: &[&str]) -> Result<Vec<String>> {
+    let mut known_good = HashSet::new();
+    for hash in good {
+        known_good.extend(commit::ancestors(hash)?);
+    }
+
+    Ok(Commit::load(bad)
+        .with_context(|| format!("Load commit {bad}"))?
+        .iter()
+        .map(|c| c.hash)
+        .filter(|hash| !known_good.contains(hash))
+        .collect())
+}
+
+fn state_path(name: &str) -> Result<PathBuf> {
+    Ok(repo::abs_path_to_repo_root()?.join(".gitlet").join(name))
+}
@@ -0,0 +1,142 @@
+//! Annotates each line of a file with the commit that last changed it.
+//!
+//! Starting from the HEAD's version of the file, the history is walked back along first parents.
+//! At each commit that changed the file, its version is compared to its parent's by their longest
+//! common subsequence of lines, as `diff` does: the lines it has in common with the parent are
+//! traced further back, and the rest are attributed to the commit.
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::DateTime;
+
+use crate::blob::Blob;
+use crate::commit::{self, Commit, get_commit_blobs};
+use crate::merge;
+use crate::repo;
+
+/// Dispatches for the `gitlet blame` command.
+///
+/// Prints each line of the HEAD commit's version of the file as
+/// `<short hash> (<author> <date>) <line>`, naming the commit that introduced the line.
+///
+/// # Panics
+///
+/// Returns an error if the HEAD commit does not track the file or if the file is not text.
+pub fn blame(filepath: &str) -> Result<()> {
+    let path = repo::path_from_repo_root(Path::new(filepath))
+        .context("Convert filepath to be relative to working tree root")?;
+    let head = repo::read_head_hash()?;
+    let Some(blob) = blob_at(&head, &path)? else {
+        anyhow::bail!("'{}' is not tracked by the HEAD commit", path.display());
+    };
+    let content = read_text(&path, &blob)?
+        .with_context(|| format!("'{}' is not a text file", path.display()))?;
+    let lines: Vec<&str> = content.lines().collect();
+
+    let origins = line_origins(&head, &path, blob, lines.len())?;
+
+    let mut commits: HashMap<&str, Commit> = HashMap::new();
+    for hash in &origins {
+        if !commits.contains_key(hash.as_str()) {
+            commits.insert(hash, Commit::load(hash)?);
+        }
+    }
+    let author = |commit: &Commit| match commit::format_commit(commit, "%an") {
+        name if name.is_empty() => "unknown".to_string(),
+        name => name,
+    };
+    let width = commits.values().map(|c| author(c).len()).max().unwrap_or(0);
+
+    for (line, hash) in lines.iter().zip(&origins) {
+        let commit = &commits[hash.as_str()];
+        let date = DateTime::from_timestamp(commit.timestamp() as i64, 0)
+            .map(|date| date.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+        println!(
+            "{} ({:width$} {date}) {line}",
+            commit.short_hash(),
+            author(commit)
+        );
+    }
+
+    Ok(())
+}
+
+/// Returns the hash of the commit that introduced each of the `line_count` lines of the file at
+/// `path` as it is in the commit `start`, where its blob is `blob`.
+fn line_origins(start: &str, path: &Path, blob: Blob, line_count: usize) -> Result<Vec<String>> {
+    let mut origins: Vec<Option<String>> = vec![None; line_count];
+    // The lines yet to be attributed, as pairs of their index in the commit being looked at and in
+    // `start`'s version.
+    let mut pending: Vec<(usize, usize)> = (0..line_count).map(|i| (i, i)).collect();
+    let (mut hash, mut blob) = (start.to_string(), blob);
+
+    while !pending.is_empty() {
+        let commit = Commit::load(&hash).with_context(|| format!("Load commit {hash}"))?;
+        let parent = commit.parents().first().map(|p| p.to_string());
+        let parent_blob = match &parent {
+            Some(parent) => blob_at(parent, path)?,
+            None => None,
+        };
+
+        let (in_parent, next) = match (parent, parent_blob) {
+            // An unchanged file has nothing to attribute to this commit.
+            (Some(parent), Some(parent_blob)) if parent_blob.hash == blob.hash => {
+                (hash, blob) = (parent, parent_blob);
+                continue;
+            }
+            (Some(parent), Some(parent_blob)) => (
+                common_lines(path, &blob, &parent_blob)?,
+                Some((parent, parent_blob)),
+            ),
+            // Every remaining line was added along with the file.
+            _ => (HashMap::new(), None),
+        };
+
+        pending = pending
+            .into_iter()
+            .filter_map(|(i, line)| match in_parent.get(&i) {
+                Some(&j) => Some((j, line)),
+                None => {
+                    origins[line] = Some(hash.clone());
+                    None
+                }
+            })
+            .collect();
+        match next {
+            Some(next) => (hash, blob) = next,
+            None => break,
+        }
+    }
+
+    Ok(origins.into_iter().map(Option::unwrap_or_default).collect())
+}
+
+/// Maps the index of each line of the `current` version of the file at `path` that is also in the
+/// `previous` version to its index there. No lines are in common if either version is not text.
+fn common_lines(path: &Path, current: &Blob, previous: &Blob) -> Result<HashMap<usize, usize>> {
+    let (Some(current), Some(previous)) = (read_text(path, current)?, read_text(path, previous)?)
+    else {
+        return Ok(HashMap::new());
+    };
+    let current: Vec<&str> = current.lines().collect();
+    let previous: Vec<&str> = previous.lines().collect();
+
+    Ok(merge::longest_common_subsequence(&current, &previous)
+        .into_iter()
+        .collect())
+}
+
+/// Returns the blob of the file at `path` in the given commit, if the commit tracks it.
+fn blob_at(hash: &str, path: &Path) -> Result<Option<Blob>> {
+    Ok(get_commit_blobs(hash)?.remove(path))
+}
+
+/// Returns the contents of the blob of the file at `path`, or None if they are not UTF-8 text.
+fn read_text(path: &Path, blob: &Blob) -> Result<Option<String>> {
+    let content = blob
+        .read()
+        .with_context(|| format!("Read blob of '{}'", path.display()))?;
+    Ok(String::from_utf8(content).ok())
+}
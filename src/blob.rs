@@ -2,56 +2,90 @@
 //! .gitlet/blobs directory.
 use std::{
     fs,
-    io::{self, BufRead, BufWriter, Write},
+    io::{self, BufRead, Read, Write},
     path,
 };
 
 use anyhow::{Context, Result};
-use flate2::{
-    Compression,
-    write::{ZlibDecoder, ZlibEncoder},
-};
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
 use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
 
 use crate::repo;
+use crate::transaction::Transaction;
+
+/// File mode of a regular, non-executable file.
+pub const REGULAR_MODE: u32 = 0o100644;
+/// File mode of an executable file.
+pub const EXECUTABLE_MODE: u32 = 0o100755;
 
 /// Represents a blob, which is the gitlet object for a tracked file.
 /// 'id': 40-char String produced by the Sha1 hash
+/// 'mode': The file mode, either REGULAR_MODE or EXECUTABLE_MODE. It is not part of the hash, so
+/// that files differing only in mode share a blob object.
 /// 'blobpath': Path to the blob
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub(crate) struct Blob {
     pub(crate) hash: String,
+    #[serde(default = "regular_mode")]
+    pub(crate) mode: u32,
+}
+
+fn regular_mode() -> u32 {
+    REGULAR_MODE
 }
 
 impl Blob {
     /// Constructs a new Blob from the provided file path. This provides the necessary metadata
     /// with which gitlet may stage a file, commit it, and restore it.
     pub fn new(fpath: &path::Path) -> Result<Self> {
-        let mut hasher = Sha1::new();
-
         let f = std::fs::File::open(fpath)
             .with_context(|| format!("opening file for new blob to hash: '{fpath:?}'"))?;
-        let buf = io::BufReader::new(&f);
-
-        for bufline in buf.lines() {
-            hasher.update(
-                bufline.with_context(|| format!("Could not read buffered file `{:?}`", &fpath))?,
-            );
-        }
+        let hash = hash_lines(io::BufReader::new(&f))
+            .with_context(|| format!("Could not read buffered file `{:?}`", &fpath))?;
+
+        #[cfg(unix)]
+        let mode = {
+            use std::os::unix::fs::PermissionsExt;
+            let permissions = f
+                .metadata()
+                .with_context(|| format!("Read permissions of '{}'", fpath.display()))?
+                .permissions();
+            if permissions.mode() & 0o111 != 0 {
+                EXECUTABLE_MODE
+            } else {
+                REGULAR_MODE
+            }
+        };
+        #[cfg(not(unix))]
+        let mode = REGULAR_MODE;
+
+        Ok(Self { hash, mode })
+    }
 
-        let hash = hasher.finalize();
-        let hash = hex::encode(hash);
+    /// Returns the path to the blob object file in `.gitlet/blobs/`.
+    fn blobpath(&self) -> Result<path::PathBuf> {
+        Ok(repo::abs_path_to_repo_root()?
+            .join(".gitlet/blobs")
+            .join(&self.hash[..2])
+            .join(&self.hash[2..]))
+    }
 
-        Ok(Self { hash })
+    /// Returns true if the blob object file has already been written to the repository.
+    pub fn exists(&self) -> bool {
+        self.blobpath().is_ok_and(|p| p.exists())
     }
 
     /// Writes the blob object file using Zlib compression on the file.
+    ///
+    /// Since blobs are addressed by their content, nothing is written if the blob object file
+    /// already exists.
     pub fn save(&self, fpath: &path::Path) -> Result<()> {
-        let blobpath = repo::abs_path_to_repo_root()?
-            .join(".gitlet/blobs")
-            .join(&self.hash[..2])
-            .join(&self.hash[2..]);
+        if self.exists() {
+            return Ok(());
+        }
+
+        let blobpath = self.blobpath()?;
         fs::create_dir_all(blobpath.parent().unwrap())
             .context("create .gitlet/blobs/##/ subdirectory")?;
 
@@ -69,55 +103,81 @@ impl Blob {
 
     /// Deletes the corresponding blob object file from the repository, consuming the Blob.
     pub fn delete(self) -> Result<()> {
-        let blobpath = repo::abs_path_to_repo_root()?
-            .join(".gitlet/blobs")
-            .join(&self.hash[..2])
-            .join(&self.hash[2..]);
+        let blobpath = self.blobpath()?;
 
         fs::remove_file(blobpath).context("Delete blob from repository")?;
         Ok(())
     }
 
-    /// Reads the blob object file using Zlib decompression to retrieve the file.
-    pub fn restore(&self, fpath: &path::Path) -> Result<()> {
-        let blobpath = repo::abs_path_to_repo_root()?
-            .join(".gitlet/blobs")
-            .join(&self.hash[..2])
-            .join(&self.hash[2..]);
+    /// Returns the decompressed contents of the blob object file, after verifying them against
+    /// the hash.
+    pub fn read(&self) -> Result<Vec<u8>> {
+        let blobpath = self.blobpath()?;
 
-        let mut blobfile =
+        let blobfile =
             fs::File::open(blobpath).context("Open blob object file for decompression")?;
+        let mut content = Vec::new();
+        ZlibDecoder::new(blobfile)
+            .read_to_end(&mut content)
+            .with_context(|| format!("Decompress blob object {}", self.hash))?;
+
+        let hash = hash_lines(content.as_slice())
+            .with_context(|| format!("Read decompressed blob object {}", self.hash))?;
+        anyhow::ensure!(
+            hash == self.hash,
+            "Blob object {} is corrupt: its contents hash to {hash}",
+            self.hash
+        );
+
+        Ok(content)
+    }
 
-        // Create any missing directories before creating the file.
-        if let Some(parent_dirs) = fpath.parent() {
-            fs::create_dir_all(parent_dirs).with_context(|| {
-                format!("Create directories for dirpath '{}'", parent_dirs.display())
-            })?;
-        }
-        let f = fs::File::create(fpath).with_context(|| {
-            format!(
-                "Create file '{}' in working tree for streaming blob object",
-                fpath.display()
-            )
-        })?;
+    /// Reads the blob object file using Zlib decompression to retrieve the file.
+    ///
+    /// The file is only replaced once the blob has been fully decompressed and its contents
+    /// verified against the hash, so a corrupt blob leaves the working tree untouched.
+    pub fn restore(&self, fpath: &path::Path) -> Result<()> {
+        let content = self.read()?;
 
-        let decoder = ZlibDecoder::new(f);
-        let mut decoder = BufWriter::new(decoder);
+        let mut tx = Transaction::begin();
+        tx.stage_file_write(fpath, content);
+        tx.commit().with_context(|| {
+            format!("Write blob object to '{}' in working tree", fpath.display())
+        })?;
 
-        std::io::copy(&mut blobfile, &mut decoder)
-            .context("Decompress blob object into working tree file")?;
+        #[cfg(unix)]
+        if self.mode == EXECUTABLE_MODE {
+            use std::os::unix::fs::PermissionsExt;
+            let mut permissions = fs::metadata(fpath)
+                .with_context(|| format!("Read permissions of '{}'", fpath.display()))?
+                .permissions();
+            permissions.set_mode(permissions.mode() | 0o111);
+            fs::set_permissions(fpath, permissions)
+                .with_context(|| format!("Make '{}' executable", fpath.display()))?;
+        }
 
         Ok(())
     }
+}
 
-    /// Returns true (wrapped as a result) if its hash equals that of the other file's.
-    ///
-    /// Note: the filepath must be either relative to the current working directory or absolute.
-    pub fn hash_same_as_other_file(&self, fpath: &path::Path) -> Result<bool> {
-        let other = Blob::new(fpath).context("Create blob of other filepath")?;
+/// Returns the hash of the contents of a blob object file, given as it is stored, compressed.
+pub(crate) fn hash_object(compressed: &[u8]) -> Result<String> {
+    let mut content = Vec::new();
+    ZlibDecoder::new(compressed)
+        .read_to_end(&mut content)
+        .context("Decompress blob object")?;
+    hash_lines(content.as_slice())
+}
 
-        Ok(self.hash == other.hash)
+/// Hashes the contents read from `reader` line by line, without line endings, which is how blobs
+/// are identified.
+fn hash_lines(reader: impl BufRead) -> Result<String> {
+    let mut hasher = Sha1::new();
+    for line in reader.lines() {
+        hasher.update(line?);
     }
+
+    Ok(hex::encode(hasher.finalize()))
 }
 
 #[cfg(test)]
@@ -140,6 +200,7 @@ mod tests {
 
             Ok(Blob {
                 hash: hash.to_string(),
+                mode: REGULAR_MODE,
             })
         }
     }
@@ -184,4 +245,136 @@ mod tests {
             Ok(())
         })
     }
+
+    #[test]
+    fn save_skips_existing_blob() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+
+        test_utils::set_dir(&tmpdir, || {
+            std::fs::create_dir_all(".gitlet/blobs")?;
+
+            std::fs::write("a.txt", "Same text.")?;
+            std::fs::write("b.txt", "Same text.")?;
+            let blob = Blob::new(Path::new("a.txt"))?;
+            assert!(!blob.exists());
+
+            blob.save(Path::new("a.txt"))?;
+            assert!(blob.exists());
+
+            let blobpath = blob.blobpath()?;
+            let modified = std::fs::metadata(&blobpath)?.modified()?;
+            std::thread::sleep(std::time::Duration::from_millis(10));
+
+            Blob::new(Path::new("b.txt"))?.save(Path::new("b.txt"))?;
+            assert_eq!(modified, std::fs::metadata(&blobpath)?.modified()?);
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn binary_blob_round_trip() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+
+        test_utils::set_dir(&tmpdir, || {
+            std::fs::create_dir_all(".gitlet/blobs")?;
+
+            // NUL and control bytes and mixed line endings, kept to valid UTF-8 since blobs are
+            // hashed line by line.
+            let content: Vec<u8> = (0u8..128).chain(*b"\r\n\0\r\r\n\n\0").collect();
+            std::fs::write("data.bin", &content)?;
+            let blob = Blob::new(Path::new("data.bin"))?;
+            blob.save(Path::new("data.bin"))?;
+
+            assert_eq!(content, blob.read()?);
+            blob.restore(Path::new("copy.bin"))?;
+            assert_eq!(content, std::fs::read("copy.bin")?);
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn binary_hash_ignores_line_endings() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+
+        test_utils::set_dir(&tmpdir, || {
+            // Hashing line by line drops the line endings, so the hash is not that of the raw
+            // bytes, and files differing only in their line endings share a blob.
+            std::fs::write("lf.bin", b"\0one\ntwo\n")?;
+            std::fs::write("crlf.bin", b"\0one\r\ntwo\r\n")?;
+            std::fs::write("none.bin", b"\0onetwo")?;
+            let hash = Blob::new(Path::new("lf.bin"))?.hash;
+            assert_ne!(hex::encode(Sha1::digest(b"\0one\ntwo\n")), hash);
+            assert_eq!(hash, Blob::new(Path::new("crlf.bin"))?.hash);
+            assert_eq!(hash, Blob::new(Path::new("none.bin"))?.hash);
+
+            // Nor can files that are not valid UTF-8 be hashed at all.
+            std::fs::write("invalid.bin", [0xff, 0xfe, 0x00])?;
+            assert!(Blob::new(Path::new("invalid.bin")).is_err());
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn corrupt_blob_leaves_file_untouched() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+
+        test_utils::set_dir(&tmpdir, || {
+            std::fs::create_dir_all(".gitlet/blobs")?;
+
+            std::fs::write("tmp.txt", "Test text.")?;
+            let blob = Blob::new(Path::new("tmp.txt"))?;
+            blob.save(Path::new("tmp.txt"))?;
+
+            std::fs::write("tmp.txt", "Modified text.")?;
+            blob.restore(Path::new("tmp.txt"))?;
+            assert_eq!("Test text.", std::fs::read_to_string("tmp.txt")?);
+
+            // Not zlib data at all.
+            std::fs::write("tmp.txt", "Modified text.")?;
+            std::fs::write(blob.blobpath()?, "garbage")?;
+            assert!(blob.restore(Path::new("tmp.txt")).is_err());
+            assert_eq!("Modified text.", std::fs::read_to_string("tmp.txt")?);
+
+            // Valid zlib data whose contents do not match the hash.
+            let mut e = ZlibEncoder::new(Vec::new(), Compression::default());
+            e.write_all(b"Other text.")?;
+            std::fs::write(blob.blobpath()?, e.finish()?)?;
+            let err = blob.restore(Path::new("tmp.txt")).unwrap_err();
+            assert!(err.to_string().contains("is corrupt"));
+            assert_eq!("Modified text.", std::fs::read_to_string("tmp.txt")?);
+
+            Ok(())
+        })
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restore_executable_mode() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmpdir = assert_fs::TempDir::new()?;
+
+        test_utils::set_dir(&tmpdir, || {
+            std::fs::create_dir_all(".gitlet/blobs")?;
+
+            std::fs::write("run.sh", "echo hi")?;
+            let regular = Blob::new(Path::new("run.sh"))?;
+            assert_eq!(REGULAR_MODE, regular.mode);
+
+            std::fs::set_permissions("run.sh", std::fs::Permissions::from_mode(0o755))?;
+            let executable = Blob::new(Path::new("run.sh"))?;
+            assert_eq!(EXECUTABLE_MODE, executable.mode);
+            assert_eq!(regular.hash, executable.hash);
+
+            executable.save(Path::new("run.sh"))?;
+            std::fs::remove_file("run.sh")?;
+            executable.restore(Path::new("run.sh"))?;
+            assert_ne!(0, std::fs::metadata("run.sh")?.permissions().mode() & 0o111);
+
+            Ok(())
+        })
+    }
 }
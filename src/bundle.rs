@@ -0,0 +1,380 @@
+//! Bundles package commits and their blobs, along with the refs that point to them, into a single
+//! file so that they may be exchanged between repositories without a network connection.
+//!
+//! A bundle file begins with a text header:
+//!
+//! ```text
+//! # gitlet bundle v1
+//! -<hash of a prerequisite commit, which the receiving repository must already have>
+//! <hash> <refname>
+//! [blank line]
+//! ```
+//!
+//! followed by the objects themselves, each as a line `commit <hash> <length>` or
+//! `blob <hash> <length>` and then `<length>` bytes of the object file exactly as it is stored in
+//! `.gitlet/commits/` or `.gitlet/blobs/`.
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::blob;
+use crate::commit::{self, Commit, get_commit_blobs};
+use crate::repo;
+use crate::transaction::Transaction;
+
+const SIGNATURE: &str = "# gitlet bundle v1";
+
+/// The parsed contents of a bundle file.
+struct Bundle {
+    prerequisites: Vec<String>,
+    refs: Vec<(String, String)>,
+    objects: Vec<(ObjectKind, String, Vec<u8>)>,
+}
+
+#[derive(PartialEq)]
+enum ObjectKind {
+    Commit,
+    Blob,
+}
+
+impl ObjectKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ObjectKind::Commit => "commit",
+            ObjectKind::Blob => "blob",
+        }
+    }
+
+    /// Returns the path to the object with the given hash, relative to the `.gitlet` directory.
+    fn path(&self, hash: &str) -> PathBuf {
+        let dir = match self {
+            ObjectKind::Commit => "commits",
+            ObjectKind::Blob => "blobs",
+        };
+        Path::new(dir).join(&hash[..2]).join(&hash[2..])
+    }
+}
+
+/// Writes a bundle file containing the commits named by `rev_list`.
+///
+/// Each revision is either a branch name (or `HEAD`), which bundles its entire history, or a range
+/// `<commit>..<branch>`, which bundles only the commits reachable from `<branch>` but not from
+/// `<commit>`. In the latter case, the receiving repository must already have `<commit>`.
+pub fn create(file: &Path, rev_list: &[String]) -> Result<()> {
+    anyhow::ensure!(!rev_list.is_empty(), "Refusing to create an empty bundle");
+
+    let mut included: HashSet<String> = HashSet::new();
+    let mut excluded: HashSet<String> = HashSet::new();
+    let mut refs: Vec<(String, String)> = Vec::new();
+
+    for rev in rev_list {
+        let (base, tip) = match rev.split_once("..") {
+            Some((base, tip)) => (Some(base), tip),
+            None => (None, rev.as_str()),
+        };
+
+        let refname = if tip == "HEAD" {
+            repo::get_head_branch().context("Get name of currently checked out branch")?
+        } else {
+            tip.to_string()
+        };
+        let tip_hash = repo::resolve_ref(&refname)?;
+        anyhow::ensure!(
+            repo::is_commit_hash(&tip_hash),
+            "Branch '{refname}' does not have any commits to bundle"
+        );
+
+        if let Some(base) = base {
+            excluded.extend(commit::ancestors(&repo::resolve_ref(base)?)?);
+        }
+        included.extend(commit::ancestors(&tip_hash)?);
+        refs.push((tip_hash, format!("refs/{refname}")));
+    }
+
+    let commits: Vec<Commit> = included
+        .difference(&excluded)
+        .map(|hash| Commit::load(hash))
+        .collect::<Result<_>>()
+        .context("Load commits to bundle")?;
+    anyhow::ensure!(!commits.is_empty(), "Refusing to create an empty bundle");
+
+    // The parents of the bundled commits that are not themselves bundled must already be present
+    // in the receiving repository.
+    let mut prerequisites: Vec<String> = commits
+        .iter()
+        .flat_map(|c| c.parents())
+        .filter(|p| excluded.contains(*p))
+        .map(String::from)
+        .collect();
+    prerequisites.sort();
+    prerequisites.dedup();
+
+    let prerequisite_blobs: HashSet<String> = prerequisites
+        .iter()
+        .map(|hash| get_commit_blobs(hash))
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .flat_map(|blobs| blobs.into_values().map(|b| b.hash))
+        .collect();
+
+    let mut blobs: Vec<String> = Vec::new();
+    for commit in commits.iter() {
+        for blob in get_commit_blobs(&commit.hash)?.into_values() {
+            if !prerequisite_blobs.contains(&blob.hash) {
+                blobs.push(blob.hash);
+            }
+        }
+    }
+    blobs.sort();
+    blobs.dedup();
+
+    let gitlet_dir = repo::abs_path_to_repo_root()?.join(".gitlet");
+    let mut out = std::io::BufWriter::new(
+        fs::File::create(file)
+            .with_context(|| format!("Create bundle file '{}'", file.display()))?,
+    );
+
+    writeln!(out, "{SIGNATURE}")?;
+    for hash in prerequisites.iter() {
+        writeln!(out, "-{hash}")?;
+    }
+    for (hash, refname) in refs.iter() {
+        writeln!(out, "{hash} {refname}")?;
+    }
+    writeln!(out)?;
+
+    let objects = commits
+        .iter()
+        .map(|c| (ObjectKind::Commit, &c.hash))
+        .chain(blobs.iter().map(|b| (ObjectKind::Blob, b)));
+    for (kind, hash) in objects {
+        let content = fs::read(gitlet_dir.join(kind.path(hash)))
+            .with_context(|| format!("Read {} {hash}", kind.as_str()))?;
+        writeln!(out, "{} {hash} {}", kind.as_str(), content.len())?;
+        out.write_all(&content)?;
+    }
+    out.flush().context("Write bundle file")?;
+
+    println!(
+        "Bundled {} commit(s) and {} blob(s) into '{}'",
+        commits.len(),
+        blobs.len(),
+        file.display()
+    );
+
+    Ok(())
+}
+
+/// Copies the objects in a bundle file into the repository and creates or fast-forwards the
+/// branches it lists. The checked out branch, if the HEAD is not detached, is never updated.
+pub fn unbundle(file: &Path) -> Result<()> {
+    let bundle = read(file)?;
+
+    let gitlet_dir = repo::abs_path_to_repo_root()?.join(".gitlet");
+    check_prerequisites(&bundle, &gitlet_dir)?;
+
+    // Write all of the objects or none of them, so that a failed unbundle leaves no commits behind
+    // whose blobs are missing.
+    let mut tx = Transaction::begin();
+    for (kind, hash, content) in bundle.objects.iter() {
+        let path = gitlet_dir.join(kind.path(hash));
+        if !path.exists() {
+            tx.stage_file_write(path, content.as_slice());
+        }
+    }
+    tx.commit().context("Write bundled objects")?;
+
+    let head_branch = repo::current_branch().context("Get name of currently checked out branch")?;
+    for (hash, refname) in bundle.refs.iter() {
+        let branch_name = &refname["refs/".len()..];
+        let ref_path = gitlet_dir.join(refname);
+        let current = fs::read_to_string(&ref_path).unwrap_or_default();
+        let current = current.trim();
+
+        if current == hash {
+            println!("{hash} {refname} (up to date)");
+        } else if head_branch.as_deref() == Some(branch_name) {
+            println!("{hash} {refname} (not updating checked out branch)");
+        } else if commit::is_ancestor_of(current, hash)? {
+            fs::create_dir_all(ref_path.parent().unwrap())
+                .with_context(|| format!("Create directory for ref '{refname}'"))?;
+            fs::write(&ref_path, hash).with_context(|| format!("Update ref '{refname}'"))?;
+            println!("{hash} {refname}");
+        } else {
+            println!("{hash} {refname} (not a fast-forward, skipped)");
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the repository has the commits a bundle file requires, without copying anything
+/// into it, and describes the refs and commits the bundle holds.
+pub fn verify(file: &Path) -> Result<()> {
+    let bundle = read(file)?;
+
+    let gitlet_dir = repo::abs_path_to_repo_root()?.join(".gitlet");
+    check_prerequisites(&bundle, &gitlet_dir)?;
+
+    println!("The bundle contains {} ref(s):", bundle.refs.len());
+    for (hash, refname) in bundle.refs.iter() {
+        println!("{hash} {refname}");
+    }
+
+    let mut timestamps = Vec::new();
+    for (_, hash, content) in bundle
+        .objects
+        .iter()
+        .filter(|(kind, ..)| *kind == ObjectKind::Commit)
+    {
+        let commit: Commit = serde_json::from_slice(content)
+            .with_context(|| format!("Read bundled commit {hash}"))?;
+        timestamps.push(commit.timestamp());
+    }
+    if let (Some(first), Some(last)) = (timestamps.iter().min(), timestamps.iter().max()) {
+        let date = |t: u64| {
+            chrono::DateTime::from_timestamp(t as i64, 0)
+                .map(|d| d.to_rfc2822())
+                .unwrap_or_default()
+        };
+        println!(
+            "The bundle holds {} commit(s), dated {} to {}",
+            timestamps.len(),
+            date(*first),
+            date(*last)
+        );
+    }
+
+    println!("'{}' is okay", file.display());
+
+    Ok(())
+}
+
+/// Fails if the repository lacks any of the commits that the bundle requires, listing them.
+fn check_prerequisites(bundle: &Bundle, gitlet_dir: &Path) -> Result<()> {
+    let missing: Vec<&String> = bundle
+        .prerequisites
+        .iter()
+        .filter(|hash| !gitlet_dir.join(ObjectKind::Commit.path(hash)).exists())
+        .collect();
+    if !missing.is_empty() {
+        for hash in missing {
+            eprintln!("Missing prerequisite commit {hash}");
+        }
+        anyhow::bail!("Repository lacks the commits this bundle requires");
+    }
+
+    Ok(())
+}
+
+/// Reads and parses a bundle file.
+fn read(file: &Path) -> Result<Bundle> {
+    let content =
+        fs::read(file).with_context(|| format!("Read bundle file '{}'", file.display()))?;
+
+    let header_end = content
+        .windows(2)
+        .position(|w| w == b"\n\n")
+        .context("Bundle header is not terminated")?;
+    let header = std::str::from_utf8(&content[..header_end]).context("Read bundle header")?;
+    let mut lines = header.lines();
+
+    anyhow::ensure!(
+        lines.next() == Some(SIGNATURE),
+        "'{}' is not a gitlet bundle",
+        file.display()
+    );
+
+    let mut bundle = Bundle {
+        prerequisites: Vec::new(),
+        refs: Vec::new(),
+        objects: Vec::new(),
+    };
+
+    for line in lines {
+        if let Some(hash) = line.strip_prefix('-') {
+            anyhow::ensure!(
+                repo::is_commit_hash(hash),
+                "Invalid bundle prerequisite hash '{hash}'"
+            );
+            bundle.prerequisites.push(hash.to_string());
+        } else {
+            let (hash, refname) = line
+                .split_once(' ')
+                .with_context(|| format!("Malformed bundle ref line: '{line}'"))?;
+            anyhow::ensure!(
+                repo::is_commit_hash(hash),
+                "Invalid bundle ref hash '{hash}'"
+            );
+            // Only branches are bundled, and their refs must stay within `.gitlet/refs/`.
+            refname
+                .strip_prefix("refs/")
+                .context("Bundle refs must start with 'refs/'")
+                .and_then(|branch| repo::check_ref_format(branch, true))
+                .with_context(|| format!("Invalid bundle ref name '{refname}'"))?;
+            bundle.refs.push((hash.to_string(), refname.to_string()));
+        }
+    }
+
+    let mut rest = &content[header_end + 2..];
+    while !rest.is_empty() {
+        let line_end = rest
+            .iter()
+            .position(|&b| b == b'\n')
+            .context("Truncated bundle object header")?;
+        let line = std::str::from_utf8(&rest[..line_end]).context("Read bundle object header")?;
+
+        let mut fields = line.split(' ');
+        let (Some(kind), Some(hash), Some(len), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            anyhow::bail!("Malformed bundle object header: '{line}'");
+        };
+        let kind = match kind {
+            "commit" => ObjectKind::Commit,
+            "blob" => ObjectKind::Blob,
+            _ => anyhow::bail!("Unknown bundle object type '{kind}'"),
+        };
+        anyhow::ensure!(
+            repo::is_commit_hash(hash),
+            "Invalid bundle object hash '{hash}'"
+        );
+        let len: usize = len.parse().context("Parse bundle object length")?;
+
+        rest = &rest[line_end + 1..];
+        anyhow::ensure!(rest.len() >= len, "Truncated bundle object {hash}");
+        let object = &rest[..len];
+        check_object(&kind, hash, object)?;
+        bundle
+            .objects
+            .push((kind, hash.to_string(), object.to_vec()));
+        rest = &rest[len..];
+    }
+
+    Ok(bundle)
+}
+
+/// Fails unless the contents of a bundled object hash to the hash it is bundled under.
+fn check_object(kind: &ObjectKind, hash: &str, content: &[u8]) -> Result<()> {
+    let actual = match kind {
+        ObjectKind::Commit => {
+            let commit: Commit = serde_json::from_slice(content)
+                .with_context(|| format!("Read bundled commit {hash}"))?;
+            anyhow::ensure!(commit.is_intact(), "Bundled commit {hash} is corrupt");
+            commit.hash
+        }
+        ObjectKind::Blob => {
+            blob::hash_object(content).with_context(|| format!("Read bundled blob {hash}"))?
+        }
+    };
+    anyhow::ensure!(
+        actual == hash,
+        "Bundled {} {hash} is corrupt: its contents do not match its hash",
+        kind.as_str()
+    );
+
+    Ok(())
+}
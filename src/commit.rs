@@ -2,7 +2,7 @@
 //! a mapping of filenames to blobs. In addition to this HashMap, a commit comprises a parent
 //! commit (or two, in the case of a merge commit), a message, a timestamp, and an id created by
 //! taking the sha1 hash of the message, timestamp, and parent commit(s).
-use std::collections::HashMap;
+use std::collections::{BinaryHeap, HashMap, HashSet};
 use std::fmt::Display;
 use std::io::Read;
 use std::path::Path;
@@ -14,7 +14,9 @@ use chrono::DateTime;
 use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1};
 
-use crate::blob::{self, Blob};
+use crate::blob::Blob;
+use crate::config::{Config, ConfigScope};
+use crate::transaction::Transaction;
 use crate::{index, repo};
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -22,19 +24,100 @@ pub(crate) struct Commit {
     pub(crate) hash: String,
     parent: String,
     merge_parent: String, // Empty string, "", when not a merge.
+    // Empty for commits made before authors were recorded.
+    #[serde(default)]
+    author_name: String,
+    #[serde(default)]
+    author_email: String,
     message: String,
     timestamp: u64,
     blobs: HashMap<PathBuf, Blob>,
 }
 
-impl Commit {
-    /// Creates a new commit object using the current state of the index.
-    pub fn new(
-        parent: String,
-        merge_parent: Option<String>,
-        message: String,
-        index: index::Index,
-    ) -> Result<Self> {
+/// Builds a new commit, which is the only way to create one outside of this module.
+///
+/// ```ignore
+/// let commit = CommitBuilder::new()
+///     .parent(head_hash)
+///     .message("Add tmp.txt")
+///     .index(index)
+///     .build()?;
+/// ```
+#[derive(Default)]
+pub(crate) struct CommitBuilder {
+    parent: String,
+    author: Option<(String, String)>,
+    message: String,
+    timestamp: Option<u64>,
+    index: index::Index,
+    allow_empty_message: bool,
+}
+
+impl CommitBuilder {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the hash of the parent commit. Leave it unset, or empty, for the first commit.
+    pub(crate) fn parent(mut self, hash: impl Into<String>) -> Self {
+        self.parent = hash.into();
+        self
+    }
+
+    /// Sets the name and email of the author. Defaults to those configured (see
+    /// [`Config::author`]).
+    pub(crate) fn author(mut self, name: impl Into<String>, email: impl Into<String>) -> Self {
+        self.author = Some((name.into(), email.into()));
+        self
+    }
+
+    pub(crate) fn message(mut self, message: impl Into<String>) -> Self {
+        self.message = message.into();
+        self
+    }
+
+    /// Sets the timestamp, in seconds since the UNIX epoch. Defaults to now.
+    pub(crate) fn timestamp(mut self, timestamp: Option<u64>) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Sets the staging area whose changes to the parent's files the commit records.
+    pub(crate) fn index(mut self, index: index::Index) -> Self {
+        self.index = index;
+        self
+    }
+
+    /// Accepts an empty or whitespace-only message, which is otherwise rejected.
+    pub(crate) fn allow_empty_message(mut self, allow: bool) -> Self {
+        self.allow_empty_message = allow;
+        self
+    }
+
+    /// Creates the commit object using the state of the index.
+    ///
+    /// # Panics
+    ///
+    /// Returns an error if the message is empty and that is not allowed, or if the parent is not a
+    /// full commit hash.
+    pub(crate) fn build(self) -> Result<Commit> {
+        let CommitBuilder {
+            parent,
+            author,
+            message,
+            timestamp,
+            index,
+            allow_empty_message,
+        } = self;
+
+        if message.trim().is_empty() && !allow_empty_message {
+            anyhow::bail!("Aborting commit due to empty commit message");
+        }
+        anyhow::ensure!(
+            parent.is_empty() || repo::is_commit_hash(&parent),
+            "Invalid parent commit hash: '{parent}'"
+        );
+
         // Check in case this is the first commit.
         let blobs = if !parent.is_empty() {
             get_commit_blobs(&parent)?
@@ -47,45 +130,36 @@ impl Commit {
             .filter(|(k, _)| !index.removals.contains(k))
             .collect();
 
-        // blobs.extend(index.additions);
-        for (fname, blob) in index.additions.into_iter() {
-            blobs
-                .entry(fname)
-                .and_modify(|b| {
-                    *b = blob::Blob {
-                        hash: blob.hash.clone(),
-                    }
-                })
-                .or_insert(blob::Blob {
-                    hash: blob.hash.clone(),
-                });
-        }
-
-        let timestamp = time::SystemTime::now()
-            .duration_since(time::UNIX_EPOCH)
-            .context("Create timestamp using UNIX_EPOCH")?
-            .as_secs();
+        blobs.extend(index.additions);
 
-        let merge_parent = merge_parent.unwrap_or_default();
+        let timestamp = match timestamp {
+            Some(timestamp) => timestamp,
+            None => now()?,
+        };
+        let (author_name, author_email) = match author {
+            Some(author) => author,
+            None => Config::load(ConfigScope::Merged)
+                .context("Load author from config")?
+                .author(),
+        };
 
-        let mut hasher = Sha1::new();
-        hasher.update(&parent);
-        hasher.update(&merge_parent);
-        hasher.update(&message);
-        hasher.update(timestamp.to_string());
-        let hash = hasher.finalize();
-        let hash = hex::encode(hash);
-
-        Ok(Commit {
-            hash,
+        let mut commit = Commit {
+            hash: String::new(),
             parent,
-            merge_parent,
+            merge_parent: String::new(),
+            author_name,
+            author_email,
             message,
             timestamp,
             blobs,
-        })
+        };
+        commit.hash = commit.compute_hash();
+
+        Ok(commit)
     }
+}
 
+impl Commit {
     /// Loads the commit object with the given identifying sha1 hash.
     pub(crate) fn load(hash: &str) -> Result<Self> {
         // For before first commit and the HEAD is empty.
@@ -95,6 +169,8 @@ impl Commit {
                 hash: String::default(),
                 parent: String::default(),
                 merge_parent: String::default(),
+                author_name: String::default(),
+                author_email: String::default(),
                 message: String::default(),
                 timestamp: 0,
                 blobs: HashMap::default(),
@@ -117,25 +193,223 @@ impl Commit {
         Ok(commit)
     }
 
-    /// Writes the commit object to the repository.
-    pub(crate) fn save(self) -> Result<()> {
+    /// Stages writing the commit object to the repository as part of the given transaction.
+    pub(crate) fn save(&self, tx: &mut Transaction) -> Result<()> {
         let commit_path = repo::abs_path_to_repo_root()?
             .join(".gitlet/commits")
             .join(&self.hash[..2])
             .join(&self.hash[2..]);
-        fs::create_dir(commit_path.parent().unwrap())
-            .context("create .gitlet/commits/##/ subdirectory")?;
 
-        let commitfile = fs::File::create(commit_path).context("Create commit file")?;
-        serde_json::to_writer(commitfile, &self).context("Save commit to .gitlet/commits")?;
+        let json = serde_json::to_vec(self).context("Serialize commit")?;
+        tx.stage_file_write(commit_path, json);
 
         Ok(())
     }
 
+    /// Returns the hash identifying the commit, which is the SHA-1 hash of its parents, author,
+    /// message, and timestamp.
+    fn compute_hash(&self) -> String {
+        let mut hasher = Sha1::new();
+        hasher.update(&self.parent);
+        hasher.update(&self.merge_parent);
+        hasher.update(&self.author_name);
+        hasher.update(&self.author_email);
+        hasher.update(&self.message);
+        hasher.update(self.timestamp.to_string());
+        hex::encode(hasher.finalize())
+    }
+
+    /// Returns true if the commit's hash is the one its contents hash to.
+    pub(crate) fn is_intact(&self) -> bool {
+        self.compute_hash() == self.hash
+    }
+
     /// Returns true if the commit tracks the given file.
     pub(crate) fn tracks(&self, filepath: &Path) -> bool {
         self.blobs.contains_key(filepath)
     }
+
+    /// Returns the whole commit message.
+    pub(crate) fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Returns the first line of the commit message without trailing whitespace, or `(empty)` if
+    /// the message is empty.
+    pub(crate) fn summary(&self) -> &str {
+        match self.message.lines().next().map(str::trim_end) {
+            Some(line) if !line.is_empty() => line,
+            _ => "(empty)",
+        }
+    }
+
+    /// Returns the body of the commit message, i.e. everything after the first blank line, or an
+    /// empty string if there is none.
+    pub(crate) fn body(&self) -> &str {
+        let mut offset = 0;
+        let mut lines = self.message.split_inclusive('\n');
+        for line in lines.by_ref() {
+            offset += line.len();
+            if line.trim().is_empty() {
+                return self.message[offset..].trim();
+            }
+        }
+
+        ""
+    }
+
+    /// Returns a hash identifying the snapshot of files recorded by the commit, which is shared by
+    /// all commits with the same files and contents. It is the SHA-1 hash of a line
+    /// `<blob hash> <path>` for each file, sorted by path.
+    pub(crate) fn tree_hash(&self) -> String {
+        let mut files: Vec<(&PathBuf, &Blob)> = self.blobs.iter().collect();
+        files.sort_by_key(|(path, _)| *path);
+
+        let mut hasher = Sha1::new();
+        for (path, blob) in files {
+            hasher.update(format!("{} {}\n", blob.hash, path.display()));
+        }
+        hex::encode(hasher.finalize())
+    }
+
+    /// Returns the hash abbreviated to its first 7 characters.
+    pub(crate) fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(7)]
+    }
+
+    /// Returns the author as `name <email>`, or an empty string for commits made before authors
+    /// were recorded.
+    pub(crate) fn author(&self) -> String {
+        if self.author_name.is_empty() && self.author_email.is_empty() {
+            return String::new();
+        }
+        format!("{} <{}>", self.author_name, self.author_email)
+    }
+
+    /// Returns the name and email of the author, which are empty for commits made before authors
+    /// were recorded.
+    pub(crate) fn author_identity(&self) -> (String, String) {
+        (self.author_name.clone(), self.author_email.clone())
+    }
+
+    /// Returns the commit's timestamp in seconds since the UNIX epoch.
+    pub(crate) fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    /// Returns the hashes of the commit's parent and, if it is a merge commit, its merge parent.
+    pub(crate) fn parents(&self) -> Vec<&str> {
+        [self.parent.as_str(), self.merge_parent.as_str()]
+            .into_iter()
+            .filter(|p| !p.is_empty())
+            .collect()
+    }
+
+    /// Returns the hashes of the commits that are ancestors of every one of `hashes`, in
+    /// [`topological_order`], so that their merge base comes first.
+    pub(crate) fn common_ancestors(hashes: &[&str]) -> Result<Vec<String>> {
+        let mut common: Option<HashSet<String>> = None;
+        for hash in hashes {
+            let reachable = ancestors(hash)?;
+            common = Some(match common {
+                Some(mut common) => {
+                    common.retain(|hash| reachable.contains(hash));
+                    common
+                }
+                None => reachable,
+            });
+        }
+
+        let common = common.unwrap_or_default();
+        let commits: HashMap<String, Commit> = common
+            .iter()
+            .map(|hash| Ok((hash.clone(), Commit::load(hash)?)))
+            .collect::<Result<_>>()?;
+
+        Ok(topological_order(&common, &commits))
+    }
+}
+
+/// Orders the commits in `hashes` such that none comes before any of its descendants, choosing the
+/// newest among those that may come next.
+pub(crate) fn topological_order(
+    hashes: &HashSet<String>,
+    commits: &HashMap<String, Commit>,
+) -> Vec<String> {
+    let mut children: HashMap<&str, usize> = hashes.iter().map(|h| (h.as_str(), 0)).collect();
+    for hash in hashes {
+        for parent in commits[hash].parents() {
+            if let Some(count) = children.get_mut(parent) {
+                *count += 1;
+            }
+        }
+    }
+
+    let mut ready: BinaryHeap<(u64, &str)> = children
+        .iter()
+        .filter(|(_, count)| **count == 0)
+        .map(|(hash, _)| (commits[*hash].timestamp(), *hash))
+        .collect();
+    let mut order = Vec::with_capacity(hashes.len());
+    while let Some((_, hash)) = ready.pop() {
+        order.push(hash.to_string());
+        for parent in commits[hash].parents() {
+            if let Some(count) = children.get_mut(parent) {
+                *count -= 1;
+                if *count == 0 {
+                    ready.push((commits[parent].timestamp(), parent));
+                }
+            }
+        }
+    }
+
+    order
+}
+
+/// Returns the hashes of all commits reachable from the given commit, including itself, following
+/// both parents of merge commits.
+pub(crate) fn ancestors(hash: &str) -> Result<HashSet<String>> {
+    let mut reachable = HashSet::new();
+    let mut to_visit = vec![hash.to_string()];
+
+    while let Some(hash) = to_visit.pop() {
+        if hash.is_empty() || reachable.contains(&hash) {
+            continue;
+        }
+
+        let commit = Commit::load(&hash).with_context(|| format!("Load commit {hash}"))?;
+        to_visit.extend(commit.parents().into_iter().map(String::from));
+        reachable.insert(hash);
+    }
+
+    Ok(reachable)
+}
+
+/// Returns true if the commit `ancestor` is reachable from the commit `descendant` by following
+/// parents, including when they are the same commit.
+///
+/// The empty hash, standing in for a branch without commits, is an ancestor of every commit.
+pub(crate) fn is_ancestor_of(ancestor: &str, descendant: &str) -> Result<bool> {
+    if ancestor.is_empty() {
+        return Ok(true);
+    }
+
+    let mut visited = HashSet::new();
+    let mut to_visit = vec![descendant.to_string()];
+
+    while let Some(hash) = to_visit.pop() {
+        if hash == ancestor {
+            return Ok(true);
+        }
+        if hash.is_empty() || !visited.insert(hash.clone()) {
+            continue;
+        }
+
+        let commit = Commit::load(&hash).with_context(|| format!("Load commit {hash}"))?;
+        to_visit.extend(commit.parents().into_iter().map(String::from));
+    }
+
+    Ok(false)
 }
 
 /// Returns a commit's HashMap of <filename, blob> entries.
@@ -149,6 +423,7 @@ pub(crate) fn get_commit_blobs(commit_id: &str) -> Result<HashMap<PathBuf, Blob>
 ///
 /// ===
 /// commit [sha1 hash]
+/// Author: [name <email>], unless it was not recorded
 /// Date: [timestamp]
 /// [commit message]
 /// [newline]
@@ -160,6 +435,12 @@ impl Display for Commit {
         buf.push_str("commit ");
         buf.push_str(&self.hash);
 
+        let author = self.author();
+        if !author.is_empty() {
+            buf.push_str("\nAuthor: ");
+            buf.push_str(&author);
+        }
+
         buf.push_str("\nDate: ");
         let date = DateTime::from_timestamp(self.timestamp as i64, 0).unwrap();
         buf.push_str(&date.to_rfc2822());
@@ -172,15 +453,183 @@ impl Display for Commit {
     }
 }
 
+/// Returns the current time as seconds since the UNIX epoch.
+pub(crate) fn now() -> Result<u64> {
+    Ok(time::SystemTime::now()
+        .duration_since(time::UNIX_EPOCH)
+        .context("Create timestamp using UNIX_EPOCH")?
+        .as_secs())
+}
+
+/// Parses a date given on the command line into seconds since the UNIX epoch.
+///
+/// The date is either in RFC 3339 format, e.g. `2024-05-01T12:00:00+02:00`, or relative to now,
+/// e.g. `3 days ago`, in seconds, minutes, hours, days, or weeks.
+pub(crate) fn parse_date(date: &str) -> Result<u64> {
+    if let Ok(date) = DateTime::parse_from_rfc3339(date) {
+        return u64::try_from(date.timestamp())
+            .with_context(|| format!("Date '{date}' is before the UNIX epoch"));
+    }
+
+    let invalid = || format!("invalid date: '{date}'");
+    let (amount, unit) = date
+        .trim()
+        .strip_suffix(" ago")
+        .and_then(|relative| relative.split_once(' '))
+        .with_context(invalid)?;
+    let amount: u64 = amount.parse().with_context(invalid)?;
+    let unit_seconds = match unit.trim().trim_end_matches('s') {
+        "second" => 1,
+        "minute" => 60,
+        "hour" => 60 * 60,
+        "day" => 24 * 60 * 60,
+        "week" => 7 * 24 * 60 * 60,
+        _ => anyhow::bail!(invalid()),
+    };
+
+    let seconds = amount.checked_mul(unit_seconds).with_context(invalid)?;
+    now()?
+        .checked_sub(seconds)
+        .with_context(|| format!("Date '{date}' is before the UNIX epoch"))
+}
+
+/// A format in which to print commits, as given to `log --pretty`.
+pub(crate) enum Pretty {
+    /// The hash and subject on one line.
+    Oneline,
+    /// The hash and subject.
+    Short,
+    /// The hash and whole message.
+    Full,
+    /// The hash, date, and whole message.
+    Fuller,
+    /// An mbox message, as sent by email.
+    Email,
+    /// A format string, as given to [`format_commit`].
+    Format(String),
+}
+
+impl std::str::FromStr for Pretty {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "oneline" => Pretty::Oneline,
+            "short" => Pretty::Short,
+            "full" => Pretty::Full,
+            "fuller" => Pretty::Fuller,
+            "email" => Pretty::Email,
+            _ => match s.strip_prefix("format:") {
+                Some(format) => Pretty::Format(format.to_string()),
+                None => anyhow::bail!("invalid pretty format: '{s}'"),
+            },
+        })
+    }
+}
+
+impl Pretty {
+    /// Formats the commit in this format. Formats spanning several lines end with a blank line to
+    /// separate them from the next commit.
+    pub(crate) fn format(&self, commit: &Commit) -> String {
+        let indented: String = commit
+            .message
+            .lines()
+            .map(|line| format!("    {line}\n"))
+            .collect();
+        // Commits made before authors were recorded have no author line.
+        let author = |label: &str| match commit.author() {
+            author if author.is_empty() => String::new(),
+            author => format!("{label}: {author}\n"),
+        };
+        let header = format!("commit {}\n{}", commit.hash, author("Author"));
+        match self {
+            Pretty::Oneline => format_commit(commit, "%H %s"),
+            Pretty::Short => format!("{header}\n    {}\n", commit.summary()),
+            Pretty::Full => format!("{header}\n{indented}"),
+            Pretty::Fuller => format!(
+                "{header}{}\n{indented}",
+                format_commit(commit, "Date: %ad%n")
+            ),
+            Pretty::Email => format!(
+                "{}{}{}",
+                format_commit(commit, "From %H Mon Sep 17 00:00:00 2001%n"),
+                author("From"),
+                format_commit(commit, "Date: %ad%nSubject: [PATCH] %s%n%n%b%n")
+            ),
+            Pretty::Format(format) => format_commit(commit, format),
+        }
+    }
+}
+
+/// Formats the commit according to a format string, in which the following placeholders are
+/// replaced:
+///
+/// - `%H`: the commit hash
+/// - `%h`: the abbreviated commit hash
+/// - `%T`: the tree hash, which identifies the snapshot of files (see [`Commit::tree_hash`])
+/// - `%an`: the author name
+/// - `%ae`: the author email
+/// - `%s`: the subject, i.e. the first line of the message
+/// - `%b`: the body of the message
+/// - `%ad`: the date
+/// - `%n`: a newline
+/// - `%%`: a literal `%`
+///
+/// Anything else is copied as is.
+pub(crate) fn format_commit(commit: &Commit, format: &str) -> String {
+    let mut out = String::new();
+    let mut chars = format.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('H') => out.push_str(&commit.hash),
+            Some('h') => out.push_str(commit.short_hash()),
+            Some('T') => out.push_str(&commit.tree_hash()),
+            Some('s') => out.push_str(commit.summary()),
+            Some('b') => out.push_str(commit.body()),
+            Some('a') if chars.peek() == Some(&'d') => {
+                chars.next();
+                let date = DateTime::from_timestamp(commit.timestamp as i64, 0).unwrap();
+                out.push_str(&date.to_rfc2822());
+            }
+            Some('a') if chars.peek() == Some(&'n') => {
+                chars.next();
+                out.push_str(&commit.author_name);
+            }
+            Some('a') if chars.peek() == Some(&'e') => {
+                chars.next();
+                out.push_str(&commit.author_email);
+            }
+            Some('n') => out.push('\n'),
+            Some('%') => out.push('%'),
+            Some(other) => {
+                out.push('%');
+                out.push(other);
+            }
+            None => out.push('%'),
+        }
+    }
+
+    out
+}
+
 /// Data type for iterating through the commit history for the gitlet log command.
 ///
 /// Each iteration returns the `current_hash` and advances it to whichever commit
 /// represented by `parent_hash` and `merge_hash` is more recent (i.e. later in time)
 /// to the current hash. If there is no `merge_hash`, then it advances to the parent.
+///
+/// With `first_parent` set, merge parents are never followed.
 pub(crate) struct CommitIter {
     current_hash: Option<String>,
     parent_hash: Option<String>,
     merge_hash: Option<String>,
+    first_parent: bool,
 }
 
 impl Commit {
@@ -191,15 +640,27 @@ impl Commit {
             current_hash: Some(self.hash.clone()),
             parent_hash,
             merge_hash,
+            first_parent: false,
         }
     }
 }
 
+impl CommitIter {
+    /// Follows only the first parent of each commit, i.e. the mainline through merges.
+    pub(crate) fn first_parent(mut self) -> Self {
+        self.first_parent = true;
+        self
+    }
+}
+
 impl Iterator for CommitIter {
     type Item = Commit;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.current_hash.as_ref()?;
+        if self.first_parent {
+            self.merge_hash = None;
+        }
 
         // NOTE: This does not accommodate when a merge_parent is itself a merge commit.
         let output_hash = match (&self.parent_hash, &self.merge_hash) {
@@ -220,8 +681,7 @@ impl Iterator for CommitIter {
                 (self.parent_hash, self.merge_hash) = get_parent_hashes(hash);
                 output_hash
             }
-            (Some(parent), Some(merge)) =>
-            {
+            (Some(parent), Some(merge)) => {
                 let output_hash = self.current_hash.clone();
 
                 if parent == merge {
@@ -259,6 +719,123 @@ impl Iterator for CommitIter {
     }
 }
 
+/// Data type for iterating through the commit history in topological order, such that no commit
+/// is yielded before all of its descendants. Unlike [`CommitIter`], the whole history is loaded up
+/// front.
+pub(crate) struct CommitTopoIter {
+    commits: HashMap<String, Commit>,
+    /// Number of children of each commit that have yet to be yielded.
+    pending_children: HashMap<String, usize>,
+    /// Commits whose children have all been yielded. Used as a stack so that a line of history is
+    /// followed to its end before switching to another.
+    ready: Vec<String>,
+}
+
+impl Commit {
+    pub(crate) fn topo_iter(&self) -> Result<CommitTopoIter> {
+        let mut commits = HashMap::new();
+        let mut pending_children: HashMap<String, usize> = HashMap::new();
+
+        for hash in ancestors(&self.hash)? {
+            let commit = Commit::load(&hash).with_context(|| format!("Load commit {hash}"))?;
+            for parent in commit.parents() {
+                *pending_children.entry(parent.to_string()).or_default() += 1;
+            }
+            commits.insert(hash, commit);
+        }
+
+        let ready = if self.hash.is_empty() {
+            Vec::new()
+        } else {
+            vec![self.hash.clone()]
+        };
+
+        Ok(CommitTopoIter {
+            commits,
+            pending_children,
+            ready,
+        })
+    }
+}
+
+impl Iterator for CommitTopoIter {
+    type Item = Commit;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let hash = self.ready.pop()?;
+        let commit = self.commits.remove(&hash)?;
+
+        // Push the merge parent first, so that the first parent's line is followed first.
+        for parent in commit.parents().into_iter().rev() {
+            let pending = self.pending_children.entry(parent.to_string()).or_default();
+            *pending = pending.saturating_sub(1);
+            if *pending == 0 {
+                self.ready.push(parent.to_string());
+            }
+        }
+
+        Some(commit)
+    }
+}
+
+/// Data type for iterating through the commits reachable from any of several tips, such as every
+/// branch, most recent first. Commits reachable from more than one tip are yielded only once.
+pub(crate) struct CommitDateIter {
+    /// The timestamps and hashes of the commits whose children have been yielded, or which are
+    /// tips, so that the most recent of them comes next.
+    queue: BinaryHeap<(u64, String)>,
+    /// Every commit that has been queued, so that none is queued twice.
+    seen: HashSet<String>,
+    first_parent: bool,
+}
+
+impl CommitDateIter {
+    pub(crate) fn new(tips: &[String]) -> Result<Self> {
+        let mut iter = CommitDateIter {
+            queue: BinaryHeap::new(),
+            seen: HashSet::new(),
+            first_parent: false,
+        };
+        for tip in tips.iter().filter(|tip| !tip.is_empty()) {
+            iter.enqueue(tip)?;
+        }
+
+        Ok(iter)
+    }
+
+    /// Follows only the first parent of each commit, i.e. the mainline through merges.
+    pub(crate) fn first_parent(mut self) -> Self {
+        self.first_parent = true;
+        self
+    }
+
+    fn enqueue(&mut self, hash: &str) -> Result<()> {
+        if self.seen.insert(hash.to_string()) {
+            let commit = Commit::load(hash).with_context(|| format!("Load commit {hash}"))?;
+            self.queue.push((commit.timestamp, commit.hash));
+        }
+
+        Ok(())
+    }
+}
+
+impl Iterator for CommitDateIter {
+    type Item = Commit;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (_, hash) = self.queue.pop()?;
+        let commit = Commit::load(&hash).ok()?;
+
+        let parents = commit.parents();
+        let followed = if self.first_parent { 1 } else { parents.len() };
+        for parent in parents.into_iter().take(followed) {
+            self.enqueue(parent).ok()?;
+        }
+
+        Some(commit)
+    }
+}
+
 fn get_parent_hashes(hash: &str) -> (Option<String>, Option<String>) {
     if hash.is_empty() {
         return (None, None);
@@ -305,6 +882,244 @@ mod tests {
 
     use super::*;
 
+    /// Writes a commit object with the given parents and timestamp directly to `.gitlet/commits/`.
+    fn write_commit(hash: &str, parent: &str, merge_parent: &str, timestamp: u64) -> Result<()> {
+        let dir = format!(".gitlet/commits/{}", &hash[..2]);
+        std::fs::create_dir_all(&dir).context("Create commit subdirectory")?;
+        let f =
+            std::fs::File::create(format!("{dir}/{}", &hash[2..])).context("Create commit file")?;
+
+        let json = serde_json::json!({
+            "hash": hash,
+            "parent": parent,
+            "merge_parent": merge_parent,
+            "message": hash,
+            "timestamp": timestamp,
+            "blobs": {}
+        });
+        serde_json::to_writer(f, &json).context("Write commit json")?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn topological_order_keeps_branches_together() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+        test_utils::set_dir(&tmpdir, || {
+            // a <- b1 <- b2 <- d (merge of b2 and c2)
+            //  \-- c1 <- c2 <-/
+            // with timestamps interleaving the two branches.
+            let [a, b1, c1, b2, c2, d] = ["a", "b", "c", "e", "f", "d"].map(|c| c.repeat(40));
+            write_commit(&a, "", "", 1)?;
+            write_commit(&b1, &a, "", 2)?;
+            write_commit(&c1, &a, "", 3)?;
+            write_commit(&b2, &b1, "", 4)?;
+            write_commit(&c2, &c1, "", 5)?;
+            write_commit(&d, &b2, &c2, 6)?;
+
+            let order: Vec<String> = Commit::load(&d)?.topo_iter()?.map(|c| c.hash).collect();
+            assert_eq!(order, vec![d, b2, b1, c2, c1, a]);
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn first_parent_skips_merged_branches() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+        test_utils::set_dir(&tmpdir, || {
+            // a <- b1 <- b2 <- d (merge of b2 and c2) <- e
+            //  \-- c1 <- c2 <-/
+            let [a, b1, c1, b2, c2, d, e] =
+                ["a", "b", "c", "e", "f", "d", "1"].map(|c| c.repeat(40));
+            write_commit(&a, "", "", 1)?;
+            write_commit(&b1, &a, "", 2)?;
+            write_commit(&c1, &a, "", 3)?;
+            write_commit(&b2, &b1, "", 4)?;
+            write_commit(&c2, &c1, "", 5)?;
+            write_commit(&d, &b2, &c2, 6)?;
+            write_commit(&e, &d, "", 7)?;
+
+            let all: Vec<String> = Commit::load(&e)?.iter().map(|c| c.hash).collect();
+            assert_eq!(7, all.len());
+
+            let mainline: Vec<String> = Commit::load(&e)?
+                .iter()
+                .first_parent()
+                .map(|c| c.hash)
+                .collect();
+            assert_eq!(mainline, vec![e, d, b2, b1, a]);
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn ancestry_of_merge_history() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+        test_utils::set_dir(&tmpdir, || {
+            // a <- b <- d (merge of b and c)
+            //  \-- c <-/
+            let a = "a".repeat(40);
+            let b = "b".repeat(40);
+            let c = "c".repeat(40);
+            let d = "d".repeat(40);
+            write_commit(&a, "", "", 1)?;
+            write_commit(&b, &a, "", 2)?;
+            write_commit(&c, &a, "", 3)?;
+            write_commit(&d, &b, &c, 4)?;
+
+            assert!(is_ancestor_of(&a, &d)?);
+            assert!(is_ancestor_of(&c, &d)?);
+            assert!(is_ancestor_of(&d, &d)?);
+            assert!(is_ancestor_of("", &a)?);
+            assert!(!is_ancestor_of(&b, &c)?);
+            assert!(!is_ancestor_of(&d, &b)?);
+
+            assert_eq!(vec![a.clone()], Commit::common_ancestors(&[&b, &c])?);
+            assert_eq!(
+                vec![c.clone(), a.clone()],
+                Commit::common_ancestors(&[&c, &d])?
+            );
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn builder_validates_commit() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+        test_utils::set_dir(&tmpdir, || {
+            std::fs::create_dir_all(".gitlet")?;
+
+            let err = CommitBuilder::new().message("  ").build().unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Aborting commit due to empty commit message"
+            );
+
+            let commit = CommitBuilder::new()
+                .message("")
+                .allow_empty_message(true)
+                .timestamp(Some(1))
+                .build()?;
+            assert_eq!(commit.timestamp(), 1);
+            assert!(commit.parents().is_empty());
+
+            let err = CommitBuilder::new()
+                .parent("abc")
+                .message("Bad parent")
+                .build()
+                .unwrap_err();
+            assert_eq!(err.to_string(), "Invalid parent commit hash: 'abc'");
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn parse_commit_dates() -> Result<()> {
+        assert_eq!(parse_date("2020-01-02T03:04:05Z")?, 1577934245);
+        assert_eq!(parse_date("2020-01-02T05:04:05+02:00")?, 1577934245);
+
+        let two_days_ago = parse_date("2 days ago")?;
+        assert!((now()? - 2 * 24 * 60 * 60).abs_diff(two_days_ago) <= 1);
+        assert!((now()? - 60).abs_diff(parse_date("1 minute ago")?) <= 1);
+
+        assert!(parse_date("yesterday").is_err());
+        assert!(parse_date("2 fortnights ago").is_err());
+        let overflow = parse_date("99999999999999999 weeks ago").unwrap_err();
+        assert!(overflow.to_string().starts_with("invalid date"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn message_summary_and_body() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+        test_utils::set_dir(&tmpdir, || {
+            std::fs::create_dir_all(".gitlet")?;
+
+            let commit = |message: &str| {
+                CommitBuilder::new()
+                    .message(message)
+                    .allow_empty_message(true)
+                    .build()
+            };
+
+            let c = commit("Subject  \n\nFirst paragraph.\n\nSecond paragraph.\n")?;
+            assert_eq!("Subject", c.summary());
+            assert_eq!("First paragraph.\n\nSecond paragraph.", c.body());
+            assert_eq!(
+                "Subject / First paragraph.",
+                format_commit(&c, "%s / %b").lines().next().unwrap()
+            );
+
+            let c = commit("Subject only")?;
+            assert_eq!("Subject only", c.summary());
+            assert_eq!("", c.body());
+
+            let c = commit("")?;
+            assert_eq!("(empty)", c.summary());
+            assert_eq!("", c.body());
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn pretty_formats() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+        test_utils::set_dir(&tmpdir, || {
+            std::fs::create_dir_all(".gitlet")?;
+
+            let c = CommitBuilder::new()
+                .author("Alice", "alice@example.com")
+                .message("Subject\n\nBody.")
+                .build()?;
+            let hash = &c.hash;
+
+            let pretty = |p: &str| -> Result<String> { Ok(p.parse::<Pretty>()?.format(&c)) };
+            assert_eq!(format!("{hash} Subject"), pretty("oneline")?);
+            assert_eq!(
+                format!("commit {hash}\nAuthor: Alice <alice@example.com>\n\n    Subject\n"),
+                pretty("short")?
+            );
+            assert_eq!(
+                format!(
+                    "commit {hash}\nAuthor: Alice <alice@example.com>\n\n    Subject\n    \n    Body.\n"
+                ),
+                pretty("full")?
+            );
+            assert!(pretty("fuller")?.contains("<alice@example.com>\nDate: "));
+            assert!(pretty("email")?.contains("\nFrom: Alice <alice@example.com>\n"));
+            assert!(pretty("email")?.contains("\nSubject: [PATCH] Subject\n\nBody.\n"));
+            assert_eq!(
+                format!("{} Alice alice@example.com Subject", &hash[..7]),
+                pretty("format:%h %an %ae %s")?
+            );
+            assert!("medium".parse::<Pretty>().is_err());
+
+            // Commits made before authors were recorded have no author line.
+            let c = CommitBuilder::new()
+                .author("", "")
+                .message("Subject")
+                .build()?;
+            let hash = &c.hash;
+            assert_eq!(
+                format!("commit {hash}\n\n    Subject\n"),
+                Pretty::Short.format(&c)
+            );
+
+            // The tree hash depends only on the files, not on the rest of the commit.
+            let other = CommitBuilder::new().message("Other").build()?;
+            assert_ne!(c.hash, other.hash);
+            assert_eq!(c.tree_hash(), format_commit(&other, "%T"));
+
+            Ok(())
+        })
+    }
+
     #[test]
     fn display_commit() -> Result<()> {
         let tmpdir = assert_fs::TempDir::new()?;
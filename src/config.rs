@@ -0,0 +1,191 @@
+//! Reads and writes gitlet's settings, such as the name and email recorded as the author of new
+//! commits.
+//!
+//! Settings are JSON objects mapping keys, such as `user.name`, to values. They are read from the
+//! repository's `.gitlet/config`, which takes precedence, and from the user's `~/.gitletconfig`.
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+use crate::repo;
+
+/// The settings that may be set, with the values each accepts, if they are restricted.
+const KEYS: [(&str, Option<&[&str]>); 4] = [
+    ("commit.template", None),
+    ("core.autocrlf", Some(&["true", "false", "input"])),
+    ("user.email", None),
+    ("user.name", None),
+];
+
+/// Which settings file to read.
+#[derive(Clone, Copy)]
+pub(crate) enum ConfigScope {
+    /// The repository's `.gitlet/config`.
+    Local,
+    /// The user's `~/.gitletconfig`.
+    Global,
+    /// The global settings, overridden by the repository's where both are set.
+    Merged,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(transparent)]
+pub(crate) struct Config {
+    values: BTreeMap<String, String>,
+}
+
+impl Config {
+    /// Loads the settings in the given scope. Outside of a repository, the merged settings are
+    /// only the global ones.
+    ///
+    /// # Panics
+    ///
+    /// Returns an error if the local settings are requested outside of a repository.
+    pub(crate) fn load(scope: ConfigScope) -> Result<Self> {
+        match scope {
+            ConfigScope::Local | ConfigScope::Global => Self::read(&config_path(scope)?),
+            ConfigScope::Merged => {
+                let mut config = match global_config_path() {
+                    Some(path) => Self::read(&path)?,
+                    None => Self::default(),
+                };
+                if let Ok(local_path) = config_path(ConfigScope::Local) {
+                    config.values.extend(Self::read(&local_path)?.values);
+                }
+
+                Ok(config)
+            }
+        }
+    }
+
+    /// Reads the settings from the file at `path`. There are none if it does not exist.
+    fn read(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Read config file '{}'", path.display()))?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Parse config file '{}'", path.display()))
+    }
+
+    /// Writes the settings to the file at `path`.
+    fn write(&self, path: &Path) -> Result<()> {
+        let content = serde_json::to_string_pretty(self).context("Serialize config")?;
+        fs::write(path, content + "\n")
+            .with_context(|| format!("Write config file '{}'", path.display()))
+    }
+
+    /// Returns the value of the given key, if it is set.
+    pub(crate) fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Returns the keys that are set and their values, sorted by key.
+    pub(crate) fn entries(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.values.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Returns the name and email of the author of new commits, which are given by the
+    /// `GITLET_AUTHOR_NAME` and `GITLET_AUTHOR_EMAIL` environment variables, or else by the
+    /// `user.name` and `user.email` settings. Either is empty if it is not set.
+    pub(crate) fn author(&self) -> (String, String) {
+        let setting = |var: &str, key: &str| {
+            std::env::var(var)
+                .ok()
+                .or_else(|| self.get(key).map(str::to_string))
+                .unwrap_or_default()
+        };
+        (
+            setting("GITLET_AUTHOR_NAME", "user.name"),
+            setting("GITLET_AUTHOR_EMAIL", "user.email"),
+        )
+    }
+}
+
+/// Prints the value of `key`, returning false, for which the command exits with status 1, if it is
+/// not set. Unless `global` is set, the repository's setting takes precedence over the user's
+/// global one.
+pub fn get(key: &str, global: bool) -> Result<bool> {
+    let scope = if global {
+        ConfigScope::Global
+    } else {
+        ConfigScope::Merged
+    };
+    match Config::load(scope)?.get(key) {
+        Some(value) => println!("{value}"),
+        None => return Ok(false),
+    }
+
+    Ok(true)
+}
+
+/// Sets `key` to `value` in the repository's config file, or in the user's global one if `global`
+/// is set.
+///
+/// # Panics
+///
+/// Returns an error if `key` is not a known setting or `value` is not one it accepts.
+pub fn set(key: &str, value: &str, global: bool) -> Result<()> {
+    validate(key, value)?;
+
+    let scope = if global {
+        ConfigScope::Global
+    } else {
+        ConfigScope::Local
+    };
+    let mut config = Config::load(scope)?;
+    config.values.insert(key.to_string(), value.to_string());
+    config.write(&config_path(scope)?)
+}
+
+/// Checks that `key` is a known setting and that `value` is valid for it.
+fn validate(key: &str, value: &str) -> Result<()> {
+    let Some((_, values)) = KEYS.iter().find(|(known, _)| *known == key) else {
+        let keys: Vec<_> = KEYS.iter().map(|(known, _)| *known).collect();
+        anyhow::bail!(
+            "Unknown config key '{key}'. Known keys: {}",
+            keys.join(", ")
+        );
+    };
+    if let Some(values) = values {
+        anyhow::ensure!(
+            values.contains(&value),
+            "Invalid value '{value}' for {key}. Expected one of: {}",
+            values.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+/// Returns the path to the settings file of the local or global scope.
+fn config_path(scope: ConfigScope) -> Result<PathBuf> {
+    match scope {
+        ConfigScope::Local => Ok(repo::abs_path_to_repo_root()?.join(".gitlet/config")),
+        ConfigScope::Global => global_config_path().ok_or_else(|| anyhow!("HOME is not set")),
+        ConfigScope::Merged => anyhow::bail!("The merged settings are not stored in one file"),
+    }
+}
+
+/// Returns the path to the user's global config file, `~/.gitletconfig`.
+fn global_config_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| Path::new(&home).join(".gitletconfig"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validate_keys() {
+        assert!(validate("user.name", "Ada Lovelace").is_ok());
+        assert!(validate("core.autocrlf", "input").is_ok());
+        assert!(validate("core.autocrlf", "yes").is_err());
+        assert!(validate("user.nmae", "Ada").is_err());
+    }
+}
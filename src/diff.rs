@@ -0,0 +1,311 @@
+//! Line-by-line differences between versions of files, printed in the unified diff format with
+//! `---`/`+++` file headers and `@@` hunks.
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::{blob::Blob, commit::get_commit_blobs, index::Index, merge, repo};
+
+/// The number of unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+/// The contents and file mode of one version of a file.
+struct Version {
+    content: Vec<u8>,
+    mode: u32,
+}
+
+impl Version {
+    /// Reads the version of `fpath` stored in `blob`.
+    fn from_blob(fpath: &Path, blob: &Blob) -> Result<Self> {
+        Ok(Version {
+            content: blob
+                .read()
+                .with_context(|| format!("Read blob of '{}'", fpath.display()))?,
+            mode: blob.mode,
+        })
+    }
+}
+
+/// Prints the changes to tracked files in the working tree that have not been staged, or, if
+/// `cached` is set, the changes staged for the next commit.
+pub fn diff(cached: bool) -> Result<()> {
+    let diff = if cached {
+        staged_diff()?
+    } else {
+        unstaged_diff()?
+    };
+    print!("{diff}");
+
+    Ok(())
+}
+
+/// Returns the diff from the HEAD commit's files to those staged in the index.
+pub(crate) fn staged_diff() -> Result<String> {
+    let head_blobs = get_commit_blobs(&repo::read_head_hash()?)
+        .context("Get HEAD commit's list of tracked files")?;
+    let index = Index::load().context("Load index")?;
+
+    diff_trees(&head_blobs, &index.staged_blobs(&head_blobs))
+}
+
+/// Returns the diff from the files staged in the index, or committed if unchanged there, to those
+/// in the working tree. New files that have not been staged are left out.
+fn unstaged_diff() -> Result<String> {
+    let head_blobs = get_commit_blobs(&repo::read_head_hash()?)
+        .context("Get HEAD commit's list of tracked files")?;
+    let index = Index::load().context("Load index")?;
+    let repo_root = repo::abs_path_to_repo_root()?;
+
+    let mut out = String::new();
+    for (f, blob) in sorted(&index.staged_blobs(&head_blobs)) {
+        let fpath = repo_root.join(f);
+        let new = if !fpath.exists() {
+            None
+        } else if index.hash_file(f, &fpath)? == blob.hash {
+            continue;
+        } else {
+            Some(Version {
+                content: fs::read(&fpath).with_context(|| format!("Read '{}'", fpath.display()))?,
+                mode: Blob::new(&fpath)?.mode,
+            })
+        };
+        out.push_str(&file_diff(f, Some(Version::from_blob(f, blob)?), new));
+    }
+
+    Ok(out)
+}
+
+/// Returns the diff between two sets of files, such as those tracked by two commits, in order of
+/// their paths.
+pub(crate) fn diff_trees(
+    old: &HashMap<PathBuf, Blob>,
+    new: &HashMap<PathBuf, Blob>,
+) -> Result<String> {
+    let mut paths: Vec<&PathBuf> = old.keys().chain(new.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut out = String::new();
+    for f in paths {
+        let (old_blob, new_blob) = (old.get(f), new.get(f));
+        if let (Some(o), Some(n)) = (old_blob, new_blob)
+            && o.hash == n.hash
+            && o.mode == n.mode
+        {
+            continue;
+        }
+        let read = |blob: Option<&Blob>| blob.map(|b| Version::from_blob(f, b)).transpose();
+        out.push_str(&file_diff(f, read(old_blob)?, read(new_blob)?));
+    }
+
+    Ok(out)
+}
+
+fn sorted(blobs: &HashMap<PathBuf, Blob>) -> Vec<(&PathBuf, &Blob)> {
+    let mut blobs: Vec<(&PathBuf, &Blob)> = blobs.iter().collect();
+    blobs.sort_by_key(|(f, _)| *f);
+    blobs
+}
+
+/// Returns the diff of a single file, including its header. A missing version means that the file
+/// was added or deleted. Files that are not valid UTF-8 are only reported as differing.
+fn file_diff(fpath: &Path, old: Option<Version>, new: Option<Version>) -> String {
+    let path = fpath.display();
+    let mut out = format!("diff --gitlet a/{path} b/{path}\n");
+    match (&old, &new) {
+        (None, Some(new)) => out.push_str(&format!("new file mode {:o}\n", new.mode)),
+        (Some(old), None) => out.push_str(&format!("deleted file mode {:o}\n", old.mode)),
+        (Some(old), Some(new)) if old.mode != new.mode => out.push_str(&format!(
+            "old mode {:o}\nnew mode {:o}\n",
+            old.mode, new.mode
+        )),
+        _ => {}
+    }
+
+    let old_name = match old {
+        Some(_) => format!("a/{path}"),
+        None => "/dev/null".to_string(),
+    };
+    let new_name = match new {
+        Some(_) => format!("b/{path}"),
+        None => "/dev/null".to_string(),
+    };
+    let text = |version: Option<Version>| match version {
+        Some(v) => String::from_utf8(v.content).ok(),
+        None => Some(String::new()),
+    };
+    let (Some(old_text), Some(new_text)) = (text(old), text(new)) else {
+        out.push_str(&format!("Binary files {old_name} and {new_name} differ\n"));
+        return out;
+    };
+
+    // Only the mode changed.
+    if old_text == new_text {
+        return out;
+    }
+
+    out.push_str(&format!("--- {old_name}\n+++ {new_name}\n"));
+    out.push_str(&hunks(&old_text, &new_text));
+    out
+}
+
+/// A line of the edit script turning one version into another, holding indices into the old and
+/// new lines.
+enum Edit {
+    Keep(usize),
+    Delete(usize),
+    Insert(usize),
+}
+
+/// Returns the hunks turning `old` into `new`, each with up to [`CONTEXT`] unchanged lines around
+/// its changes.
+fn hunks(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.split_inclusive('\n').collect();
+    let new: Vec<&str> = new.split_inclusive('\n').collect();
+
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let common = merge::longest_common_subsequence(&old, &new);
+    for (o, n) in common.into_iter().chain([(old.len(), new.len())]) {
+        edits.extend((i..o).map(Edit::Delete));
+        edits.extend((j..n).map(Edit::Insert));
+        if o < old.len() {
+            edits.push(Edit::Keep(o));
+        }
+        (i, j) = (o + 1, n + 1);
+    }
+
+    // Group the changes into hunks, merging those whose contexts would touch or overlap.
+    let changes: Vec<usize> = (0..edits.len())
+        .filter(|&e| !matches!(edits[e], Edit::Keep(_)))
+        .collect();
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &e in &changes {
+        let start = e.saturating_sub(CONTEXT);
+        let end = (e + CONTEXT + 1).min(edits.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    let mut out = String::new();
+    for (start, end) in ranges {
+        // The numbers of old and new lines before the hunk.
+        let old_before = edits[..start]
+            .iter()
+            .filter(|e| !matches!(e, Edit::Insert(_)))
+            .count();
+        let new_before = edits[..start]
+            .iter()
+            .filter(|e| !matches!(e, Edit::Delete(_)))
+            .count();
+
+        let mut body = String::new();
+        let (mut old_count, mut new_count) = (0, 0);
+        for edit in &edits[start..end] {
+            let (prefix, line) = match *edit {
+                Edit::Keep(o) => {
+                    old_count += 1;
+                    new_count += 1;
+                    (' ', old[o])
+                }
+                Edit::Delete(o) => {
+                    old_count += 1;
+                    ('-', old[o])
+                }
+                Edit::Insert(n) => {
+                    new_count += 1;
+                    ('+', new[n])
+                }
+            };
+            body.push(prefix);
+            body.push_str(line);
+            if !line.ends_with('\n') {
+                body.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n{body}",
+            hunk_range(old_before, old_count),
+            hunk_range(new_before, new_count)
+        ));
+    }
+
+    out
+}
+
+/// Formats the range of lines covered by one side of a hunk, which starts after the first
+/// `before` lines. A single line is given without its count, and an empty range by the line
+/// before it.
+fn hunk_range(before: usize, count: usize) -> String {
+    match count {
+        0 => format!("{before},0"),
+        1 => format!("{}", before + 1),
+        _ => format!("{},{count}", before + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unified_hunks() {
+        let old: String = (1..=10).map(|i| format!("{i}\n")).collect();
+        let new = old.replace("2\n", "two\n").replace("10\n", "10\n11\n");
+
+        assert_eq!(
+            "@@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n 4\n 5\n@@ -8,3 +8,4 @@\n 8\n 9\n 10\n+11\n",
+            hunks(&old, &new)
+        );
+
+        // Nearby changes share a hunk.
+        let new = old.replace("2\n", "two\n").replace("8\n", "");
+        assert_eq!(
+            "@@ -1,10 +1,9 @@\n 1\n-2\n+two\n 3\n 4\n 5\n 6\n 7\n-8\n 9\n 10\n",
+            hunks(&old, &new)
+        );
+
+        assert_eq!("@@ -0,0 +1,2 @@\n+a\n+b\n", hunks("", "a\nb\n"));
+        assert_eq!(
+            "@@ -1 +1 @@\n-a\n+a\n\\ No newline at end of file\n",
+            hunks("a\n", "a")
+        );
+    }
+
+    #[test]
+    fn file_headers() {
+        let version = |content: &str, mode| {
+            Some(Version {
+                content: content.as_bytes().to_vec(),
+                mode,
+            })
+        };
+        let path = Path::new("dir/a.txt");
+
+        assert_eq!(
+            "diff --gitlet a/dir/a.txt b/dir/a.txt\nnew file mode 100644\n--- /dev/null\n+++ b/dir/a.txt\n@@ -0,0 +1 @@\n+a\n",
+            file_diff(path, None, version("a\n", 0o100644))
+        );
+        assert_eq!(
+            "diff --gitlet a/dir/a.txt b/dir/a.txt\nold mode 100644\nnew mode 100755\n",
+            file_diff(path, version("a\n", 0o100644), version("a\n", 0o100755))
+        );
+        assert_eq!(
+            "diff --gitlet a/dir/a.txt b/dir/a.txt\nBinary files a/dir/a.txt and b/dir/a.txt differ\n",
+            file_diff(
+                path,
+                version("a\n", 0o100644),
+                Some(Version {
+                    content: vec![0xff],
+                    mode: 0o100644
+                })
+            )
+        );
+    }
+}
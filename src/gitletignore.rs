@@ -0,0 +1,167 @@
+//! Parses the `.gitletignore` file at the root of the working tree, which lists patterns of
+//! untracked files that gitlet should neither report nor stage.
+//!
+//! The syntax follows `.gitignore`: one glob pattern per line, with blank lines and lines starting
+//! with `#` ignored. A pattern containing a `/` other than a trailing one is matched against the
+//! whole path from the repository root, and otherwise against the name of each file or directory at
+//! any depth. A leading `/` anchors a pattern to the root, a trailing `/` matches only directories,
+//! `**` matches any number of directories, and a leading `!` re-includes paths that an earlier
+//! pattern ignored. Everything inside an ignored directory is ignored.
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use glob::{MatchOptions, Pattern};
+
+use crate::repo;
+
+/// Wildcards do not match across directories, so that `*.txt` and `dir/*` stay within one level.
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// A single pattern from a `.gitletignore` file.
+struct Rule {
+    pattern: Pattern,
+    /// Matched against the whole path rather than against its last component.
+    anchored: bool,
+    dir_only: bool,
+    negated: bool,
+}
+
+/// The ignore rules of a repository, in the order in which they appear, so that later rules take
+/// precedence over earlier ones.
+#[derive(Default)]
+pub(crate) struct Patterns {
+    rules: Vec<Rule>,
+}
+
+impl Patterns {
+    /// Reads the rules from the `.gitletignore` file at the repository root. There are none if the
+    /// file does not exist.
+    pub(crate) fn load() -> Result<Self> {
+        let ignore_file = repo::abs_path_to_repo_root()?.join(".gitletignore");
+        if !ignore_file.exists() {
+            return Ok(Self::default());
+        }
+
+        let text = fs::read_to_string(&ignore_file).context("Read .gitletignore")?;
+        Self::parse(&text)
+    }
+
+    /// Parses the rules from the contents of a `.gitletignore` file.
+    ///
+    /// # Panics
+    ///
+    /// Returns an error if a line is not a valid glob pattern.
+    pub(crate) fn parse(text: &str) -> Result<Self> {
+        let mut rules = Vec::new();
+        for line in text.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (negated, line) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            let (dir_only, line) = match line.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, line),
+            };
+            let anchored = line.contains('/');
+            let line = line.strip_prefix('/').unwrap_or(line);
+
+            rules.push(Rule {
+                pattern: Pattern::new(line)
+                    .with_context(|| format!("Invalid pattern in .gitletignore: '{line}'"))?,
+                anchored,
+                dir_only,
+                negated,
+            });
+        }
+
+        Ok(Patterns { rules })
+    }
+
+    /// Returns true if the file at `path`, relative to the repository root, is ignored, either by
+    /// itself or because a directory containing it is.
+    pub(crate) fn is_ignored(&self, path: &Path) -> bool {
+        path.ancestors()
+            .skip(1)
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .any(|dir| self.matches(dir, true))
+            || self.matches(path, false)
+    }
+
+    /// Returns true if the last rule matching `path` ignores it.
+    pub(crate) fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        let Some(path_str) = path.to_str() else {
+            return false;
+        };
+        let name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(path_str);
+
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| {
+                (is_dir || !rule.dir_only)
+                    && rule
+                        .pattern
+                        .matches_with(if rule.anchored { path_str } else { name }, MATCH_OPTIONS)
+            })
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ignore_rules() -> Result<()> {
+        let patterns = Patterns::parse(
+            "# Build artifacts\n\
+             target/\n\
+             *.log\n\
+             !keep.log\n\
+             \n\
+             /todo.txt\n\
+             docs/*.html\n\
+             **/cache/**\n",
+        )?;
+        let ignored = |path: &str| patterns.is_ignored(Path::new(path));
+
+        // Unanchored patterns match at any depth, and directories take their contents with them.
+        assert!(ignored("target/debug/gitlet"));
+        assert!(ignored("sub/target/out.o"));
+        assert!(ignored("error.log"));
+        assert!(ignored("sub/error.log"));
+        assert!(!ignored("keep.log"));
+        assert!(!ignored("# Build artifacts"));
+
+        // A trailing slash only matches directories.
+        assert!(!ignored("sub/target"));
+
+        // Anchored patterns only match from the root, and wildcards stay within a directory.
+        assert!(ignored("todo.txt"));
+        assert!(!ignored("sub/todo.txt"));
+        assert!(ignored("docs/index.html"));
+        assert!(!ignored("docs/api/index.html"));
+        assert!(!ignored("sub/docs/index.html"));
+
+        assert!(ignored("a/b/cache/c.bin"));
+        assert!(ignored("cache/c.bin"));
+        assert!(!ignored("src/main.rs"));
+
+        assert!(Patterns::parse("[").is_err());
+
+        Ok(())
+    }
+}
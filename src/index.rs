@@ -6,9 +6,12 @@ use std::{
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
 
 use crate::{
     blob::Blob,
+    commit::get_commit_blobs,
+    gitletignore::Patterns,
     repo::{self, abs_path_to_repo_root},
 };
 
@@ -16,6 +19,43 @@ use crate::{
 pub(crate) struct Index {
     pub(crate) additions: HashMap<path::PathBuf, Blob>,
     pub(crate) removals: HashSet<path::PathBuf>,
+    #[serde(default)]
+    pub(crate) stat_cache: HashMap<path::PathBuf, StatCache>,
+}
+
+/// Metadata of a working tree file recorded alongside the hash of its contents at the time, so
+/// that the file need not be rehashed until its metadata changes.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub(crate) struct StatCache {
+    mtime: i64, // Nanoseconds since the UNIX epoch
+    size: u64,
+    hash: String,
+}
+
+impl StatCache {
+    /// Records the metadata of the file at `fpath`, whose contents hash to `hash`.
+    pub(crate) fn new(fpath: &path::Path, hash: &str) -> Result<Self> {
+        let (mtime, size) = stat(fpath)?;
+        Ok(StatCache {
+            mtime,
+            size,
+            hash: hash.to_string(),
+        })
+    }
+}
+
+/// Returns the modification time, in nanoseconds since the UNIX epoch, and size of a file.
+fn stat(fpath: &path::Path) -> Result<(i64, u64)> {
+    let metadata =
+        std::fs::metadata(fpath).with_context(|| format!("Stat file '{}'", fpath.display()))?;
+    let mtime = metadata
+        .modified()
+        .context("Read file modification time")?
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos() as i64)
+        .unwrap_or_default();
+
+    Ok((mtime, metadata.len()))
 }
 
 pub enum IndexAction {
@@ -46,7 +86,7 @@ impl Index {
     }
 
     /// Saves the staging area to .gitlet/index
-    fn save(&self) -> Result<()> {
+    pub(crate) fn save(&self) -> Result<()> {
         let index_file = repo::abs_path_to_repo_root()?.join(".gitlet/index");
         let f = std::fs::File::create(index_file)
             .with_context(|| "Create .gitlet/index file")
@@ -71,12 +111,95 @@ impl Index {
         let blob = Blob::new(&filepath).with_context(|| "Creating blob for addition to index")?;
         blob.save(&filepath)?;
 
+        self.stat_cache.insert(
+            fpath_from_root.clone(),
+            StatCache::new(&filepath, &blob.hash)?,
+        );
         self.removals.remove(&fpath_from_root);
         self.additions.insert(fpath_from_root, blob);
 
         self.save()
     }
 
+    /// Returns the hash of the contents of the working tree file at `fpath`, whose path from the
+    /// repository root is `fpath_from_root`. Files whose size and modification time match those in
+    /// the stat cache are not rehashed.
+    pub(crate) fn hash_file(
+        &self,
+        fpath_from_root: &path::Path,
+        fpath: &path::Path,
+    ) -> Result<String> {
+        if let Some(cached) = self.stat_cache.get(fpath_from_root)
+            && stat(fpath)? == (cached.mtime, cached.size)
+        {
+            return Ok(cached.hash.clone());
+        }
+
+        Ok(Blob::new(fpath)?.hash)
+    }
+
+    /// Moves the changes staged for the given paths, relative to the repository root, out of this
+    /// staging area and into a new one, which is returned.
+    ///
+    /// # Panics
+    ///
+    /// Returns an error if any of the paths has no staged changes.
+    pub(crate) fn split_off(&mut self, paths: &[path::PathBuf]) -> Result<Index> {
+        let mut split = Index::default();
+
+        for fpath in paths {
+            if let Some(blob) = self.additions.remove(fpath) {
+                split.additions.insert(fpath.clone(), blob);
+            } else if self.removals.remove(fpath) {
+                split.removals.insert(fpath.clone());
+            } else {
+                anyhow::bail!("'{}' has no staged changes", fpath.display());
+            }
+        }
+
+        Ok(split)
+    }
+
+    /// Returns the files as they would be committed: those of `head_blobs`, the files tracked by
+    /// the HEAD commit, with the staged changes applied.
+    pub(crate) fn staged_blobs(
+        &self,
+        head_blobs: &HashMap<path::PathBuf, Blob>,
+    ) -> HashMap<path::PathBuf, Blob> {
+        head_blobs
+            .iter()
+            .filter(|(f, _)| !self.removals.contains(*f))
+            .chain(&self.additions)
+            .map(|(f, blob)| (f.clone(), blob.clone()))
+            .collect()
+    }
+
+    /// Returns the staging area whose changes turn the files of `base`, such as those of a commit
+    /// about to become the HEAD, into `blobs`.
+    pub(crate) fn from_blobs(
+        blobs: HashMap<path::PathBuf, Blob>,
+        base: &HashMap<path::PathBuf, Blob>,
+    ) -> Index {
+        let removals = base
+            .keys()
+            .filter(|f| !blobs.contains_key(*f))
+            .cloned()
+            .collect();
+        let additions = blobs
+            .into_iter()
+            .filter(|(f, blob)| {
+                base.get(f)
+                    .is_none_or(|b| b.hash != blob.hash || b.mode != blob.mode)
+            })
+            .collect();
+
+        Index {
+            additions,
+            removals,
+            ..Default::default()
+        }
+    }
+
     /// Returns true if the staging area is clear.
     pub(crate) fn is_clear(&self) -> bool {
         self.additions.is_empty() && self.removals.is_empty()
@@ -102,14 +225,102 @@ impl std::fmt::Display for Index {
     }
 }
 /// Clears the index file without needing the Index
+///
+/// The stat cache survives, since it records file contents rather than what is staged.
 pub(crate) fn clear_index() -> Result<()> {
     let index_file = repo::abs_path_to_repo_root()?.join(".gitlet/index");
     if index_file.exists() {
+        let stat_cache = Index::load().map(|i| i.stat_cache).unwrap_or_default();
         std::fs::remove_file(index_file).context("Delete .gitlet/index")?;
+
+        if !stat_cache.is_empty() {
+            Index {
+                stat_cache,
+                ..Default::default()
+            }
+            .save()
+            .context("Save stat cache to a clear index")?;
+        }
     }
     Ok(())
 }
 
+/// Updates the stat cache for every tracked file whose metadata has changed but whose contents
+/// still match the staged or committed version, without staging anything. This spares `status`
+/// from rehashing files that were merely touched.
+pub fn refresh() -> Result<()> {
+    let mut index = Index::load()?;
+    let repo_root = abs_path_to_repo_root()?;
+
+    let mut tracked: HashMap<path::PathBuf, String> = get_commit_blobs(&repo::read_head_hash()?)
+        .context("Get HEAD commit's list of tracked files")?
+        .into_iter()
+        .map(|(f, blob)| (f, blob.hash))
+        .collect();
+    // Staged versions take precedence over committed ones.
+    for (f, blob) in index.additions.iter() {
+        tracked.insert(f.clone(), blob.hash.clone());
+    }
+
+    for (f, hash) in tracked {
+        let fpath = repo_root.join(&f);
+        if fpath.exists() && index.hash_file(&f, &fpath)? == hash {
+            let cache = StatCache::new(&fpath, &hash)?;
+            index.stat_cache.insert(f, cache);
+        }
+    }
+
+    index.save().context("Save refreshed stat cache")
+}
+
+/// Resets the staging area and the working tree to match the HEAD commit, discarding all staged and
+/// unstaged changes to tracked files. Untracked files, including those that were only staged for
+/// addition, are left alone.
+///
+/// Since the index only records changes relative to the HEAD, resetting it amounts to clearing it.
+pub(crate) fn reset_to_head() -> Result<()> {
+    clear_index().context("Clear the staging area")?;
+    repo::restore_head_files().context("Restore files tracked by the HEAD commit")
+}
+
+/// Returns the files staged for addition whose staged contents contain conflict markers, sorted.
+pub(crate) fn check_for_conflicts(index: &Index) -> Result<Vec<path::PathBuf>> {
+    let mut conflicted = Vec::new();
+    for (fpath, blob) in index.additions.iter() {
+        let content = blob
+            .read()
+            .with_context(|| format!("Read staged contents of '{}'", fpath.display()))?;
+        if find_conflict_markers(&String::from_utf8_lossy(&content)) {
+            conflicted.push(fpath.clone());
+        }
+    }
+    conflicted.sort();
+
+    Ok(conflicted)
+}
+
+/// Returns true if the text contains a block of conflict markers as left by a merge: a line
+/// starting with `<<<<<<< `, then a line `=======`, then a line starting with `>>>>>>> `.
+///
+/// Lone markers, such as a `=======` underlining a heading, do not count.
+pub(crate) fn find_conflict_markers(text: &str) -> bool {
+    let mut expected = ["<<<<<<< ", "=======", ">>>>>>> "].into_iter().peekable();
+    for line in text.lines() {
+        let Some(&marker) = expected.peek() else {
+            break;
+        };
+        let matches = match marker {
+            "=======" => line == marker,
+            _ => line.starts_with(marker),
+        };
+        if matches {
+            expected.next();
+        }
+    }
+
+    expected.peek().is_none()
+}
+
 /// Displays the files staged for addition and for removal.
 pub fn status(mut writer: impl std::io::Write) -> Result<()> {
     let index = Index::load()?;
@@ -117,10 +328,149 @@ pub fn status(mut writer: impl std::io::Write) -> Result<()> {
     Ok(())
 }
 
-/// Dispatches gitlet command either to stage or unstage a file.
-pub fn action(action: IndexAction, filepath: &str) -> Result<()> {
+/// Dispatches gitlet command either to stage or unstage files.
+///
+/// Every file is processed even if an earlier one fails, in which case the errors are combined
+/// into one. When `verbose` is true, each file is printed as it is handled, and each failure as it
+/// occurs. When staging, `set_mode` overrides the file mode recorded for the staged files, without
+/// changing the files in the working tree.
+///
+/// Staging a directory stages every changed file within it (see [`changed_files_in_dir`]), after
+/// which the number of files staged from directories is printed.
+pub fn action(
+    action: IndexAction,
+    filepaths: &[String],
+    verbose: bool,
+    set_mode: Option<u32>,
+) -> Result<()> {
     let mut index = Index::load()?;
+    let ignored = Patterns::load().context("Load .gitletignore")?;
+    let mut errors = Vec::new();
+    // The number of files staged from directories, if any were given.
+    let mut staged_from_dirs: Option<usize> = None;
+
+    for filepath in filepaths {
+        let dir = path::Path::new(filepath);
+        let files = match action {
+            IndexAction::Add if dir.is_dir() => {
+                staged_from_dirs.get_or_insert(0);
+                match changed_files_in_dir(&index, dir, &ignored) {
+                    Ok(files) => files,
+                    Err(e) => {
+                        if verbose {
+                            eprintln!("error: {filepath}: {e:#}");
+                        }
+                        errors.push(format!("{filepath}: {e:#}"));
+                        continue;
+                    }
+                }
+            }
+            _ => vec![filepath.clone()],
+        };
+
+        for file in &files {
+            match action_on_file(&mut index, &action, file, set_mode, &ignored) {
+                Ok(()) => {
+                    if let Some(count) = staged_from_dirs.as_mut()
+                        && file != filepath
+                    {
+                        *count += 1;
+                    }
+                    if verbose {
+                        match action {
+                            IndexAction::Add => println!("add '{file}'"),
+                            IndexAction::Unstage => println!("unstage '{file}'"),
+                        }
+                    }
+                }
+                Err(e) => {
+                    if verbose {
+                        eprintln!("error: {file}: {e:#}");
+                    }
+                    errors.push(format!("{file}: {e:#}"));
+                }
+            }
+        }
+    }
+
+    index
+        .save()
+        .with_context(|| "Saving the staging area to the index file")?;
 
+    if let Some(count) = staged_from_dirs {
+        println!("Staged {count} file(s)");
+    }
+
+    if !errors.is_empty() {
+        anyhow::bail!("{}", errors.join("\n"));
+    }
+
+    Ok(())
+}
+
+/// Returns the files under `dir` that differ from their staged or committed versions, or are
+/// untracked, in the order of their paths. Hidden and ignored files are left out, as are the
+/// contents of hidden and ignored directories.
+fn changed_files_in_dir(
+    index: &Index,
+    dir: &path::Path,
+    ignored: &Patterns,
+) -> Result<Vec<String>> {
+    let dir_from_root = repo::find_working_tree_dir(dir)
+        .context("Convert directory path to be relative to working tree root")?;
+    let from_root = |fpath: &path::Path| dir_from_root.join(fpath.strip_prefix(dir).unwrap());
+    let head_blobs = get_commit_blobs(&repo::read_head_hash()?)
+        .context("Get HEAD commit's list of tracked files")?;
+
+    let mut files = Vec::new();
+    let entries = WalkDir::new(dir)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            e.depth() == 0
+                || !(e.file_name().to_string_lossy().starts_with('.')
+                    || e.file_type().is_dir() && ignored.matches(&from_root(e.path()), true))
+        });
+    for entry in entries {
+        let entry = entry.with_context(|| format!("Walk directory '{}'", dir.display()))?;
+        let fpath_from_root = from_root(entry.path());
+        if !entry.file_type().is_file() || ignored.is_ignored(&fpath_from_root) {
+            continue;
+        }
+
+        let current = index.additions.get(&fpath_from_root).or_else(|| {
+            head_blobs
+                .get(&fpath_from_root)
+                .filter(|_| !index.removals.contains(&fpath_from_root))
+        });
+        let unchanged = current.is_some_and(|blob| {
+            index
+                .hash_file(&fpath_from_root, entry.path())
+                .is_ok_and(|hash| hash == blob.hash)
+        });
+        if !unchanged {
+            let fpath = entry.path();
+            files.push(
+                fpath
+                    .strip_prefix(".")
+                    .unwrap_or(fpath)
+                    .to_string_lossy()
+                    .into_owned(),
+            );
+        }
+    }
+
+    Ok(files)
+}
+
+/// Stages or unstages a single file. Untracked files matching `ignored` are refused.
+fn action_on_file(
+    index: &mut Index,
+    action: &IndexAction,
+    filepath: &str,
+    set_mode: Option<u32>,
+    ignored: &Patterns,
+) -> Result<()> {
     let f = path::PathBuf::from(filepath);
     anyhow::ensure!(f.exists(), "Cannot stage file. File does not exist.");
 
@@ -128,17 +478,27 @@ pub fn action(action: IndexAction, filepath: &str) -> Result<()> {
         .with_context(|| "Convert filepath to be relative to working tree root")?;
 
     match action {
-        IndexAction::Add => index.stage(f, fpath_from_root).context("Stage file")?,
+        IndexAction::Add => {
+            anyhow::ensure!(
+                !ignored.is_ignored(&fpath_from_root)
+                    || index.additions.contains_key(&fpath_from_root)
+                    || repo::is_tracked_by_head(&fpath_from_root),
+                "The path is ignored by .gitletignore"
+            );
+            index
+                .stage(f, fpath_from_root.clone())
+                .context("Stage file")?;
+            if let (Some(mode), Some(blob)) = (set_mode, index.additions.get_mut(&fpath_from_root))
+            {
+                blob.mode = mode;
+            }
+        }
         IndexAction::Unstage => {
             index.additions.remove(&fpath_from_root);
             index.removals.remove(&fpath_from_root);
         }
     }
 
-    index
-        .save()
-        .with_context(|| "Saving the staging area to the index file")?;
-
     Ok(())
 }
 
@@ -239,6 +599,104 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn stage_with_mode() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+
+        test_utils::set_dir(&tmpdir, || {
+            std::fs::create_dir_all(".gitlet/blobs")?;
+            std::fs::write("run.sh", "echo hi")?;
+            let files = ["run.sh".to_string()];
+
+            action(IndexAction::Add, &files, false, None)?;
+            let regular = Index::load()?
+                .additions
+                .remove(path::Path::new("run.sh"))
+                .unwrap();
+            assert_eq!(crate::blob::REGULAR_MODE, regular.mode);
+
+            action(
+                IndexAction::Add,
+                &files,
+                false,
+                Some(crate::blob::EXECUTABLE_MODE),
+            )?;
+            let executable = Index::load()?
+                .additions
+                .remove(path::Path::new("run.sh"))
+                .unwrap();
+            assert_eq!(crate::blob::EXECUTABLE_MODE, executable.mode);
+            assert_eq!(regular.hash, executable.hash);
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn split_off_staged_paths() -> Result<()> {
+        let mut index = Index::default();
+        for name in ["a.txt", "b.txt"] {
+            index.additions.insert(
+                path::PathBuf::from(name),
+                Blob {
+                    hash: name.repeat(8),
+                    mode: crate::blob::REGULAR_MODE,
+                },
+            );
+        }
+        index.removals.insert(path::PathBuf::from("c.txt"));
+
+        let split = index.split_off(&["a.txt".into(), "c.txt".into()])?;
+        assert!(split.additions.contains_key(path::Path::new("a.txt")));
+        assert!(split.removals.contains(path::Path::new("c.txt")));
+        assert_eq!(1, index.additions.len());
+        assert!(index.removals.is_empty());
+
+        assert!(index.split_off(&["a.txt".into()]).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn reset_staging_area_to_head() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+
+        test_utils::set_dir(&tmpdir, || {
+            repo::init(None, None)?;
+            std::fs::write("tracked.txt", "committed")?;
+            action(IndexAction::Add, &["tracked.txt".to_string()], false, None)?;
+            repo::commit(repo::CommitOptions {
+                message: Some("Add tracked.txt".to_string()),
+                ..Default::default()
+            })?;
+
+            std::fs::write("tracked.txt", "modified")?;
+            std::fs::write("new.txt", "new")?;
+            action(IndexAction::Add, &["new.txt".to_string()], false, None)?;
+            assert!(!Index::load()?.is_clear());
+
+            reset_to_head()?;
+
+            assert!(Index::load()?.is_clear());
+            assert_eq!("committed", std::fs::read_to_string("tracked.txt")?);
+            assert_eq!("new", std::fs::read_to_string("new.txt")?);
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn detect_conflict_markers() {
+        assert!(find_conflict_markers(
+            "a\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> feature\nb\n"
+        ));
+        assert!(!find_conflict_markers("Title\n=======\n\nText\n"));
+        assert!(!find_conflict_markers(">>>>>>> x\n=======\n<<<<<<< y\n"));
+        assert!(!find_conflict_markers(
+            "<<<<<<< HEAD\nours\n=======\ntheirs\n"
+        ));
+    }
+
     #[test]
     fn load_empty_staging_area() -> Result<()> {
         let tmpdir = assert_fs::TempDir::new()?;
@@ -290,8 +748,24 @@ mod tests {
             f.write_all(b"Test text.")?;
             let tmp = path::PathBuf::from("tmp.txt");
 
-            assert!(action(IndexAction::Add, tmp.to_str().unwrap()).is_ok());
-            assert!(action(IndexAction::Unstage, tmp.to_str().unwrap()).is_ok());
+            assert!(
+                action(
+                    IndexAction::Add,
+                    &[tmp.to_str().unwrap().to_string()],
+                    false,
+                    None
+                )
+                .is_ok()
+            );
+            assert!(
+                action(
+                    IndexAction::Unstage,
+                    &[tmp.to_str().unwrap().to_string()],
+                    false,
+                    None
+                )
+                .is_ok()
+            );
 
             let index = Index::load()?;
             assert!(index.additions.is_empty());
@@ -300,6 +774,83 @@ mod tests {
         })
     }
 
+    #[test]
+    fn stat_cache_skips_rehashing() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+
+        test_utils::set_dir(&tmpdir, || {
+            std::fs::create_dir_all(".gitlet/blobs")?;
+
+            std::fs::write("tmp.txt", "Test text.")?;
+            action(IndexAction::Add, &["tmp.txt".to_string()], false, None)?;
+
+            let tmp = path::Path::new("tmp.txt");
+            let mut index = Index::load()?;
+            let staged_hash = index.additions.get(tmp).unwrap().hash.clone();
+            assert_eq!(staged_hash, index.hash_file(tmp, tmp)?);
+
+            // While the file's metadata is unchanged, its contents are not rehashed.
+            index.stat_cache.get_mut(tmp).unwrap().hash = String::from("cached");
+            assert_eq!("cached", index.hash_file(tmp, tmp)?);
+
+            std::fs::write("tmp.txt", "Changed text.")?;
+            assert_ne!("cached", index.hash_file(tmp, tmp)?);
+
+            // Clearing the staging area keeps the stat cache.
+            clear_index()?;
+            let index = Index::load()?;
+            assert!(index.is_clear());
+            assert_eq!(
+                staged_hash,
+                index.stat_cache.get(tmp).unwrap().hash.as_str()
+            );
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn refresh_stale_stat_cache() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+
+        test_utils::set_dir(&tmpdir, || {
+            repo::init(None, None)?;
+            std::fs::write("same.txt", "Same text.")?;
+            std::fs::write("changed.txt", "Old text.")?;
+            let files = ["same.txt".to_string(), "changed.txt".to_string()];
+            action(IndexAction::Add, &files, false, None)?;
+            repo::commit(repo::CommitOptions {
+                message: Some("Add files".to_string()),
+                ..Default::default()
+            })?;
+
+            let stale = Index::load()?.stat_cache;
+            let later = std::time::SystemTime::now() + std::time::Duration::from_secs(60);
+            for f in ["same.txt", "changed.txt"] {
+                std::fs::File::options()
+                    .append(true)
+                    .open(f)?
+                    .set_modified(later)?;
+            }
+            std::fs::write("changed.txt", "New text.")?;
+
+            refresh()?;
+
+            let index = Index::load()?;
+            assert!(index.is_clear());
+            let same = path::Path::new("same.txt");
+            assert_ne!(stale.get(same), index.stat_cache.get(same));
+            assert_eq!(
+                StatCache::new(same, &stale[same].hash)?,
+                index.stat_cache[same]
+            );
+            let changed = path::Path::new("changed.txt");
+            assert_eq!(stale.get(changed), index.stat_cache.get(changed));
+
+            Ok(())
+        })
+    }
+
     #[test]
     fn test_rm_staged() -> Result<()> {
         let tmpdir = assert_fs::TempDir::new()?;
@@ -311,7 +862,15 @@ mod tests {
             f.write_all(b"Test text.")?;
             let tmp = path::PathBuf::from("tmp.txt");
 
-            assert!(action(IndexAction::Add, tmp.to_str().unwrap()).is_ok());
+            assert!(
+                action(
+                    IndexAction::Add,
+                    &[tmp.to_str().unwrap().to_string()],
+                    false,
+                    None
+                )
+                .is_ok()
+            );
 
             assert!(rm(true, tmp.to_str().unwrap()).is_ok());
 
@@ -1,5 +1,16 @@
+pub mod bisect;
+pub mod blame;
 pub mod blob;
+pub mod bundle;
 pub mod commit;
+pub mod config;
+pub mod diff;
+mod gitletignore;
 pub mod index;
+pub mod merge;
 pub mod repo;
+pub mod stash;
+pub mod tag;
 pub mod test_utils;
+pub mod trailers;
+pub mod transaction;
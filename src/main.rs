@@ -1,16 +1,26 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
 use gitlet_rs::{
+    bisect, blame, blob, bundle, config, diff,
     index::{self, IndexAction},
-    repo,
+    merge, repo, stash, tag, trailers,
 };
 
 #[derive(Debug, Parser)]
 #[command(name = "gitlet")]
 #[command(about = "A simple version control CLI", long_about = None)]
 struct Cli {
+    /// Print the gitlet version.
+    #[arg(long)]
+    version: bool,
+    /// Print the path to the gitlet binary.
+    #[arg(long)]
+    exec_path: bool,
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
 }
 
 #[derive(Debug, Subcommand)]
@@ -19,14 +29,69 @@ enum Commands {
     Init {
         /// Optional path to specify. Default to PWD.
         repo_dir: Option<String>,
+        /// Copy the files in this directory into the new .gitlet directory. Defaults to
+        /// ~/.gitlet/templates/default/, if it exists.
+        #[arg(long, value_name = "DIR")]
+        template: Option<PathBuf>,
     },
 
-    /// Stage a file for commit
-    Add { filepath: String },
+    /// Stage files for commit
+    Add {
+        /// Print each file as it is staged.
+        #[arg(short, long)]
+        verbose: bool,
+        /// Record the files as executable (+x) or not (-x), leaving the working tree untouched.
+        #[arg(long, value_parser = ["+x", "-x"], allow_hyphen_values = true)]
+        chmod: Option<String>,
+        /// Stage nothing, but update the cached metadata of tracked files whose contents are
+        /// unchanged, e.g. after they were touched.
+        #[arg(long, conflicts_with_all = ["filepath", "chmod"])]
+        refresh: bool,
+        #[arg(required_unless_present = "refresh")]
+        filepath: Vec<String>,
+    },
 
     /// Unstage a file that is staged for commit
     Unstage { filepath: String },
 
+    /// Point the current branch at another commit, resetting the staging area by default
+    #[command(group = clap::ArgGroup::new("mode").args(["soft", "mixed", "hard"]))]
+    Reset {
+        /// Leave the staging area and working tree alone, so that the changes are staged.
+        #[arg(long)]
+        soft: bool,
+        /// Reset the staging area but not the working tree (default).
+        #[arg(long)]
+        mixed: bool,
+        /// Reset the staging area and the working tree, discarding all changes to tracked files.
+        #[arg(long)]
+        hard: bool,
+        /// Discard unstaged modifications with --hard.
+        #[arg(short, long, requires = "hard")]
+        force: bool,
+        /// The commit to reset to (default: HEAD).
+        commit: Option<String>,
+    },
+
+    /// Create a commit that undoes the changes made by another
+    Revert {
+        /// Stage the changes without committing them.
+        #[arg(short, long)]
+        no_commit: bool,
+        commit: String,
+    },
+
+    /// Discard the unstaged changes to a file
+    Restore {
+        /// Restore the file from this commit instead of from the staging area or HEAD.
+        #[arg(long, value_name = "COMMIT", conflicts_with = "staged")]
+        source: Option<String>,
+        /// Unstage the file instead, leaving the working tree alone.
+        #[arg(short = 'S', long)]
+        staged: bool,
+        filepath: String,
+    },
+
     /// Stage a file for removal
     Rm {
         #[arg(long)]
@@ -35,48 +100,686 @@ enum Commands {
     },
 
     /// Display the status of the gitlet repository
-    Status,
+    Status {
+        /// Also show the diff of the changes staged for commit.
+        #[arg(short, long)]
+        verbose: bool,
+    },
+
+    /// Show changes to tracked files that have not been staged
+    Diff {
+        /// Show the changes staged for commit instead.
+        #[arg(long, visible_alias = "staged")]
+        cached: bool,
+    },
 
     /// Commits the staged changes to the gitlet repository
-    Commit { message: String },
+    Commit {
+        /// The commit message. May be repeated, with each becoming a paragraph of the message.
+        /// When omitted, it is read from stdin, or written in $EDITOR if stdin is a terminal.
+        #[arg(short, long, value_name = "MSG")]
+        message: Vec<String>,
+        /// Create a commit that fixes up the given commit, with its message prefixed by "fixup! ".
+        #[arg(long, value_name = "COMMIT", conflicts_with = "message")]
+        fixup: Option<String>,
+        /// Create a commit to be squashed into the given commit, with its message prefixed by
+        /// "squash! " and followed by the given message, if any.
+        #[arg(long, value_name = "COMMIT", conflicts_with = "fixup")]
+        squash: Option<String>,
+        /// Pre-populate the commit message editor with the contents of the given file, in place of
+        /// the commit.template setting.
+        #[arg(short, long, value_name = "FILE", conflicts_with_all = ["message", "fixup", "squash"])]
+        template: Option<PathBuf>,
+        /// Use the given date, e.g. "2024-05-01T12:00:00Z" or "3 days ago", instead of now.
+        #[arg(long)]
+        date: Option<String>,
+        /// Create the commit even if nothing is staged.
+        #[arg(long)]
+        allow_empty: bool,
+        /// Accept an empty commit message.
+        #[arg(long)]
+        allow_empty_message: bool,
+        /// Commit even if staged files contain conflict markers.
+        #[arg(short, long)]
+        force: bool,
+        /// Commit only the staged changes to this file, leaving the rest staged. May be repeated.
+        #[arg(long, value_name = "FILE")]
+        only: Vec<PathBuf>,
+        /// Add a Signed-off-by trailer for the author, as given by GITLET_AUTHOR_NAME and
+        /// GITLET_AUTHOR_EMAIL or user.name and user.email, to the end of the message.
+        #[arg(short, long)]
+        signoff: bool,
+    },
 
     /// Prints a log of the commit history starting from the HEAD.
-    Log,
+    Log {
+        /// Skip the first N commits, counting from the HEAD.
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        skip: usize,
+        /// Show at most N commits. `-N` is shorthand for this.
+        #[arg(short = 'n', long, value_name = "N")]
+        max_count: Option<usize>,
+        /// Print each commit according to a format string, e.g. "%h %s".
+        #[arg(long)]
+        format: Option<String>,
+        /// Print each commit in a named format (oneline, short, full, fuller, email) or
+        /// "format:<string>".
+        #[arg(long, value_name = "FORMAT", conflicts_with = "format")]
+        pretty: Option<String>,
+        /// Print each commit on one line, as its abbreviated hash and subject.
+        #[arg(long, conflicts_with_all = ["format", "pretty"])]
+        oneline: bool,
+        /// Show no commit before all of its children, keeping each branch's commits together.
+        #[arg(long)]
+        topo_order: bool,
+        /// Follow only the first parent of merge commits.
+        #[arg(long)]
+        first_parent: bool,
+        /// Show the history of every branch, tag, and remote tracking branch, not only the HEAD's.
+        #[arg(long, conflicts_with = "topo_order")]
+        all: bool,
+        /// Show only commits whose message matches the regular expression. When given more than
+        /// once, commits matching any of them are shown.
+        #[arg(long, value_name = "PATTERN")]
+        grep: Vec<String>,
+        /// Show only commits whose message matches every --grep pattern.
+        #[arg(long, requires = "grep")]
+        all_match: bool,
+        /// Match --grep patterns regardless of case.
+        #[arg(short = 'i', long, requires = "grep")]
+        ignore_case: bool,
+    },
+
+    /// Prints a commit and the changes it made to its parent's files.
+    Show {
+        /// The commit to show. Defaults to the HEAD.
+        commit: Option<String>,
+    },
+
+    /// Shows the commit that last changed each line of a file.
+    Blame { filepath: String },
+
+    /// Applies the changes made by an existing commit and commits them with its message.
+    CherryPick {
+        /// The commit whose changes to apply.
+        #[arg(required_unless_present_any = ["continue_", "abort"])]
+        commit: Option<String>,
+        /// Commit the staged resolution of a cherry-pick stopped by conflicts.
+        #[arg(long = "continue", conflicts_with_all = ["commit", "abort"])]
+        continue_: bool,
+        /// Give up a cherry-pick stopped by conflicts, restoring the files it changed.
+        #[arg(long, conflicts_with = "commit")]
+        abort: bool,
+    },
+
+    /// Binary searches the history for the commit that introduced a bug.
+    Bisect {
+        #[command(subcommand)]
+        action: BisectAction,
+    },
 
     /// Prints a list of branches, marking the current with an asterisk.
     Branch {
         #[arg(short = 'D')]
         delete: bool,
+        /// Rename a branch, or the current one if only the new name is given.
+        #[arg(short = 'm', long, conflicts_with = "delete")]
+        rename: bool,
+        /// List only branches merged into the commit (default: HEAD).
+        #[arg(long, value_name = "COMMIT", num_args = 0..=1, default_missing_value = "HEAD", conflicts_with_all = ["delete", "no_merged"])]
+        merged: Option<String>,
+        /// List only branches not merged into the commit (default: HEAD).
+        #[arg(long, value_name = "COMMIT", num_args = 0..=1, default_missing_value = "HEAD", conflicts_with = "delete")]
+        no_merged: Option<String>,
         branch_name: Option<String>,
+        /// New name for the branch being renamed.
+        #[arg(requires = "rename")]
+        new_name: Option<String>,
+        /// Show the head commit of each branch. Given twice, also show how each compares to its
+        /// remote tracking branch.
+        #[arg(short, long, action = clap::ArgAction::Count)]
+        verbose: u8,
     },
 
     /// Switches branches, optionally creating the named one.
     Switch {
+        /// The branch to switch to, or a commit to check out with the HEAD detached.
         branch_name: String,
+        /// The commit at which to create the branch with `--create`. Defaults to the HEAD.
+        #[arg(requires = "create")]
+        start_point: Option<String>,
         #[arg(short, long)]
         create: bool,
+        /// Create the branch from a remote tracking branch of the same name (default).
+        #[arg(long, overrides_with = "no_guess")]
+        guess: bool,
+        /// Do not look for a matching remote tracking branch.
+        #[arg(long, overrides_with = "guess")]
+        no_guess: bool,
+        /// Discard all local changes to tracked files before switching.
+        #[arg(short, long, alias = "discard-changes")]
+        force: bool,
+    },
+
+    /// Lists commit hashes, newest first.
+    RevList {
+        /// A commit, e.g. "HEAD", or a range "<from>..<to>" of the commits reachable from <to> but
+        /// not from <from>.
+        rev_range: String,
+        /// Print only the number of commits.
+        #[arg(long)]
+        count: bool,
+        /// List at most this many commits.
+        #[arg(short = 'n', long, value_name = "N")]
+        max_count: Option<usize>,
+        /// Only list commits in the range that descend from <from>.
+        #[arg(long)]
+        ancestry_path: bool,
+    },
+
+    /// Shows which branches each of their recent commits is reachable from.
+    ShowBranch {
+        /// The branches to compare. Defaults to all local branches.
+        branches: Vec<String>,
+        /// Include remote tracking branches.
+        #[arg(long, conflicts_with = "branches")]
+        all: bool,
+        /// Leave out the commits reachable from the first branch.
+        #[arg(long)]
+        topics: bool,
+        /// Include merge commits reachable from only one branch.
+        #[arg(long)]
+        sparse: bool,
+    },
+
+    /// Checks whether a ref name is valid, exiting with status 1 if it is not.
+    CheckRefFormat {
+        refname: String,
+        /// Check the name as a branch name, which may have a single level.
+        #[arg(long)]
+        branch: bool,
+        /// Remove a leading slash and repeated slashes, expand a bare branch name to refs/<name>,
+        /// and print the resulting name.
+        #[arg(long)]
+        normalize: bool,
+        /// Accept names without a '/'.
+        #[arg(long)]
+        allow_onelevel: bool,
+    },
+
+    /// Lists refs in the repository along with their commit hashes.
+    ShowRef {
+        /// Only show branches.
+        #[arg(long)]
+        heads: bool,
+        /// Only show tags.
+        #[arg(long)]
+        tags: bool,
+        /// Only show the hashes, not the ref names.
+        #[arg(long)]
+        hash: bool,
+        /// Fail if any ref does not hold a valid commit hash.
+        #[arg(long)]
+        verify: bool,
+        /// Abbreviate hashes to the given number of characters.
+        #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "7")]
+        abbrev: Option<usize>,
+    },
+
+    /// Lists the refs in a remote repository along with their commit hashes.
+    LsRemote {
+        /// Path to the remote repository.
+        remote: PathBuf,
+        /// Only show branches.
+        #[arg(long)]
+        heads: bool,
+        /// Only show tags.
+        #[arg(long)]
+        tags: bool,
+        /// Only show refs matching this glob, e.g. "feature/*".
+        refs: Option<String>,
+    },
+
+    /// Adds trailers, such as "Signed-off-by: Alice <alice@example.com>", to a commit message read
+    /// from a file or stdin.
+    InterpretTrailers {
+        /// Write the result back to the file instead of printing it.
+        #[arg(long, requires = "file")]
+        in_place: bool,
+        /// A trailer to add, as "<token>: <value>" or "<token>=<value>". May be repeated.
+        #[arg(long = "trailer", value_name = "TRAILER")]
+        trailers: Vec<String>,
+        /// File containing the commit message. Defaults to stdin.
+        file: Option<PathBuf>,
+    },
+
+    /// Merges the changes from <BASE> to <OTHER> into <CURRENT>, marking any conflicts.
+    ///
+    /// Exits with status 0 if there were no conflicts, 1 if there were, and 2 on error.
+    MergeFile {
+        current: PathBuf,
+        base: PathBuf,
+        other: PathBuf,
+        /// Use this many characters for conflict markers.
+        #[arg(long, value_name = "N", default_value_t = 7)]
+        marker_size: usize,
+        /// Name the current, base, and other versions in conflict markers instead of using their
+        /// file names. May be given up to three times, in that order.
+        #[arg(short = 'L', long)]
+        label: Vec<String>,
+        /// Print the result instead of overwriting <CURRENT>.
+        #[arg(short = 'p', long)]
+        stdout: bool,
+        /// Do not warn about conflicts.
+        #[arg(short, long)]
+        quiet: bool,
+    },
+
+    /// Writes the files needed to serve the repository over plain HTTP.
+    UpdateServerInfo,
+
+    /// Collects diagnostic information into a zip file for bug reports.
+    Diagnose,
+
+    /// Fixes common kinds of repository corruption.
+    Repair {
+        /// Only print the repairs that would be made.
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+
+    /// Moves commits between repositories by way of a single file.
+    Bundle {
+        #[command(subcommand)]
+        action: BundleAction,
+    },
+
+    /// Lists, creates, or deletes tags, which name commits permanently.
+    Tag {
+        /// The tag to create or delete. Lists the tags if not given.
+        name: Option<String>,
+        /// The commit to tag. Defaults to the HEAD.
+        #[arg(conflicts_with = "delete")]
+        commit: Option<String>,
+        #[arg(short, long, requires = "name")]
+        delete: bool,
+        /// Make an annotated tag, which records the tagger, date, and a message.
+        #[arg(short, requires = "message")]
+        annotate: bool,
+        /// The message of an annotated tag. Implies -a.
+        #[arg(short, long, conflicts_with = "delete", requires = "name")]
+        message: Option<String>,
+    },
+
+    /// Shelves the changes to tracked files, or reapplies shelved changes.
+    Stash {
+        #[command(subcommand)]
+        action: Option<StashAction>,
+    },
+
+    /// Reads or writes a setting, such as user.name, user.email, or core.autocrlf.
+    #[command(group = clap::ArgGroup::new("action").args(["get", "set"]).required(true))]
+    Config {
+        /// Print the value of the setting, exiting with status 1 if it is not set.
+        #[arg(long, value_name = "KEY")]
+        get: Option<String>,
+        /// Set the setting to a value.
+        #[arg(long, num_args = 2, value_names = ["KEY", "VALUE"])]
+        set: Option<Vec<String>>,
+        /// Use the user's ~/.gitletconfig instead of the repository's settings.
+        #[arg(long)]
+        global: bool,
     },
 }
 
+#[derive(Debug, Subcommand)]
+enum BundleAction {
+    /// Writes the commits named by the revisions, e.g. `main` or `v1..main`, to a bundle file.
+    Create {
+        file: PathBuf,
+        #[arg(required = true)]
+        rev_list: Vec<String>,
+    },
+
+    /// Copies a bundle's commits into the repository and updates the branches it lists.
+    Unbundle { file: PathBuf },
+
+    /// Checks that the repository has the commits a bundle requires, and lists what it holds.
+    Verify { file: PathBuf },
+}
+
+#[derive(Debug, Subcommand)]
+enum BisectAction {
+    /// Begins a bisection from the HEAD.
+    Start,
+
+    /// Marks a commit, by default the HEAD, as having the bug.
+    Bad { commit: Option<String> },
+
+    /// Marks a commit, by default the HEAD, as being free of the bug.
+    Good { commit: Option<String> },
+
+    /// Ends the bisection, checking out what was checked out when it started.
+    Reset,
+}
+
+#[derive(Debug, Subcommand)]
+enum StashAction {
+    /// Saves the changes to tracked files as a new stash and resets them to the HEAD. This is the
+    /// default.
+    Push {
+        /// Describe the stash with this message instead of the HEAD commit.
+        #[arg(short, long)]
+        message: Option<String>,
+    },
+
+    /// Applies and stages the changes of a stash, by default the most recent, and then drops it.
+    Pop {
+        /// The stash to apply, e.g. `stash@{1}` or `1`.
+        stash: Option<String>,
+    },
+
+    /// Lists the stashes, most recent first.
+    List,
+
+    /// Discards a stash, by default the most recent, without applying it.
+    Drop {
+        /// The stash to discard, e.g. `stash@{1}` or `1`.
+        stash: Option<String>,
+    },
+}
+
+/// Rewrites git's numeric shorthand for the number of commits to log, e.g. `log -5`, as
+/// `log --max-count=5`, which clap can parse.
+fn expand_log_count(args: Vec<OsString>) -> Vec<OsString> {
+    let is_log = args
+        .iter()
+        .skip(1)
+        .find(|arg| !arg.to_string_lossy().starts_with('-'))
+        .is_some_and(|arg| arg == "log");
+    if !is_log {
+        return args;
+    }
+
+    let mut options_ended = false;
+    args.into_iter()
+        .map(|arg| {
+            options_ended |= arg == "--";
+            match arg.to_str().and_then(|arg| arg.strip_prefix('-')) {
+                Some(count)
+                    if !options_ended
+                        && !count.is_empty()
+                        && count.chars().all(|c| c.is_ascii_digit()) =>
+                {
+                    format!("--max-count={count}").into()
+                }
+                _ => arg,
+            }
+        })
+        .collect()
+}
+
 fn main() -> Result<()> {
-    let args = Cli::parse();
+    let args = Cli::parse_from(expand_log_count(std::env::args_os().collect()));
 
-    match args.command {
-        Commands::Init { repo_dir } => repo::init(repo_dir)?,
-        Commands::Add { filepath } => index::action(IndexAction::Add, &filepath)?,
-        Commands::Unstage { filepath } => index::action(IndexAction::Unstage, &filepath)?,
+    if args.version {
+        println!("gitlet version {}", env!("CARGO_PKG_VERSION"));
+        return Ok(());
+    }
+    if args.exec_path {
+        println!("{}", std::env::current_exe()?.display());
+        return Ok(());
+    }
+    let Some(command) = args.command else {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::MissingSubcommand,
+                "a subcommand is required",
+            )
+            .exit();
+    };
+
+    match command {
+        Commands::Init { repo_dir, template } => repo::init(repo_dir, template)?,
+        Commands::Add { refresh: true, .. } => index::refresh()?,
+        Commands::Add {
+            verbose,
+            chmod,
+            refresh: false,
+            filepath,
+        } => {
+            let set_mode = chmod.map(|chmod| match chmod.as_str() {
+                "+x" => blob::EXECUTABLE_MODE,
+                _ => blob::REGULAR_MODE,
+            });
+            index::action(IndexAction::Add, &filepath, verbose, set_mode)?
+        }
+        Commands::Unstage { filepath } => {
+            index::action(IndexAction::Unstage, &[filepath], false, None)?
+        }
+        Commands::Reset {
+            soft,
+            mixed: _,
+            hard,
+            force,
+            commit,
+        } => {
+            let mode = if soft {
+                repo::ResetMode::Soft
+            } else if hard {
+                repo::ResetMode::Hard
+            } else {
+                repo::ResetMode::Mixed
+            };
+            repo::reset(commit.as_deref(), mode, force)?
+        }
+        Commands::Revert { no_commit, commit } => repo::revert(&commit, no_commit)?,
+        Commands::Restore {
+            source,
+            staged,
+            filepath,
+        } => repo::restore(&filepath, source.as_deref(), staged)?,
         Commands::Rm { cached, filepath } => index::rm(cached, &filepath)?,
-        Commands::Status => repo::status()?,
-        Commands::Commit { message } => repo::commit(message)?,
-        Commands::Log => repo::log()?,
+        Commands::Status { verbose } => repo::status(verbose)?,
+        Commands::Diff { cached } => diff::diff(cached)?,
+        Commands::Commit {
+            message,
+            fixup,
+            squash,
+            template,
+            date,
+            allow_empty,
+            allow_empty_message,
+            force,
+            only,
+            signoff,
+        } => repo::commit(repo::CommitOptions {
+            message: (!message.is_empty()).then(|| message.join("\n\n")),
+            fixup,
+            squash,
+            template,
+            date,
+            allow_empty,
+            allow_empty_message,
+            force,
+            only,
+            signoff,
+            author: None,
+        })?,
+        Commands::Log {
+            skip,
+            max_count,
+            format,
+            pretty,
+            oneline,
+            topo_order,
+            first_parent,
+            all,
+            grep,
+            all_match,
+            ignore_case,
+        } => repo::log(repo::LogOptions {
+            skip,
+            max_count,
+            pretty: pretty
+                .or(format.map(|format| format!("format:{format}")))
+                .or(oneline.then(|| "format:%h %s".to_string())),
+            topo_order,
+            first_parent,
+            all,
+            grep,
+            all_match,
+            ignore_case,
+        })?,
+        Commands::Show { commit } => repo::show(commit.as_deref())?,
+        Commands::Blame { filepath } => blame::blame(&filepath)?,
+        Commands::CherryPick {
+            commit,
+            continue_,
+            abort,
+        } => match commit {
+            Some(commit) => repo::cherry_pick(&commit)?,
+            None if continue_ => repo::cherry_pick_continue()?,
+            None if abort => repo::cherry_pick_abort()?,
+            None => unreachable!("clap requires a commit, --continue, or --abort"),
+        },
+        Commands::Bisect { action } => match action {
+            BisectAction::Start => bisect::start()?,
+            BisectAction::Bad { commit } => bisect::mark(commit.as_deref(), false)?,
+            BisectAction::Good { commit } => bisect::mark(commit.as_deref(), true)?,
+            BisectAction::Reset => bisect::reset()?,
+        },
         Commands::Branch {
             branch_name,
+            new_name,
+            delete,
+            rename,
+            merged,
+            no_merged,
+            verbose,
+        } => repo::branch(
+            branch_name,
+            new_name,
             delete,
-        } => repo::branch(branch_name, delete)?,
+            rename,
+            merged,
+            no_merged,
+            verbose,
+        )?,
         Commands::Switch {
             branch_name,
+            start_point,
             create,
-        } => repo::switch(&branch_name, create)?,
+            guess: _,
+            no_guess,
+            force,
+        } => repo::switch(
+            &branch_name,
+            start_point.as_deref(),
+            create,
+            !no_guess,
+            force,
+        )?,
+        Commands::RevList {
+            rev_range,
+            count,
+            max_count,
+            ancestry_path,
+        } => repo::rev_list(&rev_range, count, max_count, ancestry_path)?,
+        Commands::ShowBranch {
+            branches,
+            all,
+            topics,
+            sparse,
+        } => repo::show_branch(&branches, all, topics, sparse)?,
+        Commands::CheckRefFormat {
+            refname,
+            branch,
+            normalize,
+            allow_onelevel,
+        } => {
+            if !repo::check_ref_format_command(&refname, branch, normalize, allow_onelevel)? {
+                std::process::exit(1);
+            }
+        }
+        Commands::ShowRef {
+            heads,
+            tags,
+            hash,
+            verify,
+            abbrev,
+        } => {
+            if !repo::show_ref(heads, tags, hash, verify, abbrev)? {
+                std::process::exit(1);
+            }
+        }
+        Commands::LsRemote {
+            remote,
+            heads,
+            tags,
+            refs,
+        } => {
+            if !repo::ls_remote(&remote, heads, tags, refs.as_deref())? {
+                std::process::exit(2);
+            }
+        }
+        Commands::InterpretTrailers {
+            in_place,
+            trailers,
+            file,
+        } => trailers::interpret(file.as_deref(), in_place, &trailers)?,
+        Commands::MergeFile {
+            current,
+            base,
+            other,
+            marker_size,
+            label,
+            stdout,
+            quiet,
+        } => match merge::merge_file(&current, &base, &other, &label, marker_size, stdout, quiet) {
+            Ok(0) => {}
+            Ok(_) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("Error: {e:?}");
+                std::process::exit(2);
+            }
+        },
+        Commands::UpdateServerInfo => repo::update_server_info()?,
+        Commands::Diagnose => repo::diagnose()?,
+        Commands::Repair { dry_run } => repo::repair(dry_run)?,
+        Commands::Bundle { action } => match action {
+            BundleAction::Create { file, rev_list } => bundle::create(&file, &rev_list)?,
+            BundleAction::Unbundle { file } => bundle::unbundle(&file)?,
+            BundleAction::Verify { file } => bundle::verify(&file)?,
+        },
+        Commands::Tag {
+            name,
+            commit,
+            delete,
+            annotate: _,
+            message,
+        } => tag::tag(
+            name.as_deref(),
+            commit.as_deref(),
+            delete,
+            message.as_deref(),
+        )?,
+        Commands::Stash { action } => match action {
+            None => stash::push(None)?,
+            Some(StashAction::Push { message }) => stash::push(message.as_deref())?,
+            Some(StashAction::Pop { stash }) => stash::pop(stash.as_deref())?,
+            Some(StashAction::List) => stash::list()?,
+            Some(StashAction::Drop { stash }) => stash::drop(stash.as_deref())?,
+        },
+        Commands::Config { get, set, global } => match (get, set) {
+            (Some(key), _) => {
+                if !config::get(&key, global)? {
+                    std::process::exit(1);
+                }
+            }
+            (None, Some(set)) => config::set(&set[0], &set[1], global)?,
+            (None, None) => unreachable!("clap requires --get or --set"),
+        },
     }
 
     Ok(())
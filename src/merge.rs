@@ -0,0 +1,431 @@
+//! Three-way merging of the contents of a file, which combines the changes made to a common base
+//! version on two sides and marks the regions where they conflict.
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::blob::Blob;
+
+/// The labels and marker length used when writing conflicts.
+pub(crate) struct ConflictStyle<'a> {
+    pub(crate) current_label: &'a str,
+    pub(crate) other_label: &'a str,
+    pub(crate) marker_size: usize,
+}
+
+/// Merges the changes made to `base` in `other` into `current`, writing the result back to
+/// `current`, or to stdout if `stdout` is set.
+///
+/// `labels` name the current, base, and other versions in conflict markers, in that order, and
+/// default to their file names. Only the current and other labels appear in the markers.
+///
+/// Returns the number of conflicts.
+pub fn merge_file(
+    current: &Path,
+    base: &Path,
+    other: &Path,
+    labels: &[String],
+    marker_size: usize,
+    stdout: bool,
+    quiet: bool,
+) -> Result<usize> {
+    anyhow::ensure!(labels.len() <= 3, "At most three labels may be given");
+
+    let read = |path: &Path| {
+        fs::read_to_string(path).with_context(|| format!("Read '{}'", path.display()))
+    };
+    let current_content = read(current)?;
+    let base_content = read(base)?;
+    let other_content = read(other)?;
+
+    let current_label = labels
+        .first()
+        .cloned()
+        .unwrap_or_else(|| current.display().to_string());
+    let other_label = labels
+        .get(2)
+        .cloned()
+        .unwrap_or_else(|| other.display().to_string());
+
+    let (merged, conflicts) = merge(
+        &base_content,
+        &current_content,
+        &other_content,
+        &ConflictStyle {
+            current_label: &current_label,
+            other_label: &other_label,
+            marker_size,
+        },
+    );
+
+    if stdout {
+        print!("{merged}");
+    } else {
+        fs::write(current, merged)
+            .with_context(|| format!("Write merge result to '{}'", current.display()))?;
+    }
+
+    if conflicts > 0 && !quiet {
+        eprintln!("warning: {conflicts} conflict(s) while merging");
+    }
+
+    Ok(conflicts)
+}
+
+/// Merges the changes made to `base` in `current` and in `other`, returning the result along with
+/// the number of conflicting regions, which are written between conflict markers.
+pub(crate) fn merge(
+    base: &str,
+    current: &str,
+    other: &str,
+    style: &ConflictStyle,
+) -> (String, usize) {
+    let base: Vec<&str> = base.split_inclusive('\n').collect();
+    let current: Vec<&str> = current.split_inclusive('\n').collect();
+    let other: Vec<&str> = other.split_inclusive('\n').collect();
+
+    // For each line of the base, the line of each side that it was matched with, if any.
+    let mut in_current = vec![None; base.len()];
+    for (b, c) in longest_common_subsequence(&base, &current) {
+        in_current[b] = Some(c);
+    }
+    let mut in_other = vec![None; base.len()];
+    for (b, o) in longest_common_subsequence(&base, &other) {
+        in_other[b] = Some(o);
+    }
+
+    let mut out = String::new();
+    let mut conflicts = 0;
+    let (mut b, mut c, mut o) = (0, 0, 0);
+    loop {
+        // The next base line left unchanged on both sides, or the ends of all three versions.
+        let stable = (b..base.len()).find_map(|i| Some((i, in_current[i]?, in_other[i]?)));
+        let (b_end, c_end, o_end) = stable.unwrap_or((base.len(), current.len(), other.len()));
+
+        let base_chunk = &base[b..b_end];
+        let current_chunk = &current[c..c_end];
+        let other_chunk = &other[o..o_end];
+        if current_chunk == base_chunk || current_chunk == other_chunk {
+            out.extend(other_chunk.iter().copied());
+        } else if other_chunk == base_chunk {
+            out.extend(current_chunk.iter().copied());
+        } else {
+            conflicts += 1;
+            write_conflict(&mut out, current_chunk, other_chunk, style);
+        }
+
+        match stable {
+            Some(_) => {
+                out.push_str(base[b_end]);
+                (b, c, o) = (b_end + 1, c_end + 1, o_end + 1);
+            }
+            None => break,
+        }
+    }
+
+    (out, conflicts)
+}
+
+/// How a file changes when merging sets of files.
+pub(crate) enum FileMerge {
+    /// Take this version of the file.
+    Take(Blob),
+    /// Write these merged contents, which hold conflict markers if the file conflicts.
+    Write(String),
+    /// Delete the file.
+    Delete,
+}
+
+/// The result of merging the changes made to one set of files in another, into the current set.
+pub(crate) struct TreeMerge {
+    /// The files that change in the current set, sorted by path.
+    pub(crate) changes: Vec<(PathBuf, FileMerge)>,
+    /// The files whose changes conflict, sorted by path. A conflicting file is written with
+    /// conflict markers if both sides have it as text, and otherwise left as it is.
+    pub(crate) conflicts: Vec<PathBuf>,
+}
+
+/// Merges the changes made to the files of `base` in `other` into `current`, such as to apply a
+/// commit, the other side, whose parent is the base, onto the HEAD.
+pub(crate) fn merge_trees(
+    base: &HashMap<PathBuf, Blob>,
+    current: &HashMap<PathBuf, Blob>,
+    other: &HashMap<PathBuf, Blob>,
+    style: &ConflictStyle,
+) -> Result<TreeMerge> {
+    let same = |a: Option<&Blob>, b: Option<&Blob>| match (a, b) {
+        (Some(a), Some(b)) => a.hash == b.hash && a.mode == b.mode,
+        (a, b) => a.is_none() && b.is_none(),
+    };
+    let text = |fpath: &Path, blob: &Blob| -> Result<Option<String>> {
+        let content = blob
+            .read()
+            .with_context(|| format!("Read blob of '{}'", fpath.display()))?;
+        Ok(String::from_utf8(content).ok())
+    };
+
+    let mut paths: Vec<&PathBuf> = base.keys().chain(other.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut merged = TreeMerge {
+        changes: Vec::new(),
+        conflicts: Vec::new(),
+    };
+    for f in paths {
+        let (b, c, o) = (base.get(f), current.get(f), other.get(f));
+        if same(b, o) || same(c, o) {
+            continue;
+        }
+        if same(c, b) {
+            let change = match o {
+                Some(o) => FileMerge::Take(o.clone()),
+                None => FileMerge::Delete,
+            };
+            merged.changes.push((f.clone(), change));
+            continue;
+        }
+
+        // Both sides changed the file. An added file is merged as if from empty.
+        let (Some(c), Some(o)) = (c, o) else {
+            merged.conflicts.push(f.clone());
+            continue;
+        };
+        let b_text = match b {
+            Some(b) => text(f, b)?,
+            None => Some(String::new()),
+        };
+        let (Some(b_text), Some(c_text), Some(o_text)) = (b_text, text(f, c)?, text(f, o)?) else {
+            merged.conflicts.push(f.clone());
+            continue;
+        };
+
+        let (content, conflicts) = merge(&b_text, &c_text, &o_text, style);
+        if conflicts > 0 {
+            merged.conflicts.push(f.clone());
+        }
+        merged.changes.push((f.clone(), FileMerge::Write(content)));
+    }
+
+    Ok(merged)
+}
+
+fn write_conflict(out: &mut String, current: &[&str], other: &[&str], style: &ConflictStyle) {
+    let marker = |c: char| c.to_string().repeat(style.marker_size);
+    let push_lines = |out: &mut String, lines: &[&str]| {
+        for line in lines {
+            out.push_str(line);
+        }
+        if !out.ends_with('\n') {
+            out.push('\n');
+        }
+    };
+
+    out.push_str(&format!("{} {}\n", marker('<'), style.current_label));
+    push_lines(out, current);
+    out.push_str(&format!("{}\n", marker('=')));
+    push_lines(out, other);
+    out.push_str(&format!("{} {}\n", marker('>'), style.other_label));
+}
+
+/// Returns the pairs of indices of the lines that `a` and `b` have in common, in order.
+///
+/// This is Myers' diff algorithm in its linear space form: the edit path is split at its middle
+/// snake, a run of common lines found by searching from both ends at once, and each side is solved
+/// in turn. Memory grows with the number of lines, not with their product as it would with a table
+/// of every pair, and time with the number of lines times the number of differences.
+pub(crate) fn longest_common_subsequence(a: &[&str], b: &[&str]) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    common_lines(a, b, 0, 0, &mut pairs);
+    pairs
+}
+
+/// Appends to `pairs` the common lines of `a` and `b`, which start at the lines `a_start` and
+/// `b_start` of the whole sequences.
+fn common_lines<'a>(
+    a: &[&'a str],
+    b: &[&'a str],
+    a_start: usize,
+    b_start: usize,
+    pairs: &mut Vec<(usize, usize)>,
+) {
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    pairs.extend((0..prefix).map(|i| (a_start + i, b_start + i)));
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let (a_start, b_start) = (a_start + prefix, b_start + prefix);
+
+    let suffix = a
+        .iter()
+        .rev()
+        .zip(b.iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let (a, b) = (&a[..a.len() - suffix], &b[..b.len() - suffix]);
+
+    // Without a common prefix or suffix, both being non-empty means at least two differences, so
+    // the middle snake splits the problem into smaller ones.
+    if !a.is_empty() && !b.is_empty() {
+        let (x, y, u, v) = middle_snake(a, b);
+        common_lines(&a[..x], &b[..y], a_start, b_start, pairs);
+        pairs.extend((0..u - x).map(|i| (a_start + x + i, b_start + y + i)));
+        common_lines(&a[u..], &b[v..], a_start + u, b_start + v, pairs);
+    }
+
+    let (a_end, b_end) = (a_start + a.len(), b_start + b.len());
+    pairs.extend((0..suffix).map(|i| (a_end + i, b_end + i)));
+}
+
+/// Returns the start `(x, y)` and end `(u, v)` of the middle snake of the shortest edit path from
+/// `a` to `b`, where `x` and `u` index `a`, and `y` and `v` index `b`.
+fn middle_snake(a: &[&str], b: &[&str]) -> (usize, usize, usize, usize) {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2;
+    let offset = max + 1;
+    // The furthest point along each diagonal k = x - y reached so far, as the number of lines of
+    // `a` consumed from the start, or from the end in the reverse search.
+    let mut forward = vec![0isize; (2 * offset + 1) as usize];
+    let mut reverse = vec![0isize; (2 * offset + 1) as usize];
+    let at = |k: isize| (k + offset) as usize;
+
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let (x0, y0) = (x, y);
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                (x, y) = (x + 1, y + 1);
+            }
+            forward[at(k)] = x;
+            // The reverse search is on diagonal delta - k, and has taken d - 1 steps.
+            if odd && (delta - k).abs() < d && x + reverse[at(delta - k)] >= n {
+                return (x0 as usize, y0 as usize, x as usize, y as usize);
+            }
+        }
+
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && reverse[at(k - 1)] < reverse[at(k + 1)]) {
+                reverse[at(k + 1)]
+            } else {
+                reverse[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let (x0, y0) = (x, y);
+            while x < n && y < m && a[(n - 1 - x) as usize] == b[(m - 1 - y) as usize] {
+                (x, y) = (x + 1, y + 1);
+            }
+            reverse[at(k)] = x;
+            // The forward search is on diagonal delta - k, and has taken d steps.
+            if !odd && (delta - k).abs() <= d && x + forward[at(delta - k)] >= n {
+                return (
+                    (n - x) as usize,
+                    (m - y) as usize,
+                    (n - x0) as usize,
+                    (m - y0) as usize,
+                );
+            }
+        }
+    }
+
+    unreachable!("the forward and reverse searches meet within (n + m) / 2 steps")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STYLE: ConflictStyle = ConflictStyle {
+        current_label: "ours",
+        other_label: "theirs",
+        marker_size: 7,
+    };
+
+    #[test]
+    fn merge_without_conflicts() {
+        let base = "one\ntwo\nthree\nfour\n";
+        let current = "one\n2\nthree\nfour\n";
+        let other = "one\ntwo\nthree\nfour\nfive\n";
+
+        assert_eq!(
+            ("one\n2\nthree\nfour\nfive\n".to_string(), 0),
+            merge(base, current, other, &STYLE)
+        );
+
+        // The same change on both sides is taken once.
+        assert_eq!(
+            (current.to_string(), 0),
+            merge(base, current, current, &STYLE)
+        );
+    }
+
+    #[test]
+    fn merge_with_conflicts() {
+        let base = "one\ntwo\nthree\n";
+        let current = "one\nTWO\nthree\n";
+        let other = "one\n2\nthree\n";
+
+        assert_eq!(
+            (
+                "one\n<<<<<<< ours\nTWO\n=======\n2\n>>>>>>> theirs\nthree\n".to_string(),
+                1
+            ),
+            merge(base, current, other, &STYLE)
+        );
+
+        // Markers always start on a line of their own.
+        let style = ConflictStyle {
+            marker_size: 3,
+            ..STYLE
+        };
+        assert_eq!(
+            ("<<< ours\na\n===\nb\n>>> theirs\n".to_string(), 1),
+            merge("", "a", "b\n", &style)
+        );
+    }
+
+    /// Asserts that `pairs` is a common subsequence of `a` and `b` of the given length.
+    fn assert_common_subsequence(a: &[&str], b: &[&str], pairs: &[(usize, usize)], len: usize) {
+        assert_eq!(len, pairs.len());
+        assert!(pairs.iter().all(|&(i, j)| a[i] == b[j]));
+        assert!(pairs.windows(2).all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1));
+    }
+
+    #[test]
+    fn longest_common_subsequence_of_small_inputs() {
+        let cases: [(&str, &str, usize); 6] = [
+            ("", "", 0),
+            ("abc", "", 0),
+            ("abc", "abc", 3),
+            ("abcabba", "cbabac", 4),
+            ("xaxbx", "ab", 2),
+            ("abcd", "dcba", 1),
+        ];
+        for (a, b, len) in cases {
+            let a: Vec<&str> = a.split("").filter(|s| !s.is_empty()).collect();
+            let b: Vec<&str> = b.split("").filter(|s| !s.is_empty()).collect();
+            assert_common_subsequence(&a, &b, &longest_common_subsequence(&a, &b), len);
+            assert_common_subsequence(&b, &a, &longest_common_subsequence(&b, &a), len);
+        }
+    }
+
+    #[test]
+    fn longest_common_subsequence_of_large_inputs() {
+        let a: Vec<String> = (0..20_000).map(|n| format!("line {}", n % 1000)).collect();
+        let mut b = a.clone();
+        b.remove(15_000);
+        b.insert(10_000, "new".to_string());
+        b[5_000] = "changed".to_string();
+        let a: Vec<&str> = a.iter().map(String::as_str).collect();
+        let b: Vec<&str> = b.iter().map(String::as_str).collect();
+
+        let pairs = longest_common_subsequence(&a, &b);
+        assert_common_subsequence(&a, &b, &pairs, a.len() - 2);
+    }
+}
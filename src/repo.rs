@@ -1,20 +1,32 @@
 //! This module provides methods for creating a new repository and for interacting with an existing one.
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, read_dir};
-use std::io::{self, Read, Write};
+use std::io::{self, IsTerminal, Read, Write};
 use std::path::{self, Path, PathBuf};
 
 use anyhow::{Context, Result, anyhow};
+use regex::RegexBuilder;
 use walkdir::WalkDir;
 
 use crate::blob::Blob;
-use crate::commit::{Commit, get_commit_blobs};
-use crate::index::{self, Index};
+use crate::commit::{
+    self, Commit, CommitBuilder, CommitDateIter, get_commit_blobs, is_ancestor_of,
+};
+use crate::config::{Config, ConfigScope};
+use crate::diff;
+use crate::gitletignore::Patterns;
+use crate::index::{self, Index, IndexAction};
+use crate::merge;
+use crate::trailers;
+use crate::transaction::Transaction;
 
 /// Initializes a new gitlet repository. `repo_path` is an optional argument passed to
 /// `gitlet init` to specify the directory for the new repository. It defaults to the PWD.
-pub fn init(repo_dir: Option<String>) -> Result<()> {
+///
+/// The files in the `template` directory, or else in `~/.gitlet/templates/default/` if it exists,
+/// are copied into the new `.gitlet/` directory.
+pub fn init(repo_dir: Option<String>, template: Option<PathBuf>) -> Result<()> {
     // If a repository directory was provided, then convert it to a Path,
     // otherwise, use the PWD.
     let repo_dir = match repo_dir {
@@ -29,6 +41,17 @@ pub fn init(repo_dir: Option<String>) -> Result<()> {
         ));
     }
 
+    let template = template.or_else(|| {
+        std::env::var_os("HOME")
+            .map(|home| Path::new(&home).join(".gitlet/templates/default"))
+            .filter(|dir| dir.is_dir())
+    });
+    if let Some(template) = &template
+        && !template.is_dir()
+    {
+        anyhow::bail!("Template directory '{}' does not exist", template.display());
+    }
+
     if !rpath.exists() {
         fs::create_dir(rpath).expect("Failed to create directory for repository");
     }
@@ -42,19 +65,65 @@ pub fn init(repo_dir: Option<String>) -> Result<()> {
     head.write_all(b"main")
         .context("Write 'main' to '.gitlet/HEAD'")?;
 
+    if let Some(template) = template {
+        copy_template(&template, &rpath.join(".gitlet"))
+            .with_context(|| format!("Copy template directory '{}'", template.display()))?;
+    }
+
     println!("Initialized empty Gitlet repository");
 
     Ok(())
 }
 
-/// Prints the status of the gitlet repository to stdout.
-pub fn status() -> Result<()> {
+/// Copies the files in the `template` directory into the `.gitlet` directory, leaving any file
+/// that already exists there alone. Files under `hooks/` are made executable.
+fn copy_template(template: &Path, gitlet_dir: &Path) -> Result<()> {
+    for entry in WalkDir::new(template).min_depth(1) {
+        let entry = entry.context("Read template directory")?;
+        let rel_path = entry
+            .path()
+            .strip_prefix(template)
+            .context("Strip template directory from path")?;
+        let dest = gitlet_dir.join(rel_path);
+
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&dest)
+                .with_context(|| format!("Create directory '{}'", dest.display()))?;
+            continue;
+        }
+        if dest.exists() {
+            continue;
+        }
+
+        fs::copy(entry.path(), &dest)
+            .with_context(|| format!("Copy template file '{}'", rel_path.display()))?;
+
+        #[cfg(unix)]
+        if rel_path.starts_with("hooks") {
+            use std::os::unix::fs::PermissionsExt;
+            let mut permissions = fs::metadata(&dest)
+                .with_context(|| format!("Read permissions of '{}'", dest.display()))?
+                .permissions();
+            permissions.set_mode(permissions.mode() | 0o111);
+            fs::set_permissions(&dest, permissions)
+                .with_context(|| format!("Make hook '{}' executable", rel_path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the status of the gitlet repository to stdout, followed by the diff of the staged changes
+/// if `verbose` is set.
+pub fn status(verbose: bool) -> Result<()> {
     let stdout = io::stdout();
     let handle = stdout.lock();
     let mut buf_handle = io::BufWriter::new(handle);
 
-    let branch_name = get_head_branch()?;
-    writeln!(buf_handle, "On branch {branch_name}\n")?;
+    match detached_head()? {
+        Some(hash) => writeln!(buf_handle, "HEAD detached at {}\n", &hash[..7])?,
+        None => writeln!(buf_handle, "On branch {}\n", get_head_branch()?)?,
+    }
 
     // Staged for addition and for removal
     index::status(&mut buf_handle)?;
@@ -72,15 +141,54 @@ pub fn status() -> Result<()> {
 
     writeln!(buf_handle)?;
 
+    if verbose {
+        write!(
+            buf_handle,
+            "{}",
+            diff::staged_diff().context("Diff staged changes")?
+        )?;
+    }
+
     buf_handle.flush()?;
 
     Ok(())
 }
 
 /// Dispatches for the `gitlet branch` command.
-pub fn branch(branch_name: Option<String>, delete: bool) -> Result<()> {
+///
+/// `merged` and `no_merged` name a commit against which to filter the listed branches, and
+/// `verbose` sets how much is shown about each (see [`branches`]). When
+/// `rename` is set, `branch_name` is renamed to `new_name`, or, if `new_name` is not given, the
+/// current branch is renamed to `branch_name`.
+pub fn branch(
+    branch_name: Option<String>,
+    new_name: Option<String>,
+    delete: bool,
+    rename: bool,
+    merged: Option<String>,
+    no_merged: Option<String>,
+    verbose: u8,
+) -> Result<()> {
+    if let Some(commit) = merged {
+        return branches(Some((&commit, true)), verbose);
+    }
+    if let Some(commit) = no_merged {
+        return branches(Some((&commit, false)), verbose);
+    }
+
+    if rename {
+        return match (branch_name, new_name) {
+            (Some(old_name), Some(new_name)) => rename_branch(&old_name, &new_name),
+            (Some(new_name), None) => {
+                let current_branch = get_head_branch().context("Get current branch name")?;
+                rename_branch(&current_branch, &new_name)
+            }
+            (None, _) => anyhow::bail!("Branch name required"),
+        };
+    }
+
     match (branch_name, delete) {
-        (None, false) => branches(),
+        (None, false) => branches(None, verbose),
         (None, true) => anyhow::bail!("Branch name required"),
         (Some(branch_name), true) => delete_branch(&branch_name),
         (Some(branch_name), false) => create_branch(&branch_name),
@@ -88,12 +196,29 @@ pub fn branch(branch_name: Option<String>, delete: bool) -> Result<()> {
 }
 
 /// Displays a list of branches, marking the one currently checked out with an asterisk.
-fn branches() -> Result<()> {
+///
+/// When `merge_filter` is given as `(commit, merged)`, only the branches whose head commit is an
+/// ancestor of `commit` are listed if `merged` is true, or only those whose head commit is not an
+/// ancestor of it if `merged` is false.
+///
+/// With a `verbose` level of 1, each branch is followed by the abbreviated hash and subject of its
+/// head commit. With 2 or more, its upstream, i.e. the remote tracking branch of the same name, and
+/// how far ahead of and behind it the branch is are shown as well.
+fn branches(merge_filter: Option<(&str, bool)>, verbose: u8) -> Result<()> {
+    let merge_filter = match merge_filter {
+        Some((commit, merged)) => Some((resolve_ref(commit)?, merged)),
+        None => None,
+    };
+
     let repo_root = abs_path_to_repo_root().context("Get absolute path to repo directory")?;
-    let head_branch: std::ffi::OsString = get_head_branch()
+    let head_branch: std::ffi::OsString = read_head_file()
         .context("Get name of currently checked out branch")?
         .into();
 
+    if let Some(hash) = detached_head()? {
+        println!("* (HEAD detached at {})", &hash[..7]);
+    }
+
     let mut branches: Vec<_> = repo_root
         .join(".gitlet/refs")
         .read_dir()
@@ -103,20 +228,93 @@ fn branches() -> Result<()> {
         .collect();
 
     branches.sort_by_key(|e| e.file_name());
+    let width = branches
+        .iter()
+        .map(|e| e.file_name().len())
+        .max()
+        .unwrap_or_default();
 
     for entry in branches {
+        let tip = fs::read_to_string(entry.path()).context("Read branch ref")?;
+        if let Some((hash, merged)) = &merge_filter
+            && is_ancestor_of(&tip, hash)? != *merged
+        {
+            continue;
+        }
+
         let branch_name = entry.file_name();
-        if head_branch == branch_name {
-            println!("* {}", branch_name.display());
-        } else {
-            println!("  {}", branch_name.display());
+        let marker = if head_branch == branch_name { '*' } else { ' ' };
+        let branch_name = branch_name.to_string_lossy();
+
+        // Branches without commits have nothing more to show.
+        if verbose == 0 || tip.is_empty() {
+            println!("{marker} {branch_name}");
+            continue;
         }
+
+        let tracking = if verbose > 1 {
+            tracking_info(&branch_name, &tip)?
+        } else {
+            String::new()
+        };
+        let commit = Commit::load(&tip).with_context(|| format!("Load commit {tip}"))?;
+        println!(
+            "{marker} {branch_name:width$} {} {tracking}{}",
+            &tip[..7],
+            commit.summary()
+        );
     }
 
     Ok(())
 }
 
+/// Describes how the branch whose head commit is `tip` compares to its upstream, e.g.
+/// "[origin/main: ahead 2, behind 1] ", or returns an empty string if it has none.
+///
+/// The upstream is the remote tracking branch of the same name, preferring the one from `origin`
+/// when several remotes have it.
+fn tracking_info(branch_name: &str, tip: &str) -> Result<String> {
+    let mut remotes = remote_tracking_branches(branch_name)
+        .context("Search for matching remote tracking branches")?;
+    remotes.sort();
+    let Some(remote) = remotes
+        .iter()
+        .find(|r| *r == "origin")
+        .or_else(|| remotes.first())
+    else {
+        return Ok(String::new());
+    };
+
+    let upstream = format!("{remote}/{branch_name}");
+    let upstream_tip = fs::read_to_string(
+        abs_path_to_repo_root()?
+            .join(".gitlet/refs/remotes")
+            .join(&upstream),
+    )
+    .with_context(|| format!("Read remote tracking branch '{upstream}'"))?;
+
+    let local = commit::ancestors(tip)?;
+    let remote = commit::ancestors(upstream_tip.trim())?;
+    let ahead = local.difference(&remote).count();
+    let behind = remote.difference(&local).count();
+
+    Ok(match (ahead, behind) {
+        (0, 0) => format!("[{upstream}] "),
+        (ahead, 0) => format!("[{upstream}: ahead {ahead}] "),
+        (0, behind) => format!("[{upstream}: behind {behind}] "),
+        (ahead, behind) => format!("[{upstream}: ahead {ahead}, behind {behind}] "),
+    })
+}
+
 fn create_branch(branch_name: &str) -> Result<()> {
+    let head_hash = read_head_hash().context("Get HEAD commit hash")?;
+    create_branch_at(branch_name, &head_hash)
+}
+
+/// Creates a branch named `branch_name` whose head is the commit with the given hash.
+fn create_branch_at(branch_name: &str, hash: &str) -> Result<()> {
+    check_branch_name(branch_name)?;
+
     // Create the path to the named branch.
     let branch_path = abs_path_to_repo_root()
         .context("Get absolute path to working tree root")?
@@ -127,24 +325,127 @@ fn create_branch(branch_name: &str) -> Result<()> {
         anyhow::bail!("A branch named '{branch_name}' already exists");
     }
 
-    let head_hash = read_head_hash().context("Get HEAD commit hash")?;
-
     let mut f = fs::File::create_new(branch_path)
-        .with_context(|| format!("A branch named '{branch_name}' already exists"))?;
+        .with_context(|| format!("Create ref for branch '{branch_name}'"))?;
+
+    f.write_all(hash.as_bytes())
+        .context("Write commit hash to new branch ref")?;
+
+    Ok(())
+}
+
+/// Checks that `branch_name` may be used as the name of a branch.
+fn check_branch_name(branch_name: &str) -> Result<()> {
+    check_ref_format(branch_name, true)
+        .with_context(|| format!("'{branch_name}' is not a valid branch name"))
+}
+
+/// Checks that `refname` follows the naming rules for refs, which keep refs from escaping
+/// `.gitlet/refs/` and from being mistaken for commit expressions such as `HEAD~2`.
+///
+/// A branch name, if `branch` is set, additionally may not start with `-`, so that it is not
+/// mistaken for an option, nor be `HEAD`. Since every branch is a file directly in `.gitlet/refs/`,
+/// it may not contain `/`, nor be `tags` or `remotes`, whose directories are kept there.
+///
+/// # Panics
+///
+/// Returns an error describing the first rule that is broken.
+pub(crate) fn check_ref_format(refname: &str, branch: bool) -> Result<()> {
+    anyhow::ensure!(!refname.is_empty(), "ref names may not be empty");
+    anyhow::ensure!(refname != "@", "ref names may not be '@'");
+    anyhow::ensure!(
+        !refname.starts_with('/') && !refname.ends_with('/'),
+        "ref names may not begin or end with '/'"
+    );
+    anyhow::ensure!(!refname.contains("//"), "ref names may not contain '//'");
+    anyhow::ensure!(!refname.contains(".."), "ref names may not contain '..'");
+    anyhow::ensure!(!refname.contains("@{"), "ref names may not contain '@{{'");
+    anyhow::ensure!(
+        !refname.ends_with(".lock") && !refname.ends_with('.'),
+        "ref names may not end with '.lock' or '.'"
+    );
+    anyhow::ensure!(
+        !refname.split('/').any(|c| c.starts_with('.')),
+        "ref name components may not begin with '.'"
+    );
+    if let Some(c) = refname
+        .chars()
+        .find(|c| c.is_whitespace() || c.is_control() || "\\~^:?*[".contains(*c))
+    {
+        anyhow::bail!("ref names may not contain {c:?}");
+    }
 
-    f.write_all(head_hash.as_bytes())
-        .context("Write HEAD hash to new branch ref")?;
+    if branch {
+        anyhow::ensure!(
+            !refname.starts_with('-'),
+            "branch names may not begin with '-'"
+        );
+        anyhow::ensure!(refname != "HEAD", "branch names may not be 'HEAD'");
+        anyhow::ensure!(!refname.contains('/'), "branch names may not contain '/'");
+        anyhow::ensure!(
+            refname != "tags" && refname != "remotes",
+            "branch names may not be '{refname}', which is reserved"
+        );
+    }
 
     Ok(())
 }
 
+/// Checks whether `refname` is a valid ref name, returning false, for which the command exits with
+/// status 1, if it is not.
+///
+/// Unless `allow_onelevel` or `branch` is set, the name must contain at least one `/`, as in
+/// `refs/main`. With `normalize`, a leading `/` and repeated slashes are removed before checking,
+/// a bare name such as `main` is expanded to the full name of the branch, `refs/main`, unless
+/// `branch` is set, and the normalized name is printed.
+pub fn check_ref_format_command(
+    refname: &str,
+    branch: bool,
+    normalize: bool,
+    allow_onelevel: bool,
+) -> Result<bool> {
+    let refname = if normalize {
+        let refname = refname
+            .split('/')
+            .filter(|c| !c.is_empty())
+            .collect::<Vec<_>>()
+            .join("/");
+        // Branches are kept directly in refs/, rather than in refs/heads/ as in Git.
+        if branch || refname.contains('/') {
+            refname
+        } else {
+            format!("refs/{refname}")
+        }
+    } else {
+        refname.to_string()
+    };
+
+    let result = check_ref_format(&refname, branch).and_then(|()| {
+        anyhow::ensure!(
+            allow_onelevel || branch || refname.contains('/'),
+            "ref names must contain a '/' unless --allow-onelevel is given"
+        );
+        Ok(())
+    });
+    if let Err(e) = result {
+        eprintln!("'{refname}' is not a valid ref name: {e}");
+        return Ok(false);
+    }
+
+    if normalize {
+        println!("{refname}");
+    }
+
+    Ok(true)
+}
+
 /// Deletes the named branch.
 ///
 /// # Panics
 ///
 /// Panics if the named branch is currently checked out or does not exist.
 fn delete_branch(branch_name: &str) -> Result<()> {
-    let current_branch = get_head_branch().context("Get current branch name")?;
+    let current_branch = read_head_file().context("Get current branch name")?;
     if branch_name == current_branch {
         anyhow::bail!("Cannot delete branch when it is checked out");
     }
@@ -174,16 +475,75 @@ fn delete_branch(branch_name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Renames the branch `old_name` to `new_name`, carrying HEAD along with it if it is checked out.
+fn rename_branch(old_name: &str, new_name: &str) -> Result<()> {
+    let repo_root = abs_path_to_repo_root().context("Get absolute path to working tree root")?;
+    let refs_dir = repo_root.join(".gitlet/refs");
+
+    check_branch_name(old_name)?;
+    if !refs_dir.join(old_name).is_file() {
+        anyhow::bail!("Branch '{old_name}' not found");
+    }
+    check_branch_name(new_name)?;
+    if refs_dir.join(new_name).exists() {
+        anyhow::bail!("A branch named '{new_name}' already exists");
+    }
+
+    fs::rename(refs_dir.join(old_name), refs_dir.join(new_name))
+        .with_context(|| format!("Rename branch '{old_name}' to '{new_name}'"))?;
+
+    // Keep the branch's reflog, if there is one, with the branch.
+    let logs_dir = repo_root.join(".gitlet/logs/refs");
+    if logs_dir.join(old_name).exists() {
+        fs::rename(logs_dir.join(old_name), logs_dir.join(new_name))
+            .with_context(|| format!("Rename reflog of branch '{old_name}'"))?;
+    }
+
+    if read_head_file().context("Get current branch name")? == old_name {
+        fs::write(repo_root.join(".gitlet/HEAD"), new_name)
+            .context("Write new branch name to HEAD file")?;
+    }
+
+    println!("Renamed branch '{old_name}' to '{new_name}'");
+
+    Ok(())
+}
+
 /// Switches to the named branch if it exists. If it does not exist and `create` is set, then it
-/// creates the branch and switches to it.
+/// creates the branch, at `start_point` if given or else at the HEAD, and switches to it.
+///
+/// If the branch does not exist, `create` is not set, and `guess` is set, then a remote tracking
+/// branch of the same name (e.g. `.gitlet/refs/remotes/origin/<branch_name>`) is looked for. When
+/// exactly one remote has it, a local branch is created from it and checked out.
+///
+/// Failing all that, the name is resolved as a commit expression, e.g. `HEAD~2`, and that commit is
+/// checked out with the HEAD detached from any branch.
+///
+/// With `force`, all staged and unstaged changes to tracked files are discarded before checking out
+/// the target, once it is known to exist.
 ///
 /// # Panics
 ///
 /// Returns an error if the named branch does not exist and `create` is not set, or vice versa.
-pub fn switch(branch_name: &str, create: bool) -> Result<()> {
+pub fn switch(
+    branch_name: &str,
+    start_point: Option<&str>,
+    create: bool,
+    guess: bool,
+    force: bool,
+) -> Result<()> {
+    // Local changes are only discarded once the target is known to exist, just before checkout.
+    let discard_local_changes = || -> Result<()> {
+        if force {
+            index::reset_to_head().context("Discard local changes")?;
+        }
+        Ok(())
+    };
+
     // Is it already checked out?
-    let current_branch = get_head_branch().context("Get current branch name")?;
+    let current_branch = read_head_file().context("Get current branch name")?;
     if branch_name == current_branch {
+        discard_local_changes()?;
         println!("Already on '{branch_name}'");
         return Ok(());
     }
@@ -196,6 +556,7 @@ pub fn switch(branch_name: &str, create: bool) -> Result<()> {
 
     // Does the branch exist?
     if branch_path.exists() {
+        discard_local_changes()?;
         return checkout_branch(branch_name);
     }
 
@@ -203,12 +564,95 @@ pub fn switch(branch_name: &str, create: bool) -> Result<()> {
     // Is create true?
     if create {
         // Yes: Create it and checkout.
-        create_branch(branch_name).with_context(|| format!("Create branch '{branch_name}'"))?;
+        match start_point {
+            Some(start_point) => create_branch_at(branch_name, &resolve_ref(start_point)?),
+            None => create_branch(branch_name),
+        }
+        .with_context(|| format!("Create branch '{branch_name}'"))?;
+        discard_local_changes()?;
         return checkout_branch(branch_name);
     }
 
-    // No: Bail!
-    anyhow::bail!("invalid reference: '{branch_name}'")
+    // No: Is there a remote tracking branch by that name?
+    if guess {
+        let mut remotes = remote_tracking_branches(branch_name)
+            .context("Search for matching remote tracking branches")?;
+
+        if remotes.len() > 1 {
+            remotes.sort();
+            anyhow::bail!(
+                "'{branch_name}' matched multiple remote tracking branches: {}",
+                remotes.join(", ")
+            );
+        }
+
+        if let Some(remote) = remotes.pop() {
+            let remote_ref = format!("{remote}/{branch_name}");
+            let hash = fs::read_to_string(
+                abs_path_to_repo_root()
+                    .context("Get absolute path to working tree root")?
+                    .join(".gitlet/refs/remotes")
+                    .join(&remote_ref),
+            )
+            .with_context(|| format!("Read remote tracking branch '{remote_ref}'"))?;
+            let hash = hash.trim();
+            if !is_commit_hash(hash) {
+                anyhow::bail!("Remote tracking branch '{remote_ref}' does not point to a commit");
+            }
+
+            create_branch_at(branch_name, hash)
+                .with_context(|| format!("Create branch '{branch_name}'"))?;
+            // Don't leave the new branch behind if it cannot be checked out.
+            if let Err(e) = discard_local_changes().and_then(|_| checkout_branch(branch_name)) {
+                fs::remove_file(&branch_path)
+                    .with_context(|| format!("Remove branch '{branch_name}'"))?;
+                return Err(e);
+            }
+            println!("Branch '{branch_name}' set up to track '{remote_ref}'.");
+            return Ok(());
+        }
+    }
+
+    // No: Is it a commit? Otherwise, bail!
+    let hash = resolve_ref(branch_name)?;
+    if hash.is_empty() {
+        anyhow::bail!("invalid reference: '{branch_name}'");
+    }
+    discard_local_changes()?;
+    checkout_detached(&hash)
+}
+
+/// Checks out the commit with the given hash, detaching the HEAD from any branch.
+pub(crate) fn checkout_detached(hash: &str) -> Result<()> {
+    checkout_commit(hash).with_context(|| format!("Checkout commit {hash}"))?;
+
+    let repo_root = abs_path_to_repo_root().context("Get absolute path to repo root")?;
+    fs::write(repo_root.join(".gitlet/HEAD"), hash).context("Write commit hash to HEAD file")?;
+
+    let commit = Commit::load(hash).with_context(|| format!("Load commit {hash}"))?;
+    println!("HEAD is now at {} {}", &hash[..7], commit.summary());
+
+    Ok(())
+}
+
+/// Returns the names of the remotes that have a tracking branch named `branch_name` in
+/// `.gitlet/refs/remotes/`.
+fn remote_tracking_branches(branch_name: &str) -> Result<Vec<String>> {
+    let remotes_dir = abs_path_to_repo_root()
+        .context("Get absolute path to working tree root")?
+        .join(".gitlet/refs/remotes");
+
+    if !remotes_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    Ok(remotes_dir
+        .read_dir()
+        .context("Read remotes directory")?
+        .filter_map(Result::ok)
+        .filter(|e| e.path().join(branch_name).is_file())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect())
 }
 
 /// Checks out the head commit of the named branch.
@@ -403,6 +847,21 @@ pub(crate) fn find_working_tree_dir(filepath: &Path) -> Result<PathBuf> {
     Ok(relative_path.to_path_buf())
 }
 
+/// Returns the path of a file relative to the root of the working tree, like
+/// [`find_working_tree_dir`], except that the file need not exist.
+pub(crate) fn path_from_repo_root(filepath: &Path) -> Result<PathBuf> {
+    if filepath.exists() {
+        return find_working_tree_dir(filepath);
+    }
+
+    let abs_path = path::absolute(filepath).context("Create absolute path to file name")?;
+    let repo_root = abs_path_to_repo_root().context("Get absolute path to repo root dir")?;
+    Ok(abs_path
+        .strip_prefix(&repo_root)
+        .context("Strip absolute path prefix")?
+        .to_path_buf())
+}
+
 /// Returns the absolute path to the root of the working tree in which the .gitlet/ directory resides.
 pub(crate) fn abs_path_to_repo_root() -> Result<PathBuf> {
     let curr_dir = std::env::current_dir().context("Get current working directory")?;
@@ -434,46 +893,270 @@ fn abs_path_working_file(fp: &Path) -> Result<path::PathBuf> {
     Ok(repo_root)
 }
 
+/// Options for `gitlet commit`.
+#[derive(Default)]
+pub struct CommitOptions {
+    /// The commit message. When none of it, `fixup`, or `squash` is given, the message is written
+    /// in the user's editor, pre-populated with the contents of `template` if set.
+    pub message: Option<String>,
+    /// A commit that the new commit fixes up. The message is then `fixup! ` followed by the first
+    /// line of that commit's message, marking the new commit to be folded into it.
+    pub fixup: Option<String>,
+    /// Like `fixup`, but with `squash! `, marking the new commit's message to be kept when folded.
+    /// `message`, if given, is appended to it.
+    pub squash: Option<String>,
+    /// A file whose contents pre-populate the editor, in place of the `commit.template` setting.
+    pub template: Option<PathBuf>,
+    /// Replaces the current time as the commit's timestamp (see [`commit::parse_date`]).
+    pub date: Option<String>,
+    /// Create the commit even if nothing is staged.
+    pub allow_empty: bool,
+    /// Accept an empty commit message.
+    pub allow_empty_message: bool,
+    /// Commit files staged with conflict markers in them.
+    pub force: bool,
+    /// Commit only the staged changes to these files, leaving the rest staged.
+    pub only: Vec<PathBuf>,
+    /// Add a Signed-off-by trailer for the author to the end of the message.
+    pub signoff: bool,
+    /// The name and email of the author, in place of those configured.
+    pub author: Option<(String, String)>,
+}
+
 /// Commits the staged changes to the repository.
-pub fn commit(message: String) -> Result<()> {
-    let index = index::Index::load().context("Load index for commit")?;
-    if index.is_clear() {
+///
+/// # Panics
+///
+/// Returns an error if any staged file contains conflict markers, unless `options.force` is set.
+pub fn commit(options: CommitOptions) -> Result<()> {
+    let CommitOptions {
+        message,
+        fixup,
+        squash,
+        template,
+        date,
+        allow_empty,
+        allow_empty_message,
+        force,
+        only,
+        signoff,
+        author,
+    } = options;
+
+    let mut index = index::Index::load().context("Load index for commit")?;
+
+    // With `only`, commit just those files' changes and keep the rest staged for later.
+    let remaining = if only.is_empty() {
+        None
+    } else {
+        let paths = only
+            .iter()
+            .map(|f| path_from_repo_root(f))
+            .collect::<Result<Vec<_>>>()
+            .context("Convert filepaths to be relative to working tree root")?;
+        let staged = index.split_off(&paths)?;
+        Some(std::mem::replace(&mut index, staged))
+    };
+
+    if index.is_clear() && !allow_empty {
         println!("Nothing to commit.");
         return Ok(());
     }
 
+    if !force {
+        let conflicted = index::check_for_conflicts(&index).context("Check for conflicts")?;
+        if !conflicted.is_empty() {
+            eprintln!("The following staged files contain conflict markers:");
+            for f in conflicted {
+                eprintln!("\t{}", f.display());
+            }
+            anyhow::bail!(
+                "Committing is not possible because you have unmerged files. Use --force to commit anyway."
+            );
+        }
+    }
+
+    // Without a message, the configured template is used unless one is given.
+    let template = match template {
+        None if message.is_none() && fixup.is_none() && squash.is_none() => {
+            Config::load(ConfigScope::Merged)
+                .context("Load config")?
+                .get("commit.template")
+                .map(PathBuf::from)
+        }
+        template => template,
+    };
+
+    let message = match (message, fixup, squash) {
+        (_, Some(target), _) => format!("fixup! {}", commit_subject(&target)?),
+        (None, None, Some(target)) => format!("squash! {}", commit_subject(&target)?),
+        (Some(message), None, Some(target)) => {
+            format!("squash! {}\n\n{message}", commit_subject(&target)?)
+        }
+        (Some(message), None, None) => message,
+        (None, None, None) if template.is_none() && !io::stdin().is_terminal() => {
+            let mut message = String::new();
+            io::stdin()
+                .read_to_string(&mut message)
+                .context("Read commit message from stdin")?;
+            cleanup_message(&message)
+        }
+        (None, None, None) => edit_commit_message(template.as_deref(), allow_empty_message)
+            .context("Write commit message in editor")?,
+    };
+    let message = if signoff {
+        trailers::add_trailers(&message, &[trailers::signoff()?])
+            .trim_end()
+            .to_string()
+    } else {
+        message
+    };
+
     // Get the parent commit hash.
     let parent_hash =
         read_head_hash().context("Retrieve current commit hash for parent of new commit")?;
 
-    let new_commit = Commit::new(parent_hash, None, message, index).context("Create commit")?;
-    update_head(&new_commit.hash)?;
-    new_commit.save().context("Save new commit to repository")?;
+    let timestamp = date
+        .map(|date| commit::parse_date(&date))
+        .transpose()
+        .context("Parse commit date")?;
 
-    index::clear_index().context("Clear the staging area")?;
+    let builder = match author {
+        Some((name, email)) => CommitBuilder::new().author(name, email),
+        None => CommitBuilder::new(),
+    };
+    let new_commit = builder
+        .parent(parent_hash)
+        .message(message)
+        .timestamp(timestamp)
+        .index(index)
+        .allow_empty_message(allow_empty_message)
+        .build()
+        .context("Create commit")?;
+
+    let mut tx = Transaction::begin();
+    new_commit
+        .save(&mut tx)
+        .context("Save new commit to repository")?;
+    update_head(&mut tx, &new_commit.hash)?;
+    tx.commit().context("Write new commit and update HEAD")?;
+
+    match remaining {
+        Some(remaining) => remaining
+            .save()
+            .context("Save the changes left in the staging area")?,
+        None => index::clear_index().context("Clear the staging area")?,
+    }
+
+    Ok(())
+}
+
+/// Returns the first line of the message of the commit named by `target`.
+fn commit_subject(target: &str) -> Result<String> {
+    let commit =
+        Commit::load(&resolve_ref(target)?).with_context(|| format!("Load commit '{target}'"))?;
+    Ok(commit.summary().to_string())
+}
+
+/// Opens `.gitlet/COMMIT_EDITMSG` in the user's editor and returns the message written there, with
+/// comment lines and surrounding blank lines stripped. The file is first populated with the
+/// contents of `template`, if given.
+///
+/// # Panics
+///
+/// Returns an error if the resulting message is identical to the template, or if it is empty and
+/// `allow_empty` is false.
+fn edit_commit_message(template: Option<&Path>, allow_empty: bool) -> Result<String> {
+    let template = match template {
+        Some(path) => fs::read_to_string(path)
+            .with_context(|| format!("Read commit template '{}'", path.display()))?,
+        None => String::new(),
+    };
+
+    let editmsg = abs_path_to_repo_root()
+        .context("Get absolute path to repo root")?
+        .join(".gitlet/COMMIT_EDITMSG");
+    fs::write(
+        &editmsg,
+        format!(
+            "{template}\n\
+            # Please enter the commit message for your changes. Lines starting\n\
+            # with '#' will be ignored, and an empty message aborts the commit.\n"
+        ),
+    )
+    .context("Write .gitlet/COMMIT_EDITMSG")?;
+
+    launch_editor(&editmsg)?;
+
+    let message =
+        cleanup_message(&fs::read_to_string(&editmsg).context("Read .gitlet/COMMIT_EDITMSG")?);
+    if (message.is_empty() && !allow_empty)
+        || (!message.is_empty() && message == cleanup_message(&template))
+    {
+        anyhow::bail!("Aborting commit due to empty commit message");
+    }
+
+    Ok(message)
+}
+
+/// Opens the given file in `$VISUAL` or `$EDITOR`, falling back to `vi`, and waits for it to exit.
+fn launch_editor(path: &Path) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+
+    // Run through the shell so that the editor may be given with arguments, e.g. "code --wait".
+    let status = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$@\""))
+        .arg(&editor)
+        .arg(path)
+        .status()
+        .with_context(|| format!("Launch editor '{editor}'"))?;
+
+    anyhow::ensure!(status.success(), "Editor '{editor}' exited unsuccessfully");
 
     Ok(())
 }
 
+/// Strips comment lines, trailing whitespace, and leading, trailing, and repeated blank lines from
+/// a commit message.
+fn cleanup_message(message: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in message.lines().filter(|l| !l.starts_with('#')) {
+        let line = line.trim_end();
+        if line.is_empty() && lines.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+
+    lines.join("\n")
+}
+
 /// Helper function to update HEAD file
-fn update_head(hash: &str) -> Result<()> {
+///
+/// Stages pointing the checked out branch at the given commit, or, if the HEAD is detached, the
+/// HEAD itself.
+fn update_head(tx: &mut Transaction, hash: &str) -> Result<()> {
     let repo_root = abs_path_to_repo_root().context("Get absolute path to repo root")?;
-    let mut head = std::fs::File::open(repo_root.join(".gitlet/HEAD")).context("Open HEAD file")?;
 
-    let mut branch_name = String::new();
-    head.read_to_string(&mut branch_name)
-        .context("Read branch name from HEAD")?;
+    if detached_head()?.is_some() {
+        tx.stage_file_write(repo_root.join(".gitlet/HEAD"), hash);
+        return Ok(());
+    }
 
-    let mut branch_ref = std::fs::File::create(repo_root.join(".gitlet/refs").join(branch_name))
-        .context("Truncate branch ref file")?;
-    branch_ref
-        .write_all(hash.as_bytes())
-        .context("Write hash to HEAD")?;
+    let branch_name = read_head_file()?;
+    tx.stage_file_write(repo_root.join(".gitlet/refs").join(branch_name), hash);
     Ok(())
 }
 
-/// Get the name of the branch in HEAD
-fn get_head_branch() -> Result<String> {
+/// Returns the contents of the HEAD file, which is either the name of the checked out branch or,
+/// if the HEAD is detached, a commit hash.
+pub(crate) fn read_head_file() -> Result<String> {
     let repo_root = abs_path_to_repo_root().context("Get absolute path to repo root")?;
     let mut head = std::fs::File::open(repo_root.join(".gitlet/HEAD")).context("Open HEAD file")?;
 
@@ -484,76 +1167,1295 @@ fn get_head_branch() -> Result<String> {
     Ok(branch_name)
 }
 
-/// Returns true if the given file is tracked.
-///
-/// A file is tracked if it is represented either by the HEAD commit or by the index.
-pub(crate) fn is_tracked_by_head(filepath: &Path) -> bool {
-    let Ok(head_commit) = retrieve_head_commit() else {
-        return false;
-    };
+/// Returns the hash of the checked out commit if the HEAD is detached from any branch.
+fn detached_head() -> Result<Option<String>> {
+    let head = read_head_file()?;
+    Ok(is_commit_hash(&head).then_some(head))
+}
 
-    head_commit.tracks(filepath)
+/// Returns the name of the checked out branch, or None if the HEAD is detached.
+pub(crate) fn current_branch() -> Result<Option<String>> {
+    Ok(match detached_head()? {
+        Some(_) => None,
+        None => Some(read_head_file()?),
+    })
 }
 
-fn read_head_hash() -> Result<String> {
-    let repo_root = abs_path_to_repo_root()?;
+/// Get the name of the branch in HEAD
+///
+/// # Panics
+///
+/// Returns an error if the HEAD is detached.
+pub(crate) fn get_head_branch() -> Result<String> {
+    if detached_head()?.is_some() {
+        anyhow::bail!("HEAD is detached; not on a branch");
+    }
 
-    let branch_name = std::fs::read_to_string(repo_root.join(".gitlet/HEAD"))
-        .context("Read branch name from HEAD")?;
+    read_head_file()
+}
 
-    let branch_ref = std::fs::read_to_string(repo_root.join(".gitlet/refs").join(branch_name))
-        .context("Read current HEAD commit")?;
+/// Restores the files tracked by the HEAD commit whose working copies are missing or differ from
+/// it.
+pub(crate) fn restore_head_files() -> Result<()> {
+    let repo_root = abs_path_to_repo_root().context("Get absolute path to repo root")?;
+    let head_hash = read_head_hash().context("Get hash of current HEAD commit")?;
 
-    if !branch_ref.is_empty() && branch_ref.len() != 40 {
-        anyhow::bail!("Invalid commit");
+    for (filepath, blob) in get_commit_blobs(&head_hash)? {
+        let path = repo_root.join(&filepath);
+        if !Blob::new(&path).is_ok_and(|b| b.hash == blob.hash) {
+            blob.restore(&path)?;
+        }
     }
 
-    Ok(branch_ref)
+    Ok(())
 }
 
-/// Returns the commit referenced by the HEAD file's hash.
-fn retrieve_head_commit() -> Result<Commit> {
-    Commit::load(&read_head_hash()?)
+/// How much `gitlet reset` resets besides the checked out branch.
+pub enum ResetMode {
+    /// Nothing else, so that the changes since the target commit end up staged.
+    Soft,
+    /// The staging area, leaving the changes unstaged in the working tree.
+    Mixed,
+    /// The staging area and the working tree, discarding the changes.
+    Hard,
 }
 
-/// Prints out a log of the commit history starting from the HEAD.
-pub fn log() -> Result<()> {
-    let head_commit = retrieve_head_commit().context("Retrieve head commit for log")?;
-    for c in head_commit.iter() {
-        println!("{c}");
+/// Dispatches for the `gitlet reset` command.
+///
+/// Points the checked out branch, or the HEAD if it is detached, at the given commit, which
+/// defaults to the HEAD, and resets as much else as `mode` says.
+///
+/// # Panics
+///
+/// Returns an error if a hard reset would discard unstaged modifications, unless `force` is set.
+pub fn reset(commit: Option<&str>, mode: ResetMode, force: bool) -> Result<()> {
+    let target = resolve_ref(commit.unwrap_or("HEAD"))?;
+
+    match mode {
+        ResetMode::Soft => {
+            // The index is relative to the HEAD, so it must be rebased onto the target for the
+            // staged files to stay the same.
+            let index = Index::load().context("Load index")?;
+            let staged = index.staged_blobs(&get_commit_blobs(&read_head_hash()?)?);
+            let mut rebased = Index::from_blobs(staged, &get_commit_blobs(&target)?);
+            rebased.stat_cache = index.stat_cache;
+            rebased
+                .save()
+                .context("Save staging area relative to new HEAD")?;
+        }
+        ResetMode::Mixed => index::clear_index().context("Clear the staging area")?,
+        ResetMode::Hard => {
+            if !force {
+                let unstaged = unstaged_modifications().context("Collect unstaged changes")?;
+                if !unstaged.is_empty() {
+                    eprintln!("Your local changes to the following files would be discarded:");
+                    for f in unstaged {
+                        eprintln!("\t {f}");
+                    }
+                    anyhow::bail!("Use --force to discard them");
+                }
+            }
+
+            index::reset_to_head().context("Discard changes to the HEAD commit")?;
+            checkout_commit(&target).context("Check out files of target commit")?;
+        }
+    }
+
+    let mut tx = Transaction::begin();
+    update_head(&mut tx, &target)?;
+    tx.commit().context("Move HEAD to target commit")?;
+
+    if let ResetMode::Hard = mode {
+        println!(
+            "HEAD is now at {} {}",
+            &target[..7],
+            Commit::load(&target)?.summary()
+        );
     }
+
     Ok(())
 }
 
-/// Returns all non-hidden filepaths in the working tree.
+/// Dispatches for the `gitlet revert` command.
 ///
-/// Snippet to skip hidden files: https://docs.rs/walkdir/latest/walkdir/#example-skip-hidden-files-and-directories-on-unix
-fn working_files() -> Result<Vec<PathBuf>> {
-    let repo_root = abs_path_to_repo_root().context("Get repository root directory")?;
-    let all_files = WalkDir::new(&repo_root)
-        .into_iter()
-        .filter_map(Result::ok)
-        .filter(|e| {
-            e.file_type().is_file()
-                && e.file_name()
-                    .to_str()
-                    .map(|s| !s.starts_with("."))
-                    .unwrap_or(false)
-        })
-        .map(|e| PathBuf::from(e.path().strip_prefix(&repo_root).unwrap()))
-        .collect();
+/// Undoes the changes that the given commit made to its parent's files, staging the result and
+/// committing it with a message naming the reverted commit, unless `no_commit` is set. Files
+/// changed again since the commit are merged with their parent's version where the changes do not
+/// overlap.
+///
+/// # Panics
+///
+/// Returns an error, changing nothing, if there are staged changes, if the commit is a merge, or
+/// if reverting would conflict with later changes or overwrite unstaged modifications.
+pub fn revert(hash: &str, no_commit: bool) -> Result<()> {
+    let hash = resolve_ref(hash)?;
+    let reverted = Commit::load(&hash)?;
+    let parent_blobs = match reverted.parents().as_slice() {
+        [] => HashMap::new(),
+        [parent] => get_commit_blobs(parent)?,
+        _ => anyhow::bail!("Cannot revert merge commit {}", &hash[..7]),
+    };
+    anyhow::ensure!(
+        Index::load().context("Load index")?.is_clear(),
+        "Commit or unstage the staged changes before reverting"
+    );
+
+    let head_blobs = get_commit_blobs(&read_head_hash()?)?;
+    let other_label = format!("parent of {} ({})", &hash[..7], reverted.summary());
+    let merged = merge::merge_trees(
+        &get_commit_blobs(&hash)?,
+        &head_blobs,
+        &parent_blobs,
+        &merge::ConflictStyle {
+            current_label: "HEAD",
+            other_label: &other_label,
+            marker_size: 7,
+        },
+    )?;
+    if !merged.conflicts.is_empty() {
+        eprintln!("Reverting the following files conflicts with later changes to them:");
+        for f in &merged.conflicts {
+            eprintln!("\t {}", f.display());
+        }
+        anyhow::bail!("Could not revert {}", &hash[..7]);
+    }
 
-    Ok(all_files)
-}
+    apply_merge(merged, &head_blobs, "revert")?;
 
-/// Returns names of files that are tracked (either by the HEAD or by the index) and have been
-/// changed but not staged, including deleted files, which are marked as such.
+    if !no_commit {
+        commit(CommitOptions {
+            message: Some(format!(
+                "Revert \"{}\"\n\nThis reverts commit {hash}.",
+                reverted.summary()
+            )),
+            ..Default::default()
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Dispatches for the `gitlet cherry-pick` command.
+///
+/// Applies the changes that the given commit made to its parent's files, merging them with the
+/// HEAD's files where both changed, and commits the result with the commit's message. If the
+/// changes conflict, the files are written with conflict markers and the cherry-pick stops,
+/// remembering the commit and the files it created in `.gitlet/CHERRY_PICK_HEAD` until
+/// [`cherry_pick_continue`] or [`cherry_pick_abort`]. The commit keeps the picked commit's author.
+///
+/// # Panics
+///
+/// Returns an error, changing nothing, if there are staged changes, if the commit is a merge, if a
+/// cherry-pick is already in progress, or if the changes would overwrite unstaged modifications.
+pub fn cherry_pick(hash: &str) -> Result<()> {
+    let cherry_pick_head = abs_path_to_repo_root()?.join(".gitlet/CHERRY_PICK_HEAD");
+    anyhow::ensure!(
+        !cherry_pick_head.exists(),
+        "A cherry-pick is in progress. Use --continue or --abort"
+    );
+
+    let hash = resolve_ref(hash)?;
+    let picked = Commit::load(&hash)?;
+    let parent_blobs = match picked.parents().as_slice() {
+        [] => HashMap::new(),
+        [parent] => get_commit_blobs(parent)?,
+        _ => anyhow::bail!("Cannot cherry-pick merge commit {}", &hash[..7]),
+    };
+    anyhow::ensure!(
+        Index::load().context("Load index")?.is_clear(),
+        "Commit or unstage the staged changes before cherry-picking"
+    );
+
+    let head_blobs = get_commit_blobs(&read_head_hash()?)?;
+    let other_label = format!("{} ({})", &hash[..7], picked.summary());
+    let merged = merge::merge_trees(
+        &parent_blobs,
+        &head_blobs,
+        &get_commit_blobs(&hash)?,
+        &merge::ConflictStyle {
+            current_label: "HEAD",
+            other_label: &other_label,
+            marker_size: 7,
+        },
+    )?;
+    let conflicts = merged.conflicts.clone();
+    // The files new to the HEAD, which an abort deletes. Any untracked file in the way stops
+    // apply_merge, so none existed before.
+    let created: Vec<String> = merged
+        .changes
+        .iter()
+        .filter(|(f, change)| {
+            !head_blobs.contains_key(f) && !matches!(change, merge::FileMerge::Delete)
+        })
+        .map(|(f, _)| f.to_string_lossy().into_owned())
+        .collect();
+
+    apply_merge(merged, &head_blobs, "cherry-pick")?;
+
+    if !conflicts.is_empty() {
+        let state = std::iter::once(hash.clone())
+            .chain(created)
+            .collect::<Vec<_>>();
+        fs::write(&cherry_pick_head, state.join("\n")).context("Write .gitlet/CHERRY_PICK_HEAD")?;
+        eprintln!("Cherry-picking the following files conflicts with changes to them:");
+        for f in &conflicts {
+            eprintln!("\t {}", f.display());
+        }
+        anyhow::bail!(
+            "Could not apply {other_label}. Resolve the conflicts and stage the files, then run \
+             'gitlet cherry-pick --continue', or run 'gitlet cherry-pick --abort' to give up"
+        );
+    }
+
+    commit(CommitOptions {
+        message: Some(picked.message().to_string()),
+        author: Some(picked.author_identity()),
+        ..Default::default()
+    })
+}
+
+/// Commits the staged resolution of a cherry-pick stopped by conflicts, with the message of the
+/// commit being cherry-picked.
+///
+/// # Panics
+///
+/// Returns an error if no cherry-pick is in progress or if staged files still contain conflict
+/// markers.
+pub fn cherry_pick_continue() -> Result<()> {
+    let cherry_pick_head = abs_path_to_repo_root()?.join(".gitlet/CHERRY_PICK_HEAD");
+    let (hash, _) = read_cherry_pick_head(&cherry_pick_head)?;
+    let picked = Commit::load(&hash)?;
+
+    commit(CommitOptions {
+        message: Some(picked.message().to_string()),
+        author: Some(picked.author_identity()),
+        ..Default::default()
+    })?;
+
+    fs::remove_file(cherry_pick_head).context("Delete .gitlet/CHERRY_PICK_HEAD")
+}
+
+/// Gives up a cherry-pick stopped by conflicts, restoring the files it changed to their versions in
+/// the HEAD commit, deleting those it created, and clearing the staging area. Other files are left
+/// alone.
+///
+/// # Panics
+///
+/// Returns an error if no cherry-pick is in progress.
+pub fn cherry_pick_abort() -> Result<()> {
+    let repo_root = abs_path_to_repo_root()?;
+    let cherry_pick_head = repo_root.join(".gitlet/CHERRY_PICK_HEAD");
+    let (hash, created) = read_cherry_pick_head(&cherry_pick_head)?;
+
+    let picked_blobs = get_commit_blobs(&hash)?;
+    let parent_blobs = match Commit::load(&hash)?.parents().first() {
+        Some(parent) => get_commit_blobs(parent)?,
+        None => HashMap::new(),
+    };
+    let head_blobs = get_commit_blobs(&read_head_hash()?)?;
+
+    let changed = picked_blobs.keys().chain(parent_blobs.keys()).filter(|f| {
+        match (picked_blobs.get(*f), parent_blobs.get(*f)) {
+            (Some(picked), Some(parent)) => {
+                picked.hash != parent.hash || picked.mode != parent.mode
+            }
+            _ => true,
+        }
+    });
+    for f in changed {
+        if let Some(blob) = head_blobs.get(f) {
+            blob.restore(&repo_root.join(f))?;
+        }
+    }
+    for f in created {
+        let fpath = repo_root.join(&f);
+        if fpath.exists() {
+            fs::remove_file(&fpath).with_context(|| format!("Delete '{}'", f.display()))?;
+        }
+    }
+
+    index::clear_index().context("Clear the staging area")?;
+    fs::remove_file(cherry_pick_head).context("Delete .gitlet/CHERRY_PICK_HEAD")
+}
+
+/// Reads `.gitlet/CHERRY_PICK_HEAD`, whose first line is the hash of the commit being
+/// cherry-picked, followed by the paths of the files the cherry-pick created, one per line.
+///
+/// # Panics
+///
+/// Returns an error if no cherry-pick is in progress.
+fn read_cherry_pick_head(path: &Path) -> Result<(String, Vec<PathBuf>)> {
+    anyhow::ensure!(path.exists(), "No cherry-pick is in progress");
+    let content = fs::read_to_string(path).context("Read .gitlet/CHERRY_PICK_HEAD")?;
+    let mut lines = content.lines();
+    let hash = lines.next().unwrap_or_default().to_string();
+
+    Ok((hash, lines.map(PathBuf::from).collect()))
+}
+
+/// Writes the changes of a merge into the working tree and stages those without conflicts, so that
+/// the staging area holds them relative to the files of the HEAD commit, `head_blobs`.
+///
+/// # Panics
+///
+/// Returns an error, changing nothing, if a file to be changed has unstaged modifications, or a
+/// file to be created already exists untracked, either of which `command` would overwrite.
+pub(crate) fn apply_merge(
+    merged: merge::TreeMerge,
+    head_blobs: &HashMap<PathBuf, Blob>,
+    command: &str,
+) -> Result<()> {
+    let unstaged: Vec<PathBuf> = unstaged_modifications()
+        .context("Collect paths of unstaged modified files")?
+        .iter()
+        .map(|f| PathBuf::from(f.trim_end_matches(" (deleted)")))
+        .filter(|f| merged.changes.iter().any(|(path, _)| path == f))
+        .collect();
+    if !unstaged.is_empty() {
+        eprintln!("Your local changes to the following files would be overwritten by {command}:");
+        for f in unstaged {
+            eprintln!("\t {}", f.display());
+        }
+        anyhow::bail!("");
+    }
+
+    let repo_root = abs_path_to_repo_root().context("Get repository root directory")?;
+    let mut index = Index::load().context("Load index")?;
+    let mut staged = index.staged_blobs(head_blobs);
+
+    let untracked: Vec<&PathBuf> = merged
+        .changes
+        .iter()
+        .filter(|(_, change)| !matches!(change, merge::FileMerge::Delete))
+        .map(|(f, _)| f)
+        .filter(|f| !staged.contains_key(*f) && repo_root.join(f).exists())
+        .collect();
+    if !untracked.is_empty() {
+        eprintln!("The following untracked working tree files would be overwritten by {command}:");
+        for f in untracked {
+            eprintln!("\t {}", f.display());
+        }
+        anyhow::bail!("");
+    }
+
+    for (f, change) in merged.changes {
+        let fpath = repo_root.join(&f);
+        match change {
+            merge::FileMerge::Take(blob) => {
+                blob.restore(&fpath)?;
+                staged.insert(f, blob);
+            }
+            merge::FileMerge::Write(content) => {
+                if let Some(dir) = fpath.parent() {
+                    fs::create_dir_all(dir)
+                        .with_context(|| format!("Create directory '{}'", dir.display()))?;
+                }
+                fs::write(&fpath, content)
+                    .with_context(|| format!("Write merged '{}'", f.display()))?;
+                if !merged.conflicts.contains(&f) {
+                    let blob = Blob::new(&fpath)?;
+                    blob.save(&fpath)?;
+                    staged.insert(f, blob);
+                }
+            }
+            merge::FileMerge::Delete => {
+                if fpath.exists() {
+                    fs::remove_file(&fpath).with_context(|| format!("Delete '{}'", f.display()))?;
+                }
+                staged.remove(&f);
+            }
+        }
+    }
+
+    let stat_cache = std::mem::take(&mut index.stat_cache);
+    index = Index::from_blobs(staged, head_blobs);
+    index.stat_cache = stat_cache;
+    index.save().context("Stage merged changes")
+}
+
+/// Dispatches for the `gitlet restore` command.
+///
+/// Discards the unstaged changes to the file by restoring its staged version, or its version in
+/// the HEAD commit if it has none. When `source` is given, the file is restored to its version in
+/// that commit instead. When `staged` is set, the file is unstaged, leaving the working tree alone.
+pub fn restore(filepath: &str, source: Option<&str>, staged: bool) -> Result<()> {
+    if staged {
+        return index::action(IndexAction::Unstage, &[filepath.to_string()], false, None);
+    }
+
+    let fpath_from_root = path_from_repo_root(Path::new(filepath))?;
+    if let Some(source) = source {
+        return restore_file(&fpath_from_root, &resolve_ref(source)?);
+    }
+
+    let index = Index::load().context("Load index")?;
+    match index.additions.get(&fpath_from_root) {
+        Some(blob) => blob.restore(&abs_path_working_file(&fpath_from_root)?),
+        None => restore_file(&fpath_from_root, &read_head_hash()?),
+    }
+}
+
+/// Overwrites the working tree file at `filepath`, relative to the repository root, with its
+/// version in the given commit.
+///
+/// # Panics
+///
+/// Returns an error if the commit does not track the file.
+pub(crate) fn restore_file(filepath: &Path, commit_hash: &str) -> Result<()> {
+    let blobs = get_commit_blobs(commit_hash).context("Get commit's list of tracked files")?;
+    let blob = blobs.get(filepath).with_context(|| {
+        format!(
+            "'{}' is not tracked by commit {}",
+            filepath.display(),
+            &commit_hash[..7]
+        )
+    })?;
+
+    blob.restore(&abs_path_working_file(filepath)?)
+}
+
+/// Returns true if the given file is tracked.
+///
+/// A file is tracked if it is represented either by the HEAD commit or by the index.
+pub(crate) fn is_tracked_by_head(filepath: &Path) -> bool {
+    let Ok(head_commit) = retrieve_head_commit() else {
+        return false;
+    };
+
+    head_commit.tracks(filepath)
+}
+
+pub(crate) fn read_head_hash() -> Result<String> {
+    let repo_root = abs_path_to_repo_root()?;
+
+    let branch_name = read_head_file()?;
+    if is_commit_hash(&branch_name) {
+        return Ok(branch_name);
+    }
+
+    let branch_ref = std::fs::read_to_string(repo_root.join(".gitlet/refs").join(branch_name))
+        .context("Read current HEAD commit")?;
+
+    if !branch_ref.is_empty() && branch_ref.len() != 40 {
+        anyhow::bail!("Invalid commit");
+    }
+
+    Ok(branch_ref)
+}
+
+/// Prints the hashes of the commits named by `rev_range`, newest first, and never before any of
+/// their descendants, even when made in the same second.
+///
+/// The range is either a single commit, naming it and all of its ancestors, or `<from>..<to>`,
+/// naming the commits reachable from `<to>` but not from `<from>`. With `ancestry_path`, a range is
+/// further limited to the commits that descend from `<from>`. `max_count` limits the number of
+/// commits, and `count` prints only their number.
+pub fn rev_list(
+    rev_range: &str,
+    count: bool,
+    max_count: Option<usize>,
+    ancestry_path: bool,
+) -> Result<()> {
+    let (from, to) = match rev_range.split_once("..") {
+        Some((from, to)) => (Some(resolve_ref(from)?), resolve_ref(to)?),
+        None => (None, resolve_ref(rev_range)?),
+    };
+
+    let mut hashes = commit::ancestors(&to).context("Collect reachable commits")?;
+    if let Some(from) = &from {
+        for hash in commit::ancestors(from).context("Collect excluded commits")? {
+            hashes.remove(&hash);
+        }
+        if ancestry_path {
+            let mut on_path = HashSet::new();
+            for hash in hashes {
+                if is_ancestor_of(from, &hash)? {
+                    on_path.insert(hash);
+                }
+            }
+            hashes = on_path;
+        }
+    }
+
+    let commits: HashMap<String, Commit> = hashes
+        .iter()
+        .map(|hash| Ok((hash.clone(), Commit::load(hash)?)))
+        .collect::<Result<_>>()
+        .context("Load commits")?;
+    let mut order = commit::topological_order(&hashes, &commits);
+    order.truncate(max_count.unwrap_or(usize::MAX));
+
+    if count {
+        println!("{}", order.len());
+    } else {
+        for hash in order {
+            println!("{hash}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints which of the named branches each of their recent commits is reachable from.
+///
+/// The branches are listed first, the current one marked with `*` and the others with `!`. Then
+/// each commit reachable from some but not all of them is printed newest first, with no commit
+/// before its descendants, under a column per branch holding `*` (for the current branch) or `+`
+/// if the commit is reachable from that branch. The last row is their newest common commit, i.e.
+/// their merge base, marked with `-` in every column.
+///
+/// Reachability is looked up in the set of each branch's ancestors, collected once per branch,
+/// rather than by walking the history from each branch for every commit as
+/// [`commit::is_ancestor_of`] would.
+///
+/// Without `branches`, all local branches are shown, along with the remote tracking branches if
+/// `all` is set. `topics` leaves out the commits reachable from the first branch. Merge commits
+/// reachable from only one of the branches are left out unless `sparse` is set.
+pub fn show_branch(branches: &[String], all: bool, topics: bool, sparse: bool) -> Result<()> {
+    let gitlet_dir = abs_path_to_repo_root()
+        .context("Get absolute path to repo root")?
+        .join(".gitlet");
+
+    let tips: Vec<(String, String)> = if branches.is_empty() {
+        read_refs(&gitlet_dir)
+            .context("Read refs")?
+            .into_iter()
+            .filter(|(refname, hash)| {
+                is_commit_hash(hash)
+                    && (ref_kind_matches(refname, true, false)
+                        || (all && refname.starts_with("refs/remotes/")))
+            })
+            .map(|(refname, hash)| {
+                let name = refname.trim_start_matches("refs/");
+                (name.trim_start_matches("remotes/").to_string(), hash)
+            })
+            .collect()
+    } else {
+        branches
+            .iter()
+            .map(|name| Ok((name.clone(), resolve_ref(name)?)))
+            .collect::<Result<_>>()?
+    };
+    anyhow::ensure!(!tips.is_empty(), "No branches with commits to show");
+
+    let current = current_branch()?;
+    let reachable: Vec<HashSet<String>> = tips
+        .iter()
+        .map(|(_, hash)| commit::ancestors(hash))
+        .collect::<Result<_>>()
+        .context("Collect reachable commits")?;
+
+    let mut commits: HashMap<String, Commit> = HashMap::new();
+    for hash in reachable.iter().flatten() {
+        if !commits.contains_key(hash) {
+            commits.insert(hash.clone(), Commit::load(hash)?);
+        }
+    }
+
+    let tip_hashes: Vec<&str> = tips.iter().map(|(_, hash)| hash.as_str()).collect();
+    let common = Commit::common_ancestors(&tip_hashes).context("Find common ancestors")?;
+    let common_set: HashSet<&String> = common.iter().collect();
+    let mut shown: HashSet<String> = commits.keys().cloned().collect();
+    shown.retain(|hash| !(common_set.contains(hash) || topics && reachable[0].contains(hash)));
+    if !sparse {
+        shown.retain(|hash| {
+            commits[hash].parents().len() < 2
+                || reachable.iter().filter(|r| r.contains(hash)).count() > 1
+        });
+    }
+
+    // Name each commit after the first branch whose first parents lead to it, e.g. "main~2".
+    let mut names: HashMap<&str, String> = HashMap::new();
+    for (name, hash) in tips.iter() {
+        let mut hash = hash.as_str();
+        for n in 0.. {
+            let Some(commit) = commits.get(hash) else {
+                break;
+            };
+            names.entry(hash).or_insert_with(|| match n {
+                0 => name.clone(),
+                n => format!("{name}~{n}"),
+            });
+            match commit.parents().first() {
+                Some(parent) => hash = parent,
+                None => break,
+            }
+        }
+    }
+
+    for (i, (name, hash)) in tips.iter().enumerate() {
+        let marker = if current.as_ref() == Some(name) {
+            '*'
+        } else {
+            '!'
+        };
+        println!(
+            "{}{marker} [{name}] {}",
+            " ".repeat(i),
+            commits[hash].summary()
+        );
+    }
+    println!("{}", "-".repeat(tips.len()));
+
+    let row = |hash: &str, marks: String| {
+        let name = names
+            .get(hash)
+            .cloned()
+            .unwrap_or_else(|| hash[..7].to_string());
+        println!("{marks} [{name}] {}", commits[hash].summary());
+    };
+
+    for hash in commit::topological_order(&shown, &commits) {
+        let marks: String = tips
+            .iter()
+            .zip(reachable.iter())
+            .map(|((name, _), r)| match r.contains(&hash) {
+                false => ' ',
+                true if current.as_ref() == Some(name) => '*',
+                true => '+',
+            })
+            .collect();
+        row(&hash, marks);
+    }
+
+    // The merge base is the common commit that no other common commit descends from.
+    if let Some(hash) = common.first() {
+        row(hash, "-".repeat(tips.len()));
+    }
+
+    Ok(())
+}
+
+/// Lists the refs in the repository along with the hashes to which they point, sorted by ref name.
+///
+/// `heads` and `tags` limit the output to branches and tags, respectively. `hash_only` omits the
+/// ref names, and `abbrev` shortens the hashes to that many characters. With `verify`, every ref
+/// must hold a valid hash.
+///
+/// Returns false if no refs match, for which the command exits with status 1.
+pub fn show_ref(
+    heads: bool,
+    tags: bool,
+    hash_only: bool,
+    verify: bool,
+    abbrev: Option<usize>,
+) -> Result<bool> {
+    let repo_root = abs_path_to_repo_root().context("Get absolute path to repo root")?;
+
+    let refs: Vec<(String, String)> = read_refs(&repo_root.join(".gitlet"))
+        .context("Read refs")?
+        .into_iter()
+        .filter(|(refname, _)| ref_kind_matches(refname, heads, tags))
+        .collect();
+
+    let mut found = false;
+    for (refname, hash) in refs {
+        if !is_commit_hash(&hash) {
+            // Branches without commits have no hash to show.
+            if verify {
+                anyhow::bail!("'{refname}' does not point to a valid commit hash");
+            }
+            continue;
+        }
+
+        found = true;
+        let hash = &hash[..abbrev.unwrap_or(40).clamp(4, 40)];
+        if hash_only {
+            println!("{hash}");
+        } else {
+            println!("{hash} {refname}");
+        }
+    }
+
+    Ok(found)
+}
+
+/// Lists the refs of the repository at the local path `remote` along with the hashes to which they
+/// point, without fetching anything from it.
+///
+/// `heads` and `tags` limit the output to branches and tags, respectively, and `pattern`, a glob, to
+/// refs whose name or trailing path components match it.
+///
+/// Returns false if no refs match, for which the command exits with status 2.
+pub fn ls_remote(remote: &Path, heads: bool, tags: bool, pattern: Option<&str>) -> Result<bool> {
+    let gitlet_dir = remote.join(".gitlet");
+    if !gitlet_dir.is_dir() {
+        anyhow::bail!(
+            "'{}' does not appear to be a gitlet repository",
+            remote.display()
+        );
+    }
+
+    let pattern = pattern
+        .map(glob::Pattern::new)
+        .transpose()
+        .context("Parse ref pattern")?;
+
+    let mut found = false;
+    for (refname, hash) in read_refs(&gitlet_dir).context("Read remote refs")? {
+        if !is_commit_hash(&hash) || !ref_kind_matches(&refname, heads, tags) {
+            continue;
+        }
+        if let Some(pattern) = &pattern {
+            // As in Git, the pattern may match any number of trailing path components.
+            let mut tails = std::iter::once(refname.as_str())
+                .chain(refname.match_indices('/').map(|(i, _)| &refname[i + 1..]));
+            if !tails.any(|tail| pattern.matches(tail)) {
+                continue;
+            }
+        }
+
+        found = true;
+        println!("{hash} {refname}");
+    }
+
+    Ok(found)
+}
+
+/// Writes the static files that allow the repository to be served by a plain HTTP server:
+/// `.gitlet/info/refs`, which lists every branch and tag as `<hash> <refname>`, and
+/// `.gitlet/objects/info/packs`, which lists the repository's pack files.
+///
+/// Gitlet stores every object as its own file, so the list of packs is always empty for now.
+pub fn update_server_info() -> Result<()> {
+    let gitlet_dir = abs_path_to_repo_root()
+        .context("Get absolute path to repo root")?
+        .join(".gitlet");
+
+    let mut info_refs = String::new();
+    for (refname, hash) in read_refs(&gitlet_dir).context("Read refs")? {
+        if is_commit_hash(&hash) && ref_kind_matches(&refname, true, true) {
+            info_refs.push_str(&format!("{hash} {refname}\n"));
+        }
+    }
+
+    let mut tx = Transaction::begin();
+    tx.stage_file_write(gitlet_dir.join("info/refs"), info_refs);
+    tx.stage_file_write(gitlet_dir.join("objects/info/packs"), "\n");
+    tx.commit().context("Write server info files")
+}
+
+/// Returns true if the ref should be listed given the `heads` and `tags` filters, which limit
+/// listing to branches and tags, respectively. Either matches when neither is set.
+fn ref_kind_matches(refname: &str, heads: bool, tags: bool) -> bool {
+    let is_head = !refname.trim_start_matches("refs/").contains('/');
+    let is_tag = refname.starts_with("refs/tags/");
+    (!heads && !tags) || (heads && is_head) || (tags && is_tag)
+}
+
+/// Collects information about the gitlet installation and the repository, including its settings
+/// with `user.email` masked, and writes it to `gitlet-diagnostics-<timestamp>.zip` in the current
+/// directory, for attaching to bug reports.
+pub fn diagnose() -> Result<()> {
+    let repo_root = abs_path_to_repo_root().context("Get absolute path to repo root")?;
+    let gitlet_dir = repo_root.join(".gitlet");
+
+    let mut report = String::new();
+    report.push_str(&format!("gitlet version {}\n", env!("CARGO_PKG_VERSION")));
+    report.push_str(&format!(
+        "os: {} ({})\n",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    ));
+    report.push_str(&format!(
+        "filesystem: {}\n",
+        filesystem_type(&repo_root).unwrap_or_else(|| "unknown".to_string())
+    ));
+
+    // Objects and refs
+    let branch_count = fs::read_dir(gitlet_dir.join("refs"))
+        .context("Read refs directory")?
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_ok_and(|f| f.is_file()))
+        .count();
+    report.push_str(&format!("branches: {branch_count}\n"));
+
+    let mut total_size = 0;
+    for component in ["commits", "blobs", "refs", "index"] {
+        let (count, size) = count_files(&gitlet_dir.join(component));
+        total_size += size;
+        match component {
+            "commits" | "blobs" => {
+                // One file per object, e.g. "commit files: 3".
+                let kind = component.trim_end_matches('s');
+                report.push_str(&format!("{kind} files: {count} ({size} bytes)\n"))
+            }
+            _ => report.push_str(&format!("{component} size: {size} bytes\n")),
+        }
+    }
+    report.push_str(&format!("total size: {total_size} bytes\n"));
+
+    match Index::load() {
+        Ok(index) => report.push_str(&format!(
+            "staged: {} addition(s), {} removal(s)\n",
+            index.additions.len(),
+            index.removals.len()
+        )),
+        Err(e) => report.push_str(&format!("index: unreadable ({e:#})\n")),
+    }
+
+    match Config::load(ConfigScope::Merged) {
+        Ok(config) => {
+            report.push_str("\n=== config ===\n");
+            for (key, value) in config.entries() {
+                let value = match key {
+                    "user.email" => mask_email(value),
+                    _ => value.to_string(),
+                };
+                report.push_str(&format!("{key} = {value}\n"));
+            }
+        }
+        Err(e) => report.push_str(&format!("config: unreadable ({e:#})\n")),
+    }
+
+    let gc_log = gitlet_dir.join("gc.log");
+    if let Ok(log) = fs::read_to_string(gc_log) {
+        let lines: Vec<&str> = log.lines().collect();
+        report.push_str("\n=== gc.log (last 20 lines) ===\n");
+        for line in &lines[lines.len().saturating_sub(20)..] {
+            report.push_str(line);
+            report.push('\n');
+        }
+    }
+
+    let filename = format!(
+        "gitlet-diagnostics-{}.zip",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    );
+    let file = fs::File::create(&filename)
+        .with_context(|| format!("Create diagnostics file '{filename}'"))?;
+    let mut zip = zip::ZipWriter::new(file);
+    zip.start_file("diagnostics.txt", zip::write::SimpleFileOptions::default())
+        .context("Add report to diagnostics archive")?;
+    zip.write_all(report.as_bytes())
+        .context("Write report to diagnostics archive")?;
+    zip.finish().context("Finish diagnostics archive")?;
+
+    println!("Created diagnostics file: ./{filename}");
+
+    Ok(())
+}
+
+/// Fixes known kinds of repository corruption, printing each repair made. With `dry_run`, only
+/// prints the repairs that would be made.
+///
+/// The repairs are:
+/// - a missing HEAD is recreated pointing to `main`;
+/// - a missing `main` branch is recreated pointing to the most recent commit that no other commit
+///   has as a parent;
+/// - a stale `.gitlet/index.lock`, older than one hour, is removed;
+/// - a missing or unreadable index is replaced with a clear one.
+pub fn repair(dry_run: bool) -> Result<()> {
+    let repo_root = abs_path_to_repo_root().context("Get absolute path to repo root")?;
+    let gitlet_dir = repo_root.join(".gitlet");
+    let prefix = if dry_run { "Would repair" } else { "Repaired" };
+    let mut repaired = false;
+
+    let head = gitlet_dir.join("HEAD");
+    if !head.exists() {
+        println!("{prefix}: recreate missing HEAD pointing to 'main'");
+        if !dry_run {
+            fs::write(&head, "main").context("Write 'main' to '.gitlet/HEAD'")?;
+        }
+        repaired = true;
+    }
+
+    let main_ref = gitlet_dir.join("refs/main");
+    if !main_ref.exists() {
+        let tip = latest_tip_commit().context("Find most recent commit")?;
+        println!(
+            "{prefix}: recreate missing branch 'main' pointing to {}",
+            if tip.is_empty() { "no commit" } else { &tip }
+        );
+        if !dry_run {
+            fs::create_dir_all(gitlet_dir.join("refs")).context("Create '.gitlet/refs/'")?;
+            fs::write(&main_ref, tip).context("Write '.gitlet/refs/main'")?;
+        }
+        repaired = true;
+    }
+
+    let lock = gitlet_dir.join("index.lock");
+    let lock_age = fs::metadata(&lock)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok());
+    if lock_age.is_some_and(|age| age > std::time::Duration::from_secs(60 * 60)) {
+        println!("{prefix}: remove stale index.lock");
+        if !dry_run {
+            fs::remove_file(&lock).context("Delete '.gitlet/index.lock'")?;
+        }
+        repaired = true;
+    }
+
+    let index_file = gitlet_dir.join("index");
+    if !index_file.exists() {
+        println!("{prefix}: recreate missing index");
+        if !dry_run {
+            Index::load().context("Create a clear index")?;
+        }
+        repaired = true;
+    } else if Index::load().is_err() {
+        println!("{prefix}: replace unreadable index with a clear one");
+        if !dry_run {
+            index::clear_index().context("Delete unreadable index")?;
+            Index::load().context("Create a clear index")?;
+        }
+        repaired = true;
+    }
+
+    if !repaired {
+        println!("Nothing to repair");
+    }
+
+    Ok(())
+}
+
+/// Returns the hash of the most recent commit that is not the parent of any other commit, or an
+/// empty string if there are no commits.
+fn latest_tip_commit() -> Result<String> {
+    let commits_dir = abs_path_to_repo_root()?.join(".gitlet/commits");
+
+    let mut commits = Vec::new();
+    for entry in WalkDir::new(&commits_dir)
+        .min_depth(2)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+    {
+        let rel_path = entry.path().strip_prefix(&commits_dir)?;
+        let hash: String = rel_path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect();
+        if let Ok(commit) = Commit::load(&hash) {
+            commits.push(commit);
+        }
+    }
+
+    let parents: HashSet<&str> = commits.iter().flat_map(|c| c.parents()).collect();
+
+    Ok(commits
+        .iter()
+        .filter(|c| !parents.contains(c.hash.as_str()))
+        .max_by_key(|c| c.timestamp())
+        .map(|c| c.hash.clone())
+        .unwrap_or_default())
+}
+
+/// Returns the number of files under `path` (which may itself be a file) and their total size.
+fn count_files(path: &Path) -> (usize, u64) {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+        .fold((0, 0), |(count, size), e| {
+            (count + 1, size + e.metadata().map(|m| m.len()).unwrap_or(0))
+        })
+}
+
+/// Hides all but the first character of the local part of `email`, e.g. `a***@example.com`, so that
+/// it can be shared in a diagnostics report.
+fn mask_email(email: &str) -> String {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            let first: String = local.chars().take(1).collect();
+            format!("{first}***@{domain}")
+        }
+        None => "***".to_string(),
+    }
+}
+
+/// Returns the type of the filesystem on which `path` resides, as listed in `/proc/mounts`.
+fn filesystem_type(path: &Path) -> Option<String> {
+    let mounts = fs::read_to_string("/proc/mounts").ok()?;
+
+    // The mount point with the longest matching prefix is the one containing the path.
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().skip(1);
+            Some((fields.next()?, fields.next()?))
+        })
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.len())
+        .map(|(_, fs_type)| fs_type.to_string())
+}
+
+/// Returns every ref under the given `.gitlet` directory's `refs/` as a pair of its name, relative
+/// to the `.gitlet` directory (e.g. `refs/main`), and its contents, sorted by name.
+fn read_refs(gitlet_dir: &Path) -> Result<Vec<(String, String)>> {
+    let mut refs = Vec::new();
+
+    for entry in WalkDir::new(gitlet_dir.join("refs"))
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+    {
+        let refname = entry
+            .path()
+            .strip_prefix(gitlet_dir)
+            .context("Strip .gitlet directory from ref path")?
+            .to_string_lossy()
+            .into_owned();
+        let hash =
+            fs::read_to_string(entry.path()).with_context(|| format!("Read ref '{refname}'"))?;
+        refs.push((refname, hash.trim().to_string()));
+    }
+
+    refs.sort();
+
+    Ok(refs)
+}
+
+/// Returns true if the given string is a full, 40-character hexadecimal commit hash.
+pub(crate) fn is_commit_hash(hash: &str) -> bool {
+    hash.len() == 40 && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Resolves a reference to the hash of the commit it names.
+///
+/// The reference may be `HEAD`, a branch name, a tag name, or a commit hash, which may be abbreviated to as few
+/// as four characters so long as it is unambiguous. It may be followed by any number of `~<n>`,
+/// selecting the n-th generation ancestor by first parents, and `^<n>`, selecting the n-th parent,
+/// where `n` defaults to 1.
+pub(crate) fn resolve_ref(name: &str) -> Result<String> {
+    if let Some(split) = name.find(['~', '^']) {
+        let (base, mut suffixes) = name.split_at(split);
+        let mut hash = resolve_ref(base)?;
+
+        while let Some(op) = suffixes.chars().next() {
+            let digits = suffixes[1..]
+                .find(|c: char| !c.is_ascii_digit())
+                .map_or(suffixes.len(), |i| i + 1);
+            let n: usize = match &suffixes[1..digits] {
+                "" => 1,
+                n => n
+                    .parse()
+                    .with_context(|| format!("invalid reference: '{name}'"))?,
+            };
+            suffixes = &suffixes[digits..];
+
+            let (generations, parent) = match op {
+                '~' => (n, 0),
+                '^' if n == 0 => continue,
+                '^' => (1, n - 1),
+                _ => anyhow::bail!("invalid reference: '{name}'"),
+            };
+            for _ in 0..generations {
+                let commit = Commit::load(&hash).with_context(|| format!("Load commit {hash}"))?;
+                hash = match commit.parents().get(parent) {
+                    Some(parent) => parent.to_string(),
+                    None => anyhow::bail!("invalid reference: '{name}'"),
+                };
+            }
+        }
+
+        return Ok(hash);
+    }
+
+    if name == "HEAD" {
+        return read_head_hash().context("Get HEAD commit hash");
+    }
+
+    let repo_root = abs_path_to_repo_root().context("Get absolute path to repo root")?;
+
+    let branch_path = repo_root.join(".gitlet/refs").join(name);
+    if branch_path.is_file() {
+        return fs::read_to_string(&branch_path)
+            .with_context(|| format!("Read branch ref '{name}'"));
+    }
+
+    let tag_path = repo_root.join(".gitlet/refs/tags").join(name);
+    if tag_path.is_file() {
+        return fs::read_to_string(&tag_path).with_context(|| format!("Read tag ref '{name}'"));
+    }
+
+    if name.len() >= 4 && name.len() <= 40 && name.chars().all(|c| c.is_ascii_hexdigit()) {
+        let commit_dir = repo_root.join(".gitlet/commits").join(&name[..2]);
+        let matches: Vec<String> = read_dir(&commit_dir)
+            .into_iter()
+            .flatten()
+            .filter_map(Result::ok)
+            .map(|e| format!("{}{}", &name[..2], e.file_name().to_string_lossy()))
+            .filter(|hash| hash.starts_with(name))
+            .collect();
+
+        match matches.len() {
+            0 => {}
+            1 => return Ok(matches.into_iter().next().unwrap()),
+            _ => anyhow::bail!("short commit hash '{name}' is ambiguous"),
+        }
+    }
+
+    anyhow::bail!("invalid reference: '{name}'")
+}
+
+/// Returns the commit referenced by the HEAD file's hash.
+fn retrieve_head_commit() -> Result<Commit> {
+    Commit::load(&read_head_hash()?)
+}
+
+/// Options for `gitlet log`.
+#[derive(Default)]
+pub struct LogOptions {
+    /// Leave out this many commits, counting from the HEAD.
+    pub skip: usize,
+    /// Print no more than this many commits after those skipped.
+    pub max_count: Option<usize>,
+    /// Print each commit in this format, either a named one such as `oneline` or
+    /// `format:<string>` (see [`commit::format_commit`]), instead of in full.
+    pub pretty: Option<String>,
+    /// Show no commit before all of its descendants, keeping the commits of a branch together
+    /// rather than interleaved by date.
+    pub topo_order: bool,
+    /// Do not follow merge parents, leaving only the mainline.
+    pub first_parent: bool,
+    /// Show the history of every ref, i.e. of every branch, tag, and remote tracking branch, as
+    /// well as the HEAD's, with the commits of all of them ordered by date.
+    pub all: bool,
+    /// Show only the commits whose message matches one of these regular expressions.
+    pub grep: Vec<String>,
+    /// Show only the commits whose message matches all of the `grep` patterns instead.
+    pub all_match: bool,
+    /// Match the `grep` patterns regardless of case.
+    pub ignore_case: bool,
+}
+
+/// Prints out a log of the commit history starting from the HEAD.
+///
+/// # Panics
+///
+/// Returns an error if `pretty` is not a known format or a `grep` pattern is not a valid regular
+/// expression.
+pub fn log(options: LogOptions) -> Result<()> {
+    let LogOptions {
+        skip,
+        max_count,
+        pretty,
+        topo_order,
+        first_parent,
+        all,
+        grep,
+        all_match,
+        ignore_case,
+    } = options;
+
+    let patterns = grep
+        .iter()
+        .map(|pattern| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(ignore_case)
+                .build()
+                .with_context(|| format!("Invalid pattern for --grep: '{pattern}'"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let shown = |c: &Commit| {
+        let mut matches = patterns.iter().map(|re| re.is_match(c.message()));
+        if all_match {
+            matches.all(|m| m)
+        } else {
+            patterns.is_empty() || matches.any(|m| m)
+        }
+    };
+
+    let pretty = pretty
+        .map(|pretty| pretty.parse::<commit::Pretty>())
+        .transpose()?;
+    let head_commit = retrieve_head_commit().context("Retrieve head commit for log")?;
+    // The mainline is a single chain, so it is already in topological order.
+    let commits: Box<dyn Iterator<Item = Commit>> = if all {
+        let gitlet_dir = abs_path_to_repo_root()?.join(".gitlet");
+        let mut tips: Vec<String> = read_refs(&gitlet_dir)
+            .context("Read refs")?
+            .into_iter()
+            .map(|(_, hash)| hash)
+            .collect();
+        tips.push(head_commit.hash.clone());
+        let commits = CommitDateIter::new(&tips).context("Collect tips of refs")?;
+        if first_parent {
+            Box::new(commits.first_parent())
+        } else {
+            Box::new(commits)
+        }
+    } else if first_parent {
+        Box::new(head_commit.iter().first_parent())
+    } else if topo_order {
+        Box::new(head_commit.topo_iter().context("Sort commit history")?)
+    } else {
+        Box::new(head_commit.iter())
+    };
+
+    for c in commits
+        .filter(shown)
+        .skip(skip)
+        .take(max_count.unwrap_or(usize::MAX))
+    {
+        match &pretty {
+            Some(pretty) => println!("{}", pretty.format(&c)),
+            None => println!("{c}"),
+        }
+    }
+    Ok(())
+}
+
+/// Dispatches for the `gitlet show` command.
+///
+/// Prints the given commit, which defaults to the HEAD, as `log` does, followed by the diff of the
+/// changes it made to its parent's files, or for a merge commit, to its first parent's files.
+pub fn show(commit: Option<&str>) -> Result<()> {
+    let hash = resolve_ref(commit.unwrap_or("HEAD"))?;
+    anyhow::ensure!(!hash.is_empty(), "There are no commits yet");
+
+    let commit = Commit::load(&hash).with_context(|| format!("Load commit {hash}"))?;
+    let parent_blobs = match commit.parents().first() {
+        Some(parent) => get_commit_blobs(parent)?,
+        None => HashMap::new(),
+    };
+    let diff = diff::diff_trees(&parent_blobs, &get_commit_blobs(&hash)?)
+        .context("Diff commit against its parent")?;
+
+    println!("{commit}");
+    print!("{diff}");
+
+    Ok(())
+}
+
+/// Returns all non-hidden filepaths in the working tree that are not ignored by `.gitletignore`.
+/// Ignored directories are not descended into.
+///
+/// Snippet to skip hidden files: https://docs.rs/walkdir/latest/walkdir/#example-skip-hidden-files-and-directories-on-unix
+fn working_files() -> Result<Vec<PathBuf>> {
+    let repo_root = abs_path_to_repo_root().context("Get repository root directory")?;
+    let ignored = Patterns::load().context("Load .gitletignore")?;
+    let all_files = WalkDir::new(&repo_root)
+        .into_iter()
+        .filter_entry(|e| {
+            let path = e.path().strip_prefix(&repo_root).unwrap();
+            path.as_os_str().is_empty() || !ignored.matches(path, e.file_type().is_dir())
+        })
+        .filter_map(Result::ok)
+        .filter(|e| {
+            e.file_type().is_file()
+                && e.file_name()
+                    .to_str()
+                    .map(|s| !s.starts_with("."))
+                    .unwrap_or(false)
+        })
+        .map(|e| PathBuf::from(e.path().strip_prefix(&repo_root).unwrap()))
+        .collect();
+
+    Ok(all_files)
+}
+
+/// Returns names of files that are tracked (either by the HEAD or by the index) and have been
+/// changed but not staged, including deleted files, which are marked as such.
 fn unstaged_modifications() -> Result<Vec<String>> {
     let mut unstaged: Vec<String> = Vec::new();
 
     // Iterate through all tracked files in the working tree, comparing current hash with both HEAD
-    // and index.
-    let working_files = working_files().context("Collect filepaths in working tree")?;
+    // and index. Tracked files are checked even if they match .gitletignore.
+    let repo_root = abs_path_to_repo_root().context("Get repository root directory")?;
+    let in_working_tree = |f: &Path| repo_root.join(f).is_file();
     let index = Index::load().context("Load index")?;
 
     for (f, tracked_blob) in get_commit_blobs(&read_head_hash()?)
@@ -561,30 +2463,30 @@ fn unstaged_modifications() -> Result<Vec<String>> {
         .iter()
     {
         // If file is in neither the working tree nor staged removals, then it has been deleted.
-        if !working_files.contains(f) && !index.removals.contains(f) {
+        if !in_working_tree(f) && !index.removals.contains(f) {
             let mut deleted_file = String::from(f.to_str().unwrap());
             deleted_file.push_str(" (deleted)");
             unstaged.push(deleted_file);
-        } else if working_files.contains(f) {
+        } else if in_working_tree(f) {
             // Render the filepath to be absolute.
             let abs_fpath = abs_path_working_file(f).context("Create absolute path to file")?;
 
             // Compare first to the index, in case the changes have already been staged.
             // Then compare to last commited blob.
+            // The stat cache spares rehashing files that have not been touched.
             if index.additions.contains_key(f)
-                && !index
-                    .additions
-                    .get(f)
-                    .unwrap()
-                    .hash_same_as_other_file(&abs_fpath)
-                    .unwrap_or(false)
+                && index
+                    .hash_file(f, &abs_fpath)
+                    .map(|hash| index.additions.get(f).unwrap().hash != hash)
+                    .unwrap_or(true)
             {
                 // File has been staged for addition and subsequently changed.
                 unstaged.push(String::from(f.to_str().unwrap()));
             } else if !index.additions.contains_key(f)
-                && !tracked_blob
-                    .hash_same_as_other_file(&abs_fpath)
-                    .context("Compare current file to recent commit version")?
+                && tracked_blob.hash
+                    != index
+                        .hash_file(f, &abs_fpath)
+                        .context("Compare current file to recent commit version")?
             {
                 // File has been modified but not staged for addition.
                 unstaged.push(String::from(f.to_str().unwrap()));
@@ -599,12 +2501,18 @@ fn unstaged_modifications() -> Result<Vec<String>> {
         .iter()
         .filter(|(k, _)| !is_tracked_by_head(k))
     {
-        if !working_files.contains(f) {
+        if !in_working_tree(f) {
             let mut deleted_file = String::from(f.to_str().unwrap());
             deleted_file.push_str(" (deleted)");
             unstaged.push(deleted_file);
-        } else if !staged_blob.hash_same_as_other_file(f).unwrap_or(true) {
-            unstaged.push(String::from(f.to_str().unwrap()));
+        } else {
+            let abs_fpath = abs_path_working_file(f).context("Create absolute path to file")?;
+            if index
+                .hash_file(f, &abs_fpath)
+                .is_ok_and(|hash| staged_blob.hash != hash)
+            {
+                unstaged.push(String::from(f.to_str().unwrap()));
+            }
         }
     }
 
@@ -633,6 +2541,13 @@ mod tests {
 
     use std::fs;
 
+    #[test]
+    fn cleanup_commit_message() {
+        let message = "\n# comment\nSubject  \n\n\n\nBody\n# Another comment\n\n";
+        assert_eq!("Subject\n\nBody", cleanup_message(message));
+        assert_eq!("", cleanup_message("# Only a comment\n\n"));
+    }
+
     #[test]
     fn create_rel_path_from_repo_root() -> Result<()> {
         let tmpdir = assert_fs::TempDir::new()?;
@@ -707,12 +2622,14 @@ mod tests {
             serde_json::to_writer(&mut f, &json).context("Write commit json")?;
 
             let mut head_file = fs::File::create(".gitlet/HEAD").context("Create HEAD file")?;
-            head_file.write(b"main")?;
+            head_file.write_all(b"main")?;
 
             fs::create_dir(".gitlet/refs").context("Create refs directory")?;
             fs::File::create(".gitlet/refs/main").context("Create main branch ref file")?;
 
-            update_head("9f58103e11b63e5ccca06154ab8838be7639a574")?;
+            let mut tx = Transaction::begin();
+            update_head(&mut tx, "9f58103e11b63e5ccca06154ab8838be7639a574")?;
+            tx.commit()?;
 
             assert!(is_tracked_by_head(Path::new("b.txt")));
 
@@ -731,7 +2648,7 @@ mod tests {
             let expected: Vec<PathBuf> = ["a.txt", "b.txt"]
                 .into_iter()
                 .rev()
-                .map(|t| std::path::PathBuf::from(t))
+                .map(std::path::PathBuf::from)
                 .collect();
 
             let actual = working_files()?;
@@ -758,12 +2675,14 @@ mod tests {
             let mut expected: Vec<PathBuf> = filenames
                 .into_iter()
                 .rev()
-                .map(|t| std::path::PathBuf::from(t))
+                .map(std::path::PathBuf::from)
                 .collect();
 
             let mut actual = working_files()?;
 
-            assert_eq!(expected.sort(), actual.sort());
+            expected.sort();
+            actual.sort();
+            assert_eq!(expected, actual);
 
             Ok(())
         })
@@ -777,10 +2696,10 @@ mod tests {
             fs::create_dir(".gitlet/commit")?;
 
             let mut head_file = fs::File::create(".gitlet/HEAD")?;
-            head_file.write(b"main")?;
+            head_file.write_all(b"main")?;
 
             let mut main_ref = fs::File::create(".gitlet/refs/main")?;
-            main_ref.write(b"")?;
+            main_ref.write_all(b"")?;
 
             create_branch("test").context("Create 'test' branch")?;
 
@@ -797,6 +2716,29 @@ mod tests {
         })
     }
 
+    #[test]
+    fn ref_name_rules() {
+        for valid in ["main", "fix-1", "v1.0"] {
+            assert!(check_ref_format(valid, true).is_ok(), "{valid}");
+        }
+        for valid in ["feature/x", "refs/tags/v1.0"] {
+            assert!(check_ref_format(valid, false).is_ok(), "{valid}");
+        }
+        for invalid in [
+            "", "@", "/main", "main/", "a//b", "a..b", "a@{1}", "a.lock", "a.", ".a", "a/.b",
+            "a b", "a\\b", "HEAD~2", "a^", "a:b", "a?", "a*", "a[",
+        ] {
+            assert!(check_ref_format(invalid, false).is_err(), "{invalid}");
+        }
+
+        assert!(check_ref_format("-b", false).is_ok());
+        assert!(check_ref_format("-b", true).is_err());
+        assert!(check_ref_format("HEAD", true).is_err());
+        assert!(check_ref_format("feature/x", true).is_err());
+        assert!(check_ref_format("tags", true).is_err());
+        assert!(check_ref_format("remotes", true).is_err());
+    }
+
     #[test]
     fn new_branch_has_head_commit_hash() -> Result<()> {
         let tmpdir = assert_fs::TempDir::new()?;
@@ -805,10 +2747,10 @@ mod tests {
             fs::create_dir(".gitlet/commit")?;
 
             let mut head_file = fs::File::create(".gitlet/HEAD")?;
-            head_file.write(b"main")?;
+            head_file.write_all(b"main")?;
 
             let mut main_ref = fs::File::create(".gitlet/refs/main")?;
-            main_ref.write(b"0452ef28c90d315dc3e05323c18b2e3724f7b275")?;
+            main_ref.write_all(b"0452ef28c90d315dc3e05323c18b2e3724f7b275")?;
 
             create_branch("test").context("Create 'test' branch")?;
 
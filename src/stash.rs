@@ -0,0 +1,204 @@
+//! Shelves uncommitted changes to tracked files so that they may be reapplied later, perhaps on top
+//! of another commit.
+//!
+//! A stash is an ordinary commit whose parent is the HEAD at the time it was made, whose message
+//! starts with `[STASH]`, and whose files are the working tree versions of the tracked files. The
+//! stashes are kept in `.gitlet/stash` as a stack of commit hashes, one per line, with the most
+//! recent first, so that `stash@{0}` names the first line. The stack is not kept under
+//! `.gitlet/refs/`, where every file is taken for a branch.
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+
+use crate::blob::Blob;
+use crate::commit::{Commit, CommitBuilder, get_commit_blobs};
+use crate::index::{self, Index};
+use crate::merge;
+use crate::repo;
+use crate::transaction::Transaction;
+
+/// Starts the message of every stash commit.
+const PREFIX: &str = "[STASH]";
+
+/// Dispatches for `gitlet stash push`, which is also what `gitlet stash` does.
+///
+/// Saves the working tree versions of the tracked files, including those staged for addition, as a
+/// new stash, and then resets the staging area and the working tree to the HEAD. Files that were
+/// only staged for addition are deleted, since the stash holds them. Untracked files are left
+/// alone. The stash is described by `message` if given, and otherwise by the HEAD commit.
+pub fn push(message: Option<&str>) -> Result<()> {
+    let head_hash = repo::read_head_hash()?;
+    anyhow::ensure!(
+        !head_hash.is_empty(),
+        "You do not have the initial commit yet"
+    );
+    let repo_root = repo::abs_path_to_repo_root()?;
+    let head_blobs = get_commit_blobs(&head_hash)?;
+
+    let index = Index::load().context("Load index")?;
+    let mut stashed: HashMap<PathBuf, Blob> = HashMap::new();
+    for f in index.staged_blobs(&head_blobs).into_keys() {
+        let fpath = repo_root.join(&f);
+        if fpath.is_file() {
+            let blob = Blob::new(&fpath)?;
+            blob.save(&fpath)?;
+            stashed.insert(f, blob);
+        }
+    }
+    let added: Vec<PathBuf> = stashed
+        .keys()
+        .filter(|f| !head_blobs.contains_key(*f))
+        .cloned()
+        .collect();
+
+    let changes = Index::from_blobs(stashed, &head_blobs);
+    if changes.is_clear() {
+        println!("No local changes to save");
+        return Ok(());
+    }
+
+    let branch = repo::current_branch()?.unwrap_or_else(|| "(no branch)".to_string());
+    let description = match message {
+        Some(message) => format!("On {branch}: {message}"),
+        None => format!(
+            "WIP on {branch}: {} {}",
+            &head_hash[..7],
+            Commit::load(&head_hash)?.summary()
+        ),
+    };
+    let stash = CommitBuilder::new()
+        .parent(head_hash)
+        .message(format!("{PREFIX} {description}"))
+        .index(changes)
+        .build()
+        .context("Create stash commit")?;
+
+    let mut stack = read_stack()?;
+    stack.insert(0, stash.hash.clone());
+    let mut tx = Transaction::begin();
+    stash.save(&mut tx).context("Save stash commit")?;
+    tx.stage_file_write(repo_root.join(".gitlet/stash"), stack.join("\n"));
+    tx.commit().context("Push stash onto the stack")?;
+
+    for f in added {
+        fs::remove_file(repo_root.join(&f))
+            .with_context(|| format!("Delete stashed file '{}'", f.display()))?;
+    }
+    index::reset_to_head().context("Reset to the HEAD commit")?;
+
+    println!("Saved working directory and index state {description}");
+
+    Ok(())
+}
+
+/// Dispatches for `gitlet stash pop`.
+///
+/// Applies the changes of the given stash, by default the most recent, to the working tree and
+/// stages them, merging them with the files of the HEAD where both changed. The stash is then
+/// dropped.
+///
+/// # Panics
+///
+/// Returns an error, changing nothing and keeping the stash, if applying it would conflict with
+/// the HEAD's files or overwrite unstaged modifications.
+pub fn pop(stash: Option<&str>) -> Result<()> {
+    let (n, hash) = find(stash)?;
+    let commit = Commit::load(&hash).context("Load stash commit")?;
+    let base_blobs = match commit.parents().first() {
+        Some(parent) => get_commit_blobs(parent)?,
+        None => HashMap::new(),
+    };
+
+    let head_blobs = get_commit_blobs(&repo::read_head_hash()?)?;
+    let merged = merge::merge_trees(
+        &base_blobs,
+        &head_blobs,
+        &get_commit_blobs(&hash)?,
+        &merge::ConflictStyle {
+            current_label: "Updated upstream",
+            other_label: "Stashed changes",
+            marker_size: 7,
+        },
+    )?;
+    if !merged.conflicts.is_empty() {
+        eprintln!("Applying the stash conflicts with changes to the following files:");
+        for f in &merged.conflicts {
+            eprintln!("\t {}", f.display());
+        }
+        anyhow::bail!("Could not apply stash@{{{n}}}; it has been kept");
+    }
+
+    repo::apply_merge(merged, &head_blobs, "stash pop")?;
+    remove(n, &hash)
+}
+
+/// Dispatches for `gitlet stash list`, printing the stashes, most recent first.
+pub fn list() -> Result<()> {
+    for (n, hash) in read_stack()?.iter().enumerate() {
+        let commit = Commit::load(hash).context("Load stash commit")?;
+        let description = commit.summary().trim_start_matches(PREFIX).trim_start();
+        println!("stash@{{{n}}}: {description}");
+    }
+
+    Ok(())
+}
+
+/// Dispatches for `gitlet stash drop`, discarding the given stash, by default the most recent,
+/// without applying it.
+pub fn drop(stash: Option<&str>) -> Result<()> {
+    let (n, hash) = find(stash)?;
+    remove(n, &hash)
+}
+
+/// Removes the stash at position `n`, whose hash is `hash`, from the stack.
+fn remove(n: usize, hash: &str) -> Result<()> {
+    let mut stack = read_stack()?;
+    stack.remove(n);
+
+    let stack_path = repo::abs_path_to_repo_root()?.join(".gitlet/stash");
+    if stack.is_empty() {
+        fs::remove_file(stack_path).context("Delete empty stash stack")?;
+    } else {
+        fs::write(stack_path, stack.join("\n")).context("Write stash stack")?;
+    }
+
+    println!("Dropped stash@{{{n}}} ({hash})");
+
+    Ok(())
+}
+
+/// Returns the position in the stack and the hash of the stash named by `stash`, either a number
+/// or `stash@{<number>}`, counting from 0 for the most recent. Defaults to the most recent.
+fn find(stash: Option<&str>) -> Result<(usize, String)> {
+    let name = stash.unwrap_or("0");
+    let n: usize = name
+        .strip_prefix("stash@{")
+        .and_then(|rest| rest.strip_suffix('}'))
+        .unwrap_or(name)
+        .parse()
+        .with_context(|| format!("Invalid stash name: '{name}'"))?;
+
+    let stack = read_stack()?;
+    anyhow::ensure!(!stack.is_empty(), "No stash entries found");
+    match stack.into_iter().nth(n) {
+        Some(hash) => Ok((n, hash)),
+        None => anyhow::bail!("stash@{{{n}}} does not exist"),
+    }
+}
+
+/// Returns the hashes of the stashes, most recent first.
+fn read_stack() -> Result<Vec<String>> {
+    let stack_path = repo::abs_path_to_repo_root()?.join(".gitlet/stash");
+    if !stack_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    Ok(fs::read_to_string(stack_path)
+        .context("Read stash stack")?
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
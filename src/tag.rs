@@ -0,0 +1,150 @@
+//! Tags name commits permanently, such as those of releases, and unlike branches never move.
+//!
+//! Every tag is a ref in `.gitlet/refs/tags/` holding the hash of the tagged commit. An annotated
+//! tag also has a tag object in `.gitlet/tags/`, a JSON file of the same name recording who made
+//! it, when, and why.
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::commit;
+use crate::config::{Config, ConfigScope};
+use crate::repo;
+use crate::transaction::Transaction;
+
+/// The object of an annotated tag.
+#[derive(Debug, Deserialize, Serialize)]
+struct Tag {
+    name: String,
+    commit: String,
+    /// The name and email of whoever made the tag, e.g. `A U Thor <author@example.com>`, or empty
+    /// if neither is set.
+    tagger: String,
+    message: String,
+    timestamp: u64,
+}
+
+/// Dispatches for the `gitlet tag` command.
+///
+/// Without a `name`, lists the tags. Otherwise, deletes the tag if `delete` is set, or else tags
+/// `commit`, which defaults to the HEAD. The tag is annotated with `message` if one is given.
+pub fn tag(
+    name: Option<&str>,
+    commit: Option<&str>,
+    delete: bool,
+    message: Option<&str>,
+) -> Result<()> {
+    match (name, delete) {
+        (None, false) => list_tags(),
+        (None, true) => anyhow::bail!("Tag name required"),
+        (Some(name), true) => delete_tag(name),
+        (Some(name), false) => create_tag(name, commit.unwrap_or("HEAD"), message),
+    }
+}
+
+/// Prints the names of the tags, sorted alphabetically.
+fn list_tags() -> Result<()> {
+    let tags_dir = repo::abs_path_to_repo_root()?.join(".gitlet/refs/tags");
+    let mut names: Vec<String> = WalkDir::new(&tags_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            e.path()
+                .strip_prefix(&tags_dir)
+                .ok()
+                .map(|name| name.to_string_lossy().into_owned())
+        })
+        .collect();
+    names.sort();
+
+    for name in names {
+        println!("{name}");
+    }
+
+    Ok(())
+}
+
+/// Creates the tag `name` for the commit named by `target`, annotated with `message` if given.
+///
+/// # Panics
+///
+/// Returns an error if `name` is not a valid ref name or a tag of that name already exists.
+fn create_tag(name: &str, target: &str, message: Option<&str>) -> Result<()> {
+    repo::check_ref_format(name, true)?;
+    let hash = repo::resolve_ref(target)?;
+    anyhow::ensure!(!hash.is_empty(), "Cannot tag before the first commit");
+
+    let tag_ref = tag_ref_path(name)?;
+    if tag_ref.exists() {
+        anyhow::bail!("A tag named '{name}' already exists");
+    }
+
+    let mut tx = Transaction::begin();
+    if let Some(message) = message {
+        let tag = Tag {
+            name: name.to_string(),
+            commit: hash.clone(),
+            tagger: tagger()?,
+            message: message.to_string(),
+            timestamp: commit::now()?,
+        };
+        let json = serde_json::to_vec(&tag).context("Serialize tag object")?;
+        tx.stage_file_write(tag_object_path(name)?, json);
+    }
+    tx.stage_file_write(tag_ref, hash.as_str());
+    tx.commit()
+        .with_context(|| format!("Create tag '{name}'"))?;
+
+    Ok(())
+}
+
+/// Deletes the tag `name`, along with its tag object if it is annotated.
+///
+/// # Panics
+///
+/// Returns an error if `name` is not a valid ref name or no tag of that name exists.
+fn delete_tag(name: &str) -> Result<()> {
+    repo::check_ref_format(name, true)?;
+    let tag_ref = tag_ref_path(name)?;
+    if !tag_ref.is_file() {
+        anyhow::bail!("Tag '{name}' not found");
+    }
+    let hash = fs::read_to_string(&tag_ref).context("Read tag ref")?;
+
+    let tag_object = tag_object_path(name)?;
+    if tag_object.exists() {
+        fs::remove_file(tag_object).with_context(|| format!("Delete tag object '{name}'"))?;
+    }
+    fs::remove_file(tag_ref).with_context(|| format!("Delete tag '{name}'"))?;
+
+    println!("Deleted tag '{name}' (was {})", &hash[..hash.len().min(7)]);
+
+    Ok(())
+}
+
+/// Returns the tagger of new annotated tags, like the author of new commits.
+fn tagger() -> Result<String> {
+    let (name, email) = Config::load(ConfigScope::Merged)
+        .context("Load tagger from config")?
+        .author();
+    if name.is_empty() && email.is_empty() {
+        return Ok(String::new());
+    }
+    Ok(format!("{name} <{email}>"))
+}
+
+fn tag_ref_path(name: &str) -> Result<PathBuf> {
+    Ok(repo::abs_path_to_repo_root()?
+        .join(".gitlet/refs/tags")
+        .join(name))
+}
+
+fn tag_object_path(name: &str) -> Result<PathBuf> {
+    Ok(repo::abs_path_to_repo_root()?
+        .join(".gitlet/tags")
+        .join(name))
+}
@@ -0,0 +1,155 @@
+//! Trailers are the `<token>: <value>` lines, such as `Signed-off-by: Alice <alice@example.com>`,
+//! that make up the last paragraph of a commit message and hold structured metadata about it.
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::config::{Config, ConfigScope};
+
+/// Adds `trailers` to the commit message read from `file`, or from stdin if there is none, and
+/// prints the result. With `in_place`, the result is written back to `file` instead.
+///
+/// Each trailer may be given as either `<token>: <value>` or `<token>=<value>`.
+pub fn interpret(file: Option<&Path>, in_place: bool, trailers: &[String]) -> Result<()> {
+    let message = match file {
+        Some(path) => fs::read_to_string(path)
+            .with_context(|| format!("Read commit message from '{}'", path.display()))?,
+        None => {
+            anyhow::ensure!(!in_place, "--in-place requires a file");
+            let mut message = String::new();
+            std::io::stdin()
+                .read_to_string(&mut message)
+                .context("Read commit message from stdin")?;
+            message
+        }
+    };
+
+    let trailers = trailers
+        .iter()
+        .map(|t| parse_trailer(t))
+        .collect::<Result<Vec<_>>>()?;
+    let message = add_trailers(&message, &trailers);
+
+    match file {
+        Some(path) if in_place => fs::write(path, message)
+            .with_context(|| format!("Write commit message to '{}'", path.display()))?,
+        _ => print!("{message}"),
+    }
+
+    Ok(())
+}
+
+/// Returns the `Signed-off-by` trailer for the current user, as configured to author commits (see
+/// [`Config::author`]).
+pub(crate) fn signoff() -> Result<String> {
+    let (name, email) = Config::load(ConfigScope::Merged)
+        .context("Load author from config")?
+        .author();
+    anyhow::ensure!(
+        !name.is_empty(),
+        "Set GITLET_AUTHOR_NAME or user.name to sign off on commits"
+    );
+    anyhow::ensure!(
+        !email.is_empty(),
+        "Set GITLET_AUTHOR_EMAIL or user.email to sign off on commits"
+    );
+    Ok(format!("Signed-off-by: {name} <{email}>"))
+}
+
+/// Appends `trailers` to the trailer block at the end of `message`, starting a new one if there is
+/// none. Trailers identical to one already present are skipped.
+pub(crate) fn add_trailers(message: &str, trailers: &[String]) -> String {
+    let message = message.trim_end();
+    let mut out = message.to_string();
+
+    // The subject is never a trailer block, even if it happens to look like one.
+    let existing: Vec<&str> = match message.rsplit_once("\n\n") {
+        Some((_, last)) if last.lines().all(is_trailer) => last.lines().collect(),
+        _ => Vec::new(),
+    };
+    let has_block = !existing.is_empty();
+
+    let mut added: Vec<&str> = Vec::new();
+    for trailer in trailers {
+        if existing.contains(&trailer.as_str()) || added.contains(&trailer.as_str()) {
+            continue;
+        }
+        if added.is_empty() && !has_block && !out.is_empty() {
+            out.push_str("\n\n");
+        } else if !out.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(trailer);
+        added.push(trailer);
+    }
+
+    if !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+/// Returns true if `line` has the form `<token>: <value>`, where the token consists of letters,
+/// digits, and hyphens.
+fn is_trailer(line: &str) -> bool {
+    match line.split_once(": ") {
+        Some((token, _)) => {
+            !token.is_empty() && token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        }
+        None => false,
+    }
+}
+
+/// Normalizes a trailer given as `<token>: <value>` or `<token>=<value>` into the former.
+fn parse_trailer(trailer: &str) -> Result<String> {
+    let (token, value) = trailer
+        .split_once(':')
+        .or_else(|| trailer.split_once('='))
+        .with_context(|| format!("Invalid trailer '{trailer}': expected '<token>: <value>'"))?;
+    let trailer = format!("{}: {}", token.trim(), value.trim());
+    anyhow::ensure!(
+        is_trailer(&trailer),
+        "Invalid trailer '{trailer}': expected '<token>: <value>'"
+    );
+    Ok(trailer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn append_trailers() -> Result<()> {
+        let signoff = parse_trailer("Signed-off-by=Alice <alice@example.com>")?;
+        assert_eq!("Signed-off-by: Alice <alice@example.com>", signoff);
+        assert!(parse_trailer("not a trailer").is_err());
+
+        // A message without trailers gets a new trailer block.
+        assert_eq!(
+            "Subject\n\nBody.\n\nSigned-off-by: Alice <alice@example.com>\n",
+            add_trailers("Subject\n\nBody.\n", std::slice::from_ref(&signoff))
+        );
+
+        // The subject is not mistaken for a trailer block.
+        assert_eq!(
+            "fix: Subject\n\nSigned-off-by: Alice <alice@example.com>\n",
+            add_trailers("fix: Subject", std::slice::from_ref(&signoff))
+        );
+
+        // Trailers join an existing block, skipping those already present.
+        let reviewed = "Reviewed-by: Bob <bob@example.com>".to_string();
+        assert_eq!(
+            "Subject\n\nSigned-off-by: Alice <alice@example.com>\nReviewed-by: Bob <bob@example.com>\n",
+            add_trailers(
+                "Subject\n\nSigned-off-by: Alice <alice@example.com>\n",
+                &[signoff.clone(), reviewed.clone(), reviewed]
+            )
+        );
+
+        assert_eq!("Subject\n", add_trailers("Subject\n\n", &[]));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,209 @@
+//! Groups the file writes of a multi-step operation, such as creating a commit and pointing a
+//! branch at it, so that either all of them happen or none do.
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use anyhow::{Context, Result};
+
+/// A set of file writes to be applied together.
+///
+/// Writes are only staged until [`Transaction::commit`] is called, which writes each file to a
+/// new temporary file beside it before renaming them all into place. If anything fails along
+/// the way, or if the transaction is dropped without being committed, the files already replaced
+/// are restored to their previous contents.
+#[derive(Default)]
+pub(crate) struct Transaction {
+    staged: Vec<(PathBuf, Vec<u8>)>,
+    /// Files already replaced, along with their previous contents, if they existed.
+    written: Vec<(PathBuf, Option<Vec<u8>>)>,
+    committed: bool,
+}
+
+impl Transaction {
+    pub(crate) fn begin() -> Self {
+        Self::default()
+    }
+
+    /// Stages writing `content` to the file at `path`, creating or truncating it.
+    pub(crate) fn stage_file_write(
+        &mut self,
+        path: impl Into<PathBuf>,
+        content: impl Into<Vec<u8>>,
+    ) {
+        self.staged.push((path.into(), content.into()));
+    }
+
+    /// Applies the staged writes, rolling back those already applied if any of them fails.
+    pub(crate) fn commit(mut self) -> Result<()> {
+        let staged = std::mem::take(&mut self.staged);
+
+        // Write everything out beside its destination first, so that a failure here leaves the
+        // destinations untouched.
+        let mut temps = Vec::with_capacity(staged.len());
+        for (path, content) in staged {
+            let temp = temp_path(&path);
+            if let Err(e) = write_temp(&temp, &content) {
+                for (_, temp) in temps {
+                    let _ = fs::remove_file(temp);
+                }
+                return Err(e);
+            }
+            temps.push((path, temp));
+        }
+
+        let mut temps = temps.into_iter();
+        for (path, temp) in temps.by_ref() {
+            let previous = fs::read(&path).ok();
+            if let Err(e) = fs::rename(&temp, &path) {
+                let _ = fs::remove_file(&temp);
+                for (_, temp) in temps {
+                    let _ = fs::remove_file(temp);
+                }
+                return Err(e).with_context(|| format!("Move '{}' into place", path.display()));
+            }
+            self.written.push((path, previous));
+        }
+
+        self.committed = true;
+        Ok(())
+    }
+
+    /// Discards the staged writes and restores every file already replaced to its previous
+    /// contents, deleting those that did not exist before. Dropping an uncommitted transaction
+    /// does this too.
+    pub(crate) fn rollback(&mut self) -> Result<()> {
+        self.staged.clear();
+
+        while let Some((path, previous)) = self.written.pop() {
+            match previous {
+                Some(content) => fs::write(&path, content),
+                None => fs::remove_file(&path),
+            }
+            .with_context(|| format!("Roll back write to '{}'", path.display()))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = self.rollback();
+        }
+    }
+}
+
+/// Returns the path of the temporary file to which `path` is written before being moved into place.
+/// It is in the same directory, so that the move is a rename, and is named uniquely to this process
+/// and write, as `.<file name>.<process id>-<count>.tmp`, so that it does not clash with the user's
+/// files.
+fn temp_path(path: &Path) -> PathBuf {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(
+        ".{}-{}.tmp",
+        std::process::id(),
+        COUNT.fetch_add(1, Ordering::Relaxed)
+    ));
+    path.with_file_name(name)
+}
+
+/// Writes `content` to the new file `temp`, failing rather than replacing a file already there.
+fn write_temp(temp: &Path, content: &[u8]) -> Result<()> {
+    if let Some(parent) = temp.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Create directory '{}'", parent.display()))?;
+    }
+    fs::File::create_new(temp)
+        .and_then(|mut f| f.write_all(content))
+        .with_context(|| format!("Write '{}'", temp.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commit_writes_all_files() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+        fs::write(tmpdir.join("existing"), "old")?;
+
+        let mut tx = Transaction::begin();
+        tx.stage_file_write(tmpdir.join("existing"), "new");
+        tx.stage_file_write(tmpdir.join("sub/created"), "created");
+
+        // Nothing is written until the transaction is committed.
+        assert_eq!("old", fs::read_to_string(tmpdir.join("existing"))?);
+        assert!(!tmpdir.join("sub/created").exists());
+
+        tx.commit()?;
+
+        assert_eq!("new", fs::read_to_string(tmpdir.join("existing"))?);
+        assert_eq!("created", fs::read_to_string(tmpdir.join("sub/created"))?);
+        assert_eq!(2, fs::read_dir(&*tmpdir)?.count());
+
+        Ok(())
+    }
+
+    #[test]
+    fn commit_keeps_files_named_like_temporary_files() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+        let lookalike = temp_path(&tmpdir.join("f"));
+        fs::write(&lookalike, "mine")?;
+
+        let mut tx = Transaction::begin();
+        tx.stage_file_write(tmpdir.join("f"), "new");
+        tx.commit()?;
+
+        assert_eq!("new", fs::read_to_string(tmpdir.join("f"))?);
+        assert_eq!("mine", fs::read_to_string(&lookalike)?);
+        assert_eq!(2, fs::read_dir(&*tmpdir)?.count());
+
+        Ok(())
+    }
+
+    #[test]
+    fn failed_commit_leaves_files_untouched() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+        fs::write(tmpdir.join("existing"), "old")?;
+        // A file where a directory is needed makes the second write fail.
+        fs::write(tmpdir.join("blocker"), "")?;
+
+        let mut tx = Transaction::begin();
+        tx.stage_file_write(tmpdir.join("existing"), "new");
+        tx.stage_file_write(tmpdir.join("blocker/file"), "content");
+
+        assert!(tx.commit().is_err());
+        assert_eq!("old", fs::read_to_string(tmpdir.join("existing"))?);
+        assert_eq!(2, fs::read_dir(&*tmpdir)?.count());
+
+        Ok(())
+    }
+
+    #[test]
+    fn rollback_restores_previous_contents() -> Result<()> {
+        let tmpdir = assert_fs::TempDir::new()?;
+        fs::write(tmpdir.join("existing"), "old")?;
+
+        let mut tx = Transaction::begin();
+        tx.written
+            .push((tmpdir.join("existing"), Some(b"old".to_vec())));
+        tx.written.push((tmpdir.join("created"), None));
+        fs::write(tmpdir.join("existing"), "new")?;
+        fs::write(tmpdir.join("created"), "created")?;
+
+        tx.rollback()?;
+
+        assert_eq!("old", fs::read_to_string(tmpdir.join("existing"))?);
+        assert!(!tmpdir.join("created").exists());
+
+        Ok(())
+    }
+}
@@ -4,7 +4,7 @@ use std::error::Error;
 use std::process::Command;
 
 use assert_cmd::prelude::*;
-use predicates::prelude::predicate;
+use predicates::prelude::*;
 
 #[test]
 fn stage_file() -> Result<(), Box<dyn Error>> {
@@ -44,3 +44,133 @@ fn stage_nonexistent_file() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn stage_verbose_continues_past_errors() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    std::fs::write(tmpdir.join("a.txt"), "a")?;
+    std::fs::write(tmpdir.join("b.txt"), "b")?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("add")
+        .arg("-v")
+        .arg("a.txt")
+        .arg("missing.txt")
+        .arg("b.txt");
+    cmd.assert()
+        .failure()
+        .stdout(predicate::str::diff("add 'a.txt'\nadd 'b.txt'\n"))
+        .stderr(predicate::str::contains(
+            "error: missing.txt: Cannot stage file. File does not exist.",
+        ));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("status");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("a.txt").and(predicate::str::contains("b.txt")));
+
+    Ok(())
+}
+
+#[test]
+fn stage_with_chmod() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    std::fs::write(tmpdir.join("run.sh"), "echo hi")?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("add")
+        .arg("--chmod=+x")
+        .arg("run.sh");
+    cmd.assert().success();
+
+    let index = std::fs::read_to_string(tmpdir.join(".gitlet/index"))?;
+    assert!(index.contains(&format!("\"mode\":{}", 0o100755)));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("add")
+        .arg("--chmod")
+        .arg("-x")
+        .arg("run.sh");
+    cmd.assert().success();
+
+    let index = std::fs::read_to_string(tmpdir.join(".gitlet/index"))?;
+    assert!(index.contains(&format!("\"mode\":{}", 0o100644)));
+
+    Ok(())
+}
+
+#[test]
+fn refuse_to_stage_ignored_file() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    std::fs::write(tmpdir.join(".gitletignore"), "# Logs\n*.log\n")?;
+    std::fs::write(tmpdir.join("debug.log"), "")?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("debug.log");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "debug.log: The path is ignored by .gitletignore",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn stage_directory() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    std::fs::create_dir_all(tmpdir.join("src/nested"))?;
+    std::fs::create_dir_all(tmpdir.join("target"))?;
+    std::fs::write(tmpdir.join(".gitletignore"), "target/\n")?;
+    std::fs::write(tmpdir.join("a.txt"), "a")?;
+    std::fs::write(tmpdir.join("src/b.txt"), "b")?;
+    std::fs::write(tmpdir.join("src/.hidden"), "")?;
+    std::fs::write(tmpdir.join("src/nested/c.txt"), "c")?;
+    std::fs::write(tmpdir.join("target/out.o"), "")?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("-v").arg("src");
+    cmd.assert().success().stdout(predicate::str::diff(
+        "add 'src/b.txt'\nadd 'src/nested/c.txt'\nStaged 2 file(s)\n",
+    ));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("-m")
+        .arg("Add src");
+    cmd.assert().success();
+
+    // Unchanged tracked files, hidden files, and ignored directories are skipped.
+    std::fs::write(tmpdir.join("src/b.txt"), "B")?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("-v").arg(".");
+    cmd.assert().success().stdout(predicate::str::diff(
+        "add 'a.txt'\nadd 'src/b.txt'\nStaged 2 file(s)\n",
+    ));
+
+    Ok(())
+}
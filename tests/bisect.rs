@@ -0,0 +1,120 @@
+//! Tests the bisect command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+/// Runs gitlet in `dir` with the given arguments, asserting that it succeeds, and returns its
+/// output.
+fn gitlet(dir: &std::path::Path, args: &[&str]) -> Result<String, Box<dyn Error>> {
+    let output = Command::cargo_bin("gitlet")?
+        .current_dir(dir)
+        .args(args)
+        .output()?;
+    assert!(output.status.success(), "gitlet {args:?} failed");
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Initializes a repository in `dir` with a commit for each of the given contents of a.txt.
+/// Returns the hashes of the commits, oldest first.
+fn repo_with_history(
+    dir: &std::path::Path,
+    contents: &[&str],
+) -> Result<Vec<String>, Box<dyn Error>> {
+    gitlet(dir, &["init"])?;
+    let mut hashes = Vec::new();
+    for (n, content) in contents.iter().enumerate() {
+        std::fs::write(dir.join("a.txt"), content)?;
+        gitlet(dir, &["add", "a.txt"])?;
+        gitlet(dir, &["commit", "-m", &format!("Commit {n}")])?;
+        hashes.push(std::fs::read_to_string(dir.join(".gitlet/refs/main"))?);
+    }
+
+    Ok(hashes)
+}
+
+#[test]
+fn bisect_finds_first_bad_commit() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let hashes = repo_with_history(&tmpdir, &["ok", "ok", "ok", "bug", "bug", "bug", "bug"])?;
+
+    gitlet(&tmpdir, &["bisect", "start"])?;
+    gitlet(&tmpdir, &["bisect", "bad"])?;
+    let mut output = gitlet(&tmpdir, &["bisect", "good", &hashes[0][..7]])?;
+
+    let mut tested = 0;
+    while !output.contains("is the first bad commit") {
+        tested += 1;
+        assert!(tested <= 3, "bisect did not narrow down the candidates");
+        let head = std::fs::read_to_string(tmpdir.join(".gitlet/HEAD"))?;
+        assert_eq!(
+            head,
+            std::fs::read_to_string(tmpdir.join(".gitlet/BISECT_HEAD"))?
+        );
+        output = match std::fs::read_to_string(tmpdir.join("a.txt"))?.as_str() {
+            "bug" => gitlet(&tmpdir, &["bisect", "bad"])?,
+            _ => gitlet(&tmpdir, &["bisect", "good"])?,
+        };
+    }
+    assert!(output.starts_with(&format!("{} is the first bad commit", hashes[3])));
+
+    gitlet(&tmpdir, &["bisect", "reset"])?;
+    assert_eq!(
+        "main",
+        std::fs::read_to_string(tmpdir.join(".gitlet/HEAD"))?
+    );
+    assert_eq!("bug", std::fs::read_to_string(tmpdir.join("a.txt"))?);
+    assert!(!tmpdir.join(".gitlet/BISECT_LOG").exists());
+    assert!(!tmpdir.join(".gitlet/BISECT_HEAD").exists());
+
+    Ok(())
+}
+
+#[test]
+fn bisect_waits_for_good_and_bad() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    repo_with_history(&tmpdir, &["ok", "bug"])?;
+
+    gitlet(&tmpdir, &["bisect", "start"])?;
+    let output = gitlet(&tmpdir, &["bisect", "bad"])?;
+    assert!(output.starts_with("Waiting for both a good and a bad commit"));
+    assert_eq!(
+        "main",
+        std::fs::read_to_string(tmpdir.join(".gitlet/HEAD"))?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn bisect_requires_start() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    repo_with_history(&tmpdir, &["ok", "bug"])?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("bisect").arg("good");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("gitlet bisect start"));
+
+    Ok(())
+}
+
+#[test]
+fn bisect_rejects_bad_reachable_from_good() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let hashes = repo_with_history(&tmpdir, &["ok", "bug"])?;
+
+    gitlet(&tmpdir, &["bisect", "start"])?;
+    gitlet(&tmpdir, &["bisect", "good"])?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .args(["bisect", "bad", &hashes[0][..7]]);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("is reachable from a good commit"));
+
+    Ok(())
+}
@@ -0,0 +1,60 @@
+//! Tests the blame command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+/// Runs gitlet in `dir` as the given author with the given arguments, asserting that it succeeds.
+fn gitlet_as(dir: &std::path::Path, author: &str, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir)
+        .env("GITLET_AUTHOR_NAME", author)
+        .env("GITLET_AUTHOR_EMAIL", format!("{author}@example.com"))
+        .args(args);
+    cmd.assert().success();
+    Ok(())
+}
+
+/// Commits `content` as the new version of a.txt, as the given author, returning the hash.
+fn commit(dir: &std::path::Path, author: &str, content: &str) -> Result<String, Box<dyn Error>> {
+    std::fs::write(dir.join("a.txt"), content)?;
+    gitlet_as(dir, author, &["add", "a.txt"])?;
+    gitlet_as(dir, author, &["commit", "-m", "Change a.txt"])?;
+    Ok(std::fs::read_to_string(dir.join(".gitlet/refs/main"))?[..7].to_string())
+}
+
+#[test]
+fn blame_lines() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    gitlet_as(&tmpdir, "Ann", &["init"])?;
+    let first = commit(&tmpdir, "Ann", "one\ntwo\nthree\n")?;
+    let second = commit(&tmpdir, "Bob", "one\nTWO\nthree\nfour\n")?;
+    // Committing another file leaves a.txt's lines where they came from.
+    std::fs::write(tmpdir.join("b.txt"), "b")?;
+    gitlet_as(&tmpdir, "Cy", &["add", "b.txt"])?;
+    gitlet_as(&tmpdir, "Cy", &["commit", "-m", "Add b.txt"])?;
+    let third = commit(&tmpdir, "Cy", "zero\none\nTWO\nthree\nfour\n")?;
+
+    let date = "[0-9]{4}-[0-9]{2}-[0-9]{2} [0-9]{2}:[0-9]{2}:[0-9]{2}";
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("blame").arg("a.txt");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(format!(
+            "^{third} \\(Cy  {date}\\) zero\n\
+             {first} \\(Ann {date}\\) one\n\
+             {second} \\(Bob {date}\\) TWO\n\
+             {first} \\(Ann {date}\\) three\n\
+             {second} \\(Bob {date}\\) four\n$"
+        ))?);
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("blame").arg("c.txt");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "'c.txt' is not tracked by the HEAD commit",
+    ));
+
+    Ok(())
+}
@@ -182,7 +182,10 @@ fn create_branch_with_commit() -> Result<(), Box<dyn Error>> {
     cmd.current_dir(&tmpdir).arg("add").arg(&tmp_path);
     cmd.assert().success();
     let mut cmd = Command::cargo_bin("gitlet")?;
-    cmd.current_dir(&tmpdir).arg("commit").arg("add tmp.txt");
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("-m")
+        .arg("add tmp.txt");
     cmd.assert().success();
 
     // "Create" a new branch called 'test_branch'
@@ -198,3 +201,239 @@ fn create_branch_with_commit() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn list_merged_and_unmerged_branches() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    // Add and commit a file on 'main'.
+    let mut cmd = Command::new("touch");
+    cmd.current_dir(&tmpdir).arg("a.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("a.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("-m")
+        .arg("add a.txt");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("branch").arg("old_branch");
+    cmd.assert().success();
+
+    // Add and commit a file on 'feature', which 'main' does not have.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("switch")
+        .arg("-c")
+        .arg("feature");
+    cmd.assert().success();
+    let mut cmd = Command::new("touch");
+    cmd.current_dir(&tmpdir).arg("b.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("b.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("-m")
+        .arg("add b.txt");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("switch").arg("main");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("branch").arg("--merged");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("* main\n  old_branch\n"));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("branch").arg("--no-merged");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("  feature\n"));
+
+    // Everything is merged into 'feature'.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("branch")
+        .arg("--merged")
+        .arg("feature");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("  feature\n* main\n  old_branch\n"));
+
+    Ok(())
+}
+
+#[test]
+fn rename_current_branch() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    // Add and commit new file.
+    let mut cmd = Command::new("touch");
+    cmd.current_dir(&tmpdir).arg("tmp.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("tmp.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("-m")
+        .arg("add tmp.txt");
+    cmd.assert().success();
+
+    let main_hash = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("branch")
+        .arg("-m")
+        .arg("trunk");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Renamed branch 'main' to 'trunk'"));
+
+    assert!(!tmpdir.join(".gitlet/refs/main").exists());
+    let trunk_hash = std::fs::read_to_string(tmpdir.join(".gitlet/refs/trunk"))?;
+    assert_eq!(main_hash, trunk_hash);
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("status");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("On branch trunk"));
+
+    Ok(())
+}
+
+#[test]
+fn cannot_rename_to_existing_branch() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("branch").arg("test_branch");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("branch")
+        .arg("--rename")
+        .arg("test_branch")
+        .arg("main");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Error: A branch named 'main' already exists",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn cannot_rename_file_outside_refs() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("branch")
+        .arg("-m")
+        .arg("../HEAD")
+        .arg("x");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("is not a valid branch name"));
+
+    assert_eq!(
+        "main",
+        std::fs::read_to_string(tmpdir.join(".gitlet/HEAD"))?
+    );
+    assert!(!tmpdir.join(".gitlet/refs/x").exists());
+
+    Ok(())
+}
+
+#[test]
+fn verbose_branch_list() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    let commit = |message: &str| -> Result<String, Box<dyn Error>> {
+        let mut cmd = Command::cargo_bin("gitlet")?;
+        cmd.current_dir(&tmpdir)
+            .arg("commit")
+            .arg("--allow-empty")
+            .arg("-m")
+            .arg(message);
+        cmd.assert().success();
+        Ok(std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?)
+    };
+
+    let first = commit("First")?;
+    let second = commit("Second")?;
+
+    // The remote has a commit of its own on top of the first.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("switch").arg(&first);
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("--allow-empty")
+        .arg("-m")
+        .arg("Remote");
+    cmd.assert().success();
+    let remote = std::fs::read_to_string(tmpdir.join(".gitlet/HEAD"))?;
+    std::fs::create_dir_all(tmpdir.join(".gitlet/refs/remotes/origin"))?;
+    std::fs::write(tmpdir.join(".gitlet/refs/remotes/origin/main"), remote)?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("switch").arg("main");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("branch").arg("topic");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("branch").arg("-v");
+    cmd.assert().success().stdout(predicate::str::diff(format!(
+        "* main  {} Second\n  topic {} Second\n",
+        &second[..7],
+        &second[..7]
+    )));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("branch").arg("-vv");
+    cmd.assert().success().stdout(predicate::str::diff(format!(
+        "* main  {} [origin/main: ahead 1, behind 1] Second\n  topic {} Second\n",
+        &second[..7],
+        &second[..7]
+    )));
+
+    Ok(())
+}
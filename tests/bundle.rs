@@ -0,0 +1,324 @@
+//! Tests the bundle commands.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use assert_fs::prelude::*;
+use predicates::prelude::predicate;
+
+/// Initializes a repository in `dir` with a 'feature' branch holding two commits.
+fn repo_with_feature_branch(dir: &std::path::Path) -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir).arg("init");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir).arg("switch").arg("-c").arg("feature");
+    cmd.assert().success();
+
+    for (file, content) in [("a.txt", "first"), ("b.txt", "second")] {
+        std::fs::write(dir.join(file), content)?;
+        let mut cmd = Command::cargo_bin("gitlet")?;
+        cmd.current_dir(dir).arg("add").arg(file);
+        cmd.assert().success();
+        let mut cmd = Command::cargo_bin("gitlet")?;
+        cmd.current_dir(dir)
+            .arg("commit")
+            .arg("-m")
+            .arg(format!("add {file}"));
+        cmd.assert().success();
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bundle_and_unbundle_branch() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let src = tmpdir.child("src");
+    let dst = tmpdir.child("dst");
+    src.create_dir_all()?;
+    repo_with_feature_branch(&src)?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&src)
+        .arg("bundle")
+        .arg("create")
+        .arg("../repo.bundle")
+        .arg("feature");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Bundled 2 commit(s) and 2 blob(s) into '../repo.bundle'",
+    ));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init").arg("dst");
+    cmd.assert().success();
+
+    let feature_hash = std::fs::read_to_string(src.join(".gitlet/refs/feature"))?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&dst)
+        .arg("bundle")
+        .arg("unbundle")
+        .arg("../repo.bundle");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "{feature_hash} refs/feature\n"
+        )));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&dst).arg("switch").arg("feature");
+    cmd.assert().success();
+
+    assert_eq!("first", std::fs::read_to_string(dst.join("a.txt"))?);
+    assert_eq!("second", std::fs::read_to_string(dst.join("b.txt"))?);
+
+    Ok(())
+}
+
+#[test]
+fn unbundle_requires_prerequisites() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let src = tmpdir.child("src");
+    let dst = tmpdir.child("dst");
+    src.create_dir_all()?;
+    repo_with_feature_branch(&src)?;
+
+    // Bundle only the most recent commit, which requires its parent.
+    let feature_hash = std::fs::read_to_string(src.join(".gitlet/refs/feature"))?;
+    let output = Command::cargo_bin("gitlet")?
+        .current_dir(&src)
+        .arg("log")
+        .output()?;
+    let log = String::from_utf8(output.stdout)?;
+    let parent_hash = log
+        .lines()
+        .filter_map(|l| l.strip_prefix("commit "))
+        .nth(1)
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&src)
+        .arg("bundle")
+        .arg("create")
+        .arg("../repo.bundle")
+        .arg(format!("{parent_hash}..feature"));
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Bundled 1 commit(s) and 1 blob(s) into '../repo.bundle'",
+    ));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init").arg("dst");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&dst)
+        .arg("bundle")
+        .arg("verify")
+        .arg("../repo.bundle");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "Missing prerequisite commit {parent_hash}"
+        )));
+
+    // The source repository has the prerequisite.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&src)
+        .arg("bundle")
+        .arg("verify")
+        .arg("../repo.bundle");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "The bundle contains 1 ref(s):\n{feature_hash} refs/feature\n"
+        )))
+        .stdout(predicate::str::contains(
+            "The bundle holds 1 commit(s), dated ",
+        ))
+        .stdout(predicate::str::contains("'../repo.bundle' is okay"));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&dst)
+        .arg("bundle")
+        .arg("unbundle")
+        .arg("../repo.bundle");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "Missing prerequisite commit {parent_hash}"
+        )));
+
+    assert!(!dst.join(".gitlet/refs/feature").exists());
+    assert_ne!(feature_hash, parent_hash);
+
+    Ok(())
+}
+
+#[test]
+fn unbundle_fast_forwards_newline_terminated_ref() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let src = tmpdir.child("src");
+    let dst = tmpdir.child("dst");
+    src.create_dir_all()?;
+    repo_with_feature_branch(&src)?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&src)
+        .arg("bundle")
+        .arg("create")
+        .arg("../repo.bundle")
+        .arg("feature");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init").arg("dst");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&dst)
+        .arg("bundle")
+        .arg("unbundle")
+        .arg("../repo.bundle");
+    cmd.assert().success();
+
+    // Move the branch back to its first commit, as a ref written with a trailing newline.
+    let feature_hash = std::fs::read_to_string(src.join(".gitlet/refs/feature"))?;
+    let output = Command::cargo_bin("gitlet")?
+        .current_dir(&src)
+        .arg("log")
+        .output()?;
+    let log = String::from_utf8(output.stdout)?;
+    let parent_hash = log
+        .lines()
+        .filter_map(|l| l.strip_prefix("commit "))
+        .nth(1)
+        .unwrap();
+    std::fs::write(dst.join(".gitlet/refs/feature"), format!("{parent_hash}\n"))?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&dst)
+        .arg("bundle")
+        .arg("unbundle")
+        .arg("../repo.bundle");
+    cmd.assert().success().stdout(predicate::str::diff(format!(
+        "{feature_hash} refs/feature\n"
+    )));
+    assert_eq!(
+        feature_hash,
+        std::fs::read_to_string(dst.join(".gitlet/refs/feature"))?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn unbundle_rejects_malformed_bundles() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let dst = tmpdir.child("repo/dst");
+    dst.create_dir_all()?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&dst).arg("init");
+    cmd.assert().success();
+
+    let hash = "0452ef28c90d315dc3e05323c18b2e3724f7b275";
+    for (header, error) in [
+        (format!("{hash} ../../pwned"), "Invalid bundle ref name"),
+        (format!("{hash} /tmp/pwned"), "Invalid bundle ref name"),
+        ("abc refs/pwned".to_string(), "Invalid bundle ref hash"),
+        ("-a".to_string(), "Invalid bundle prerequisite hash"),
+    ] {
+        std::fs::write(
+            tmpdir.join("bad.bundle"),
+            format!("# gitlet bundle v1\n{header}\n\n"),
+        )?;
+        for action in ["verify", "unbundle"] {
+            let mut cmd = Command::cargo_bin("gitlet")?;
+            cmd.current_dir(&dst)
+                .arg("bundle")
+                .arg(action)
+                .arg("../../bad.bundle");
+            cmd.assert()
+                .failure()
+                .stderr(predicate::str::contains(error));
+        }
+    }
+    assert!(!tmpdir.join("pwned").exists());
+
+    Ok(())
+}
+
+#[test]
+fn unbundle_rejects_corrupt_objects() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let src = tmpdir.child("src");
+    let dst = tmpdir.child("dst");
+    src.create_dir_all()?;
+    repo_with_feature_branch(&src)?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&src)
+        .arg("bundle")
+        .arg("create")
+        .arg("../repo.bundle")
+        .arg("feature");
+    cmd.assert().success();
+
+    // Alter a bundled commit's message without changing its length.
+    let bundle = std::fs::read(tmpdir.join("repo.bundle"))?;
+    let at = bundle.windows(9).position(|w| w == b"add a.txt").unwrap();
+    let mut tampered = bundle.clone();
+    tampered[at + 4] = b'x';
+    std::fs::write(tmpdir.join("repo.bundle"), tampered)?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init").arg("dst");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&dst)
+        .arg("bundle")
+        .arg("unbundle")
+        .arg("../repo.bundle");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("is corrupt"));
+    assert!(!dst.join(".gitlet/refs/feature").exists());
+
+    Ok(())
+}
+
+#[test]
+fn unbundle_with_detached_head() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let src = tmpdir.child("src");
+    src.create_dir_all()?;
+    repo_with_feature_branch(&src)?;
+    let feature_hash = std::fs::read_to_string(src.join(".gitlet/refs/feature"))?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&src)
+        .arg("bundle")
+        .arg("create")
+        .arg("../repo.bundle")
+        .arg("feature");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&src).arg("switch").arg("HEAD~1");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&src)
+        .arg("bundle")
+        .arg("unbundle")
+        .arg("../repo.bundle");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(format!(
+            "{feature_hash} refs/feature (up to date)\n"
+        )));
+
+    Ok(())
+}
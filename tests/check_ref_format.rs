@@ -0,0 +1,76 @@
+//! Tests the check-ref-format command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+#[test]
+fn check_ref_names() -> Result<(), Box<dyn Error>> {
+    let check = |args: &[&str]| -> Result<_, Box<dyn Error>> {
+        let mut cmd = Command::cargo_bin("gitlet")?;
+        cmd.arg("check-ref-format").args(args);
+        Ok(cmd.assert())
+    };
+
+    check(&["refs/main"])?.success();
+    check(&["refs/a..b"])?
+        .code(1)
+        .stderr(predicate::str::contains("may not contain '..'"));
+
+    // Single-level names are only accepted when asked for.
+    check(&["main"])?.code(1);
+    check(&["--allow-onelevel", "main"])?.success();
+    check(&["--branch", "main"])?.success();
+    check(&["--branch", "--", "-main"])?.code(1);
+
+    check(&["--normalize", "//refs//main"])?
+        .success()
+        .stdout(predicate::str::diff("refs/main\n"));
+    check(&["--normalize", "main"])?
+        .success()
+        .stdout(predicate::str::diff("refs/main\n"));
+    check(&["--normalize", "--branch", "main"])?
+        .success()
+        .stdout(predicate::str::diff("main\n"));
+
+    Ok(())
+}
+
+#[test]
+fn invalid_branch_names_are_rejected() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("branch").arg("../escape");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("not a valid branch name"));
+    assert!(!tmpdir.join(".gitlet/escape").exists());
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("switch")
+        .arg("-c")
+        .arg("topic~1");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("not a valid branch name"));
+
+    // Branches are files directly in .gitlet/refs/, beside the tags and remotes directories.
+    for name in ["feature/x", "remotes", "tags"] {
+        let mut cmd = Command::cargo_bin("gitlet")?;
+        cmd.current_dir(&tmpdir).arg("branch").arg(name);
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("not a valid branch name"));
+    }
+    assert!(!tmpdir.join(".gitlet/refs/remotes").is_file());
+
+    Ok(())
+}
@@ -0,0 +1,188 @@
+//! Tests the cherry-pick command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::{PredicateBooleanExt, predicate};
+
+/// Runs gitlet in `dir` with the given arguments, asserting that it succeeds.
+fn gitlet(dir: &std::path::Path, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir).args(args);
+    cmd.assert().success();
+    Ok(())
+}
+
+/// Initializes a repository in `dir` whose main branch adds a.txt, and whose feature branch then
+/// changes its first line and adds b.txt. Returns the hash of the feature branch's commit, with
+/// main checked out.
+fn repo_with_feature(dir: &std::path::Path) -> Result<String, Box<dyn Error>> {
+    gitlet(dir, &["init"])?;
+    std::fs::write(dir.join("a.txt"), "one\ntwo\nthree\nfour\n")?;
+    gitlet(dir, &["add", "a.txt"])?;
+    gitlet(dir, &["commit", "-m", "First"])?;
+
+    gitlet(dir, &["switch", "-c", "feature"])?;
+    std::fs::write(dir.join("a.txt"), "ONE\ntwo\nthree\nfour\n")?;
+    std::fs::write(dir.join("b.txt"), "b\n")?;
+    gitlet(dir, &["add", "a.txt", "b.txt"])?;
+    gitlet(dir, &["commit", "-m", "Feature\n\nWith a body."])?;
+    let feature = std::fs::read_to_string(dir.join(".gitlet/refs/feature"))?;
+    gitlet(dir, &["switch", "main"])?;
+
+    Ok(feature)
+}
+
+fn log_messages(dir: &std::path::Path) -> Result<String, Box<dyn Error>> {
+    let output = Command::cargo_bin("gitlet")?
+        .current_dir(dir)
+        .arg("log")
+        .arg("--format=%s%n%b")
+        .output()?;
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+#[test]
+fn cherry_pick_onto_later_changes() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let feature = repo_with_feature(&tmpdir)?;
+
+    std::fs::write(tmpdir.join("a.txt"), "one\ntwo\nthree\nFOUR\n")?;
+    gitlet(&tmpdir, &["add", "a.txt"])?;
+    gitlet(&tmpdir, &["commit", "-m", "Second"])?;
+
+    gitlet(&tmpdir, &["cherry-pick", &feature[..7]])?;
+
+    assert_eq!(
+        "ONE\ntwo\nthree\nFOUR\n",
+        std::fs::read_to_string(tmpdir.join("a.txt"))?
+    );
+    assert_eq!("b\n", std::fs::read_to_string(tmpdir.join("b.txt"))?);
+    assert!(log_messages(&tmpdir)?.starts_with("Feature\nWith a body.\nSecond\n"));
+    assert_ne!(
+        feature,
+        std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cherry_pick_conflict() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let feature = repo_with_feature(&tmpdir)?;
+
+    std::fs::write(tmpdir.join("a.txt"), "1\ntwo\nthree\nfour\n")?;
+    gitlet(&tmpdir, &["add", "a.txt"])?;
+    gitlet(&tmpdir, &["commit", "-m", "Second"])?;
+    let second = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("cherry-pick").arg("feature");
+    cmd.assert().failure().stderr(
+        predicate::str::contains("a.txt")
+            .and(predicate::str::contains("--continue"))
+            .and(predicate::str::contains("--abort")),
+    );
+    let conflicted = std::fs::read_to_string(tmpdir.join("a.txt"))?;
+    assert!(conflicted.starts_with("<<<<<<< HEAD\n1\n=======\nONE\n>>>>>>> "));
+    assert!(tmpdir.join("b.txt").exists());
+
+    // Another cherry-pick has to wait.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("cherry-pick").arg(&feature);
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("A cherry-pick is in progress"));
+
+    gitlet(&tmpdir, &["cherry-pick", "--abort"])?;
+    assert_eq!(
+        "1\ntwo\nthree\nfour\n",
+        std::fs::read_to_string(tmpdir.join("a.txt"))?
+    );
+    assert!(!tmpdir.join("b.txt").exists());
+    assert_eq!(
+        second,
+        std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?
+    );
+
+    // This time, resolve the conflict and continue.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("cherry-pick").arg("feature");
+    cmd.assert().failure();
+    std::fs::write(tmpdir.join("a.txt"), "One\ntwo\nthree\nfour\n")?;
+    gitlet(&tmpdir, &["add", "a.txt"])?;
+    gitlet(&tmpdir, &["cherry-pick", "--continue"])?;
+
+    assert!(log_messages(&tmpdir)?.starts_with("Feature\nWith a body.\nSecond\n"));
+    assert!(!tmpdir.join(".gitlet/CHERRY_PICK_HEAD").exists());
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("status");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "=== Staged Files ===\n\n=== Removed Files ===\n\n=== Unstaged Modifications ===\n\n",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn cherry_pick_keeps_author_and_untracked_files() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    gitlet(&tmpdir, &["init"])?;
+    std::fs::write(tmpdir.join("a.txt"), "one\ntwo\n")?;
+    gitlet(&tmpdir, &["add", "a.txt"])?;
+    gitlet(&tmpdir, &["commit", "-m", "First"])?;
+
+    gitlet(&tmpdir, &["switch", "-c", "feature"])?;
+    std::fs::write(tmpdir.join("c.txt"), "c\n")?;
+    gitlet(&tmpdir, &["add", "c.txt"])?;
+    gitlet(&tmpdir, &["commit", "-m", "Add c.txt"])?;
+    std::fs::write(tmpdir.join("a.txt"), "ONE\ntwo\n")?;
+    std::fs::write(tmpdir.join("b.txt"), "b\n")?;
+    gitlet(&tmpdir, &["rm", "c.txt"])?;
+    gitlet(&tmpdir, &["add", "a.txt", "b.txt"])?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .env("GITLET_AUTHOR_NAME", "Picked Author")
+        .env("GITLET_AUTHOR_EMAIL", "picked@example.com")
+        .args(["commit", "-m", "Feature"]);
+    cmd.assert().success();
+
+    gitlet(&tmpdir, &["switch", "main"])?;
+    std::fs::write(tmpdir.join("a.txt"), "1\ntwo\n")?;
+    gitlet(&tmpdir, &["add", "a.txt"])?;
+    gitlet(&tmpdir, &["commit", "-m", "Second"])?;
+
+    // The picked commit deleted c.txt, which the HEAD does not track, so an untracked c.txt is not
+    // the cherry-pick's to delete.
+    std::fs::write(tmpdir.join("c.txt"), "precious\n")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("cherry-pick").arg("feature");
+    cmd.assert().failure();
+    assert!(tmpdir.join("b.txt").exists());
+
+    gitlet(&tmpdir, &["cherry-pick", "--abort"])?;
+    assert!(!tmpdir.join("b.txt").exists());
+    assert_eq!("precious\n", std::fs::read_to_string(tmpdir.join("c.txt"))?);
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("cherry-pick").arg("feature");
+    cmd.assert().failure();
+    std::fs::write(tmpdir.join("a.txt"), "One\ntwo\n")?;
+    gitlet(&tmpdir, &["add", "a.txt"])?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .env("GITLET_AUTHOR_NAME", "Someone Else")
+        .args(["cherry-pick", "--continue"]);
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .args(["log", "-1", "--format=%an <%ae> %s"]);
+    cmd.assert().success().stdout(predicate::str::diff(
+        "Picked Author <picked@example.com> Feature\n",
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+//! Tests the global command line flags.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+#[test]
+fn print_version() -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.arg("--version");
+    cmd.assert().success().stdout(predicate::str::diff(format!(
+        "gitlet version {}\n",
+        env!("CARGO_PKG_VERSION")
+    )));
+
+    Ok(())
+}
+
+#[test]
+fn print_exec_path() -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.arg("--exec-path");
+    cmd.assert().success().stdout(predicate::str::diff(format!(
+        "{}\n",
+        assert_cmd::cargo::cargo_bin("gitlet").display()
+    )));
+
+    Ok(())
+}
+
+#[test]
+fn subcommand_is_required() -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.assert()
+        .code(2)
+        .stderr(predicate::str::contains("a subcommand is required"));
+
+    Ok(())
+}
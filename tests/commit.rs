@@ -0,0 +1,539 @@
+//! Tests the commit command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+#[test]
+fn fixup_commit_message() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    // Add and commit new file.
+    std::fs::write(tmpdir.join("tmp.txt"), "first")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("tmp.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("-m")
+        .arg("Add tmp.txt\n\nWith a body.");
+    cmd.assert().success();
+
+    // Modify the file and commit it as a fixup of HEAD.
+    std::fs::write(tmpdir.join("tmp.txt"), "second")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("tmp.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("--fixup")
+        .arg("HEAD");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("log");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\nfixup! Add tmp.txt\n\n==="));
+
+    Ok(())
+}
+
+#[test]
+fn fixup_conflicts_with_message() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("--fixup")
+        .arg("HEAD")
+        .arg("-m")
+        .arg("message");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "the argument '--fixup <COMMIT>' cannot be used with '--message <MSG>'",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn template_commit_message() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    std::fs::write(tmpdir.join("tmp.txt"), "first")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("tmp.txt");
+    cmd.assert().success();
+
+    std::fs::write(tmpdir.join("template.txt"), "Subject\n\n# Explain why.\n")?;
+
+    // An editor that leaves the template as is aborts the commit.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .env("VISUAL", "true")
+        .arg("commit")
+        .arg("--template")
+        .arg("template.txt");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Aborting commit due to empty commit message",
+    ));
+
+    // An editor that appends a body to the template's subject line.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .env("VISUAL", "printf 'Because.\\n' >>")
+        .arg("commit")
+        .arg("--template")
+        .arg("template.txt");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("log");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\nSubject\n\nBecause.\n"));
+
+    Ok(())
+}
+
+#[test]
+fn configured_template_commit_message() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    std::fs::write(tmpdir.join("tmp.txt"), "first")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("tmp.txt");
+    cmd.assert().success();
+
+    std::fs::write(tmpdir.join("template.txt"), "Subject\n\n# Explain why.\n")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .env("HOME", tmpdir.path())
+        .arg("config")
+        .arg("--set")
+        .arg("commit.template")
+        .arg("template.txt");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .env("HOME", tmpdir.path())
+        .env("VISUAL", "printf 'Because.\\n' >>")
+        .arg("commit");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("log");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("\nSubject\n\nBecause.\n"));
+
+    Ok(())
+}
+
+#[test]
+fn empty_message_is_rejected_unless_allowed() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    std::fs::write(tmpdir.join("tmp.txt"), "first")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("tmp.txt");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("commit").arg("-m").arg("  ");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Aborting commit due to empty commit message",
+    ));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("--allow-empty-message")
+        .arg("-m")
+        .arg("");
+    cmd.assert().success();
+
+    assert!(!std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn allow_empty_commit() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("-m")
+        .arg("nothing");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing to commit."));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("--allow-empty")
+        .arg("-m")
+        .arg("nothing");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("log")
+        .arg("--format")
+        .arg("%s");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("nothing\n"));
+
+    Ok(())
+}
+
+#[test]
+fn squash_commit_message() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    for (contents, args) in [
+        ("first", vec!["-m", "Add tmp.txt\n\nWith a body."]),
+        ("second", vec!["--squash", "HEAD"]),
+        ("third", vec!["--squash", "HEAD~1", "-m", "Also this"]),
+    ] {
+        std::fs::write(tmpdir.join("tmp.txt"), contents)?;
+        let mut cmd = Command::cargo_bin("gitlet")?;
+        cmd.current_dir(&tmpdir).arg("add").arg("tmp.txt");
+        cmd.assert().success();
+        let mut cmd = Command::cargo_bin("gitlet")?;
+        cmd.current_dir(&tmpdir).arg("commit").args(args);
+        cmd.assert().success();
+    }
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("log")
+        .arg("--format")
+        .arg("%s%n--");
+    cmd.assert().success().stdout(predicate::str::diff(
+        "squash! Add tmp.txt\n--\nsquash! Add tmp.txt\n--\nAdd tmp.txt\n--\n",
+    ));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("log");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "squash! Add tmp.txt\n\nAlso this\n",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn commit_with_date() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    std::fs::write(tmpdir.join("tmp.txt"), "first")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("tmp.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("--date")
+        .arg("2020-01-02T05:04:05+02:00")
+        .arg("-m")
+        .arg("Add tmp.txt");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("log");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "\nDate: Thu, 2 Jan 2020 03:04:05 +0000\nAdd tmp.txt\n",
+    ));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("--allow-empty")
+        .arg("--date")
+        .arg("last week")
+        .arg("-m")
+        .arg("Nothing");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid date: 'last week'"));
+
+    Ok(())
+}
+
+#[test]
+fn refuse_to_commit_conflict_markers() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    std::fs::write(
+        tmpdir.join("tmp.txt"),
+        "<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> feature\n",
+    )?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("tmp.txt");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("-m")
+        .arg("Add tmp.txt");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "The following staged files contain conflict markers:\n\ttmp.txt\n",
+    ));
+    assert!(std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?.is_empty());
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("--force")
+        .arg("-m")
+        .arg("Add tmp.txt");
+    cmd.assert().success();
+    assert!(!std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn commit_only_given_files() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    for name in ["a.txt", "b.txt", "c.txt"] {
+        std::fs::write(tmpdir.join(name), name)?;
+        let mut cmd = Command::cargo_bin("gitlet")?;
+        cmd.current_dir(&tmpdir).arg("add").arg(name);
+        cmd.assert().success();
+    }
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("--only")
+        .arg("a.txt")
+        .arg("--only")
+        .arg("c.txt")
+        .arg("-m")
+        .arg("Add a.txt and c.txt");
+    cmd.assert().success();
+
+    // Only b.txt is left staged.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("status");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "=== Staged Files ===\nb.txt\n\n=== Removed Files ===\n",
+    ));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("--only")
+        .arg("a.txt")
+        .arg("-m")
+        .arg("Again");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("'a.txt' has no staged changes"));
+
+    Ok(())
+}
+
+#[test]
+fn signoff_commit() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    std::fs::write(tmpdir.join("tmp.txt"), "text")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("tmp.txt");
+    cmd.assert().success();
+
+    // Signing off requires knowing who the author is.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .env_remove("GITLET_AUTHOR_NAME")
+        .env("HOME", tmpdir.path())
+        .arg("commit")
+        .arg("--signoff")
+        .arg("-m")
+        .arg("Add tmp.txt");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("GITLET_AUTHOR_NAME"));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .env("GITLET_AUTHOR_NAME", "Alice")
+        .env("GITLET_AUTHOR_EMAIL", "alice@example.com")
+        .arg("commit")
+        .arg("-s")
+        .arg("-m")
+        .arg("Add tmp.txt");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("log")
+        .arg("--format")
+        .arg("%b");
+    cmd.assert().success().stdout(predicate::str::diff(
+        "Signed-off-by: Alice <alice@example.com>\n",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn message_paragraphs_and_stdin() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    // Each -m becomes its own paragraph.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("--allow-empty")
+        .arg("-m")
+        .arg("Subject")
+        .arg("--message")
+        .arg("Body.");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("log")
+        .arg("--format")
+        .arg("%s|%b");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("Subject|Body.\n"));
+
+    // Without -m, the message is read from stdin when it is not a terminal.
+    let mut cmd = assert_cmd::Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("--allow-empty")
+        .write_stdin("From stdin\n# A comment\n");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("log")
+        .arg("--format")
+        .arg("%s");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("From stdin\nSubject\n"));
+
+    Ok(())
+}
+
+#[test]
+fn record_author() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    std::fs::write(
+        tmpdir.join(".gitlet/config"),
+        r#"{"user.name": "Bob", "user.email": "bob@example.com"}"#,
+    )?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .env("HOME", tmpdir.path())
+        .env_remove("GITLET_AUTHOR_NAME")
+        .env_remove("GITLET_AUTHOR_EMAIL")
+        .arg("commit")
+        .arg("--allow-empty")
+        .arg("-m")
+        .arg("First");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("log");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "\nAuthor: Bob <bob@example.com>\nDate: ",
+    ));
+
+    // The environment takes precedence over the config.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .env("HOME", tmpdir.path())
+        .env("GITLET_AUTHOR_NAME", "Alice")
+        .env_remove("GITLET_AUTHOR_EMAIL")
+        .arg("commit")
+        .arg("--allow-empty")
+        .arg("-m")
+        .arg("Second");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("log")
+        .arg("--format")
+        .arg("%an <%ae> %s");
+    cmd.assert().success().stdout(predicate::str::diff(
+        "Alice <bob@example.com> Second\nBob <bob@example.com> First\n",
+    ));
+
+    Ok(())
+}
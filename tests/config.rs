@@ -0,0 +1,119 @@
+//! Tests the config command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+/// Runs gitlet in `dir`, with `home` as the home directory, and with the given arguments.
+fn gitlet(dir: &std::path::Path, home: &std::path::Path, args: &[&str]) -> Command {
+    let mut cmd = Command::cargo_bin("gitlet").unwrap();
+    cmd.current_dir(dir)
+        .env("HOME", home)
+        .env_remove("GITLET_AUTHOR_NAME")
+        .env_remove("GITLET_AUTHOR_EMAIL")
+        .args(args);
+    cmd
+}
+
+#[test]
+fn set_and_get() -> Result<(), Box<dyn Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let tmpdir = assert_fs::TempDir::new()?;
+    gitlet(&tmpdir, &home, &["init"]).assert().success();
+
+    gitlet(&tmpdir, &home, &["config", "--get", "user.name"])
+        .assert()
+        .code(1)
+        .stdout("");
+
+    gitlet(
+        &tmpdir,
+        &home,
+        &["config", "--global", "--set", "user.name", "Global Name"],
+    )
+    .assert()
+    .success();
+    gitlet(&tmpdir, &home, &["config", "--get", "user.name"])
+        .assert()
+        .success()
+        .stdout("Global Name\n");
+
+    // The repository's setting takes precedence, but --global still reads the user's.
+    gitlet(
+        &tmpdir,
+        &home,
+        &["config", "--set", "user.name", "Local Name"],
+    )
+    .assert()
+    .success();
+    gitlet(&tmpdir, &home, &["config", "--get", "user.name"])
+        .assert()
+        .success()
+        .stdout("Local Name\n");
+    gitlet(
+        &tmpdir,
+        &home,
+        &["config", "--global", "--get", "user.name"],
+    )
+    .assert()
+    .success()
+    .stdout("Global Name\n");
+    assert!(std::fs::read_to_string(tmpdir.join(".gitlet/config"))?.contains("Local Name"));
+
+    // New commits are attributed to the configured author.
+    gitlet(
+        &tmpdir,
+        &home,
+        &["config", "--set", "user.email", "local@example.com"],
+    )
+    .assert()
+    .success();
+    std::fs::write(tmpdir.join("a.txt"), "a")?;
+    gitlet(&tmpdir, &home, &["add", "a.txt"]).assert().success();
+    gitlet(&tmpdir, &home, &["commit", "-m", "First"])
+        .assert()
+        .success();
+    gitlet(&tmpdir, &home, &["log", "--format=%an <%ae>"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "Local Name <local@example.com>\n",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn reject_invalid_settings() -> Result<(), Box<dyn Error>> {
+    let home = assert_fs::TempDir::new()?;
+    let tmpdir = assert_fs::TempDir::new()?;
+    gitlet(&tmpdir, &home, &["init"]).assert().success();
+
+    gitlet(
+        &tmpdir,
+        &home,
+        &["config", "--set", "core.autocrlf", "input"],
+    )
+    .assert()
+    .success();
+    gitlet(
+        &tmpdir,
+        &home,
+        &["config", "--set", "core.autocrlf", "sometimes"],
+    )
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("Invalid value 'sometimes'"));
+    gitlet(&tmpdir, &home, &["config", "--set", "user.nmae", "Typo"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown config key 'user.nmae'"));
+    gitlet(&tmpdir, &home, &["config", "--get", "core.autocrlf"])
+        .assert()
+        .success()
+        .stdout("input\n");
+
+    Ok(())
+}
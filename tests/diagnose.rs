@@ -0,0 +1,54 @@
+//! Tests the diagnose command.
+
+use std::error::Error;
+use std::io::Read;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+#[test]
+fn create_diagnostics_file() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).env("HOME", tmpdir.path()).args([
+        "config",
+        "--set",
+        "user.email",
+        "alice@example.com",
+    ]);
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .env("HOME", tmpdir.path())
+        .arg("diagnose");
+    cmd.assert().success().stdout(predicate::str::starts_with(
+        "Created diagnostics file: ./gitlet-diagnostics-",
+    ));
+
+    let zip_path = std::fs::read_dir(&tmpdir)?
+        .filter_map(Result::ok)
+        .map(|e| e.path())
+        .find(|p| p.extension().is_some_and(|ext| ext == "zip"))
+        .expect("diagnostics zip file exists");
+
+    let mut archive = zip::ZipArchive::new(std::fs::File::open(zip_path)?)?;
+    let mut report = String::new();
+    archive
+        .by_name("diagnostics.txt")?
+        .read_to_string(&mut report)?;
+
+    assert!(report.starts_with(&format!("gitlet version {}\n", env!("CARGO_PKG_VERSION"))));
+    assert!(report.contains("branches: 1\n"));
+    assert!(report.contains("commit files: 0 (0 bytes)\n"));
+    assert!(report.contains("user.email = a***@example.com\n"));
+    assert!(!report.contains("alice"));
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+//! Tests the diff command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+/// Runs gitlet in `dir` with the given arguments, asserting that it succeeds.
+fn gitlet(dir: &std::path::Path, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir).args(args);
+    cmd.assert().success();
+    Ok(())
+}
+
+#[test]
+fn unstaged_and_staged_changes() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    gitlet(&tmpdir, &["init"])?;
+    std::fs::write(tmpdir.join("a.txt"), "one\ntwo\nthree\n")?;
+    std::fs::write(tmpdir.join("b.txt"), "bee\n")?;
+    gitlet(&tmpdir, &["add", "a.txt", "b.txt"])?;
+    gitlet(&tmpdir, &["commit", "-m", "Add files"])?;
+
+    // Nothing has changed yet.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("diff");
+    cmd.assert().success().stdout(predicate::str::is_empty());
+
+    std::fs::write(tmpdir.join("a.txt"), "one\n2\nthree\n")?;
+    std::fs::remove_file(tmpdir.join("b.txt"))?;
+    std::fs::write(tmpdir.join("c.txt"), "new\n")?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("diff");
+    cmd.assert().success().stdout(predicate::str::diff(
+        "diff --gitlet a/a.txt b/a.txt\n\
+         --- a/a.txt\n\
+         +++ b/a.txt\n\
+         @@ -1,3 +1,3 @@\n \
+         one\n\
+         -two\n\
+         +2\n \
+         three\n\
+         diff --gitlet a/b.txt b/b.txt\n\
+         deleted file mode 100644\n\
+         --- a/b.txt\n\
+         +++ /dev/null\n\
+         @@ -1 +0,0 @@\n\
+         -bee\n",
+    ));
+
+    // Once staged, the changes move from the plain diff to the cached one.
+    gitlet(&tmpdir, &["add", "a.txt", "c.txt"])?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("diff");
+    cmd.assert().success().stdout(predicate::str::starts_with(
+        "diff --gitlet a/b.txt b/b.txt\n",
+    ));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("diff").arg("--cached");
+    cmd.assert().success().stdout(predicate::str::diff(
+        "diff --gitlet a/a.txt b/a.txt\n\
+         --- a/a.txt\n\
+         +++ b/a.txt\n\
+         @@ -1,3 +1,3 @@\n \
+         one\n\
+         -two\n\
+         +2\n \
+         three\n\
+         diff --gitlet a/c.txt b/c.txt\n\
+         new file mode 100644\n\
+         --- /dev/null\n\
+         +++ b/c.txt\n\
+         @@ -0,0 +1 @@\n\
+         +new\n",
+    ));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("status").arg("-v");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "diff --gitlet a/c.txt b/c.txt\nnew file mode 100644\n",
+    ));
+
+    Ok(())
+}
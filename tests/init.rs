@@ -32,7 +32,7 @@ fn init_new_repo_in_cd() -> Result<(), Box<dyn Error>> {
     tmpdir
         .child(".gitlet/HEAD")
         .assert(predicate::path::exists());
-    let head = fs::File::open(&tmpdir.join(".gitlet/HEAD"))?;
+    let head = fs::File::open(tmpdir.join(".gitlet/HEAD"))?;
     let actual = std::io::read_to_string(head)?;
     assert_eq!(String::from("main"), actual);
 
@@ -66,7 +66,7 @@ fn init_new_repo_in_path() -> Result<(), Box<dyn Error>> {
         .child(".gitlet/HEAD")
         .assert(predicate::path::exists());
 
-    let head = fs::File::open(&tmpdir.join(".gitlet/HEAD"))?;
+    let head = fs::File::open(tmpdir.join(".gitlet/HEAD"))?;
     let actual = std::io::read_to_string(head)?;
     assert_eq!(String::from("main"), actual);
 
@@ -109,7 +109,7 @@ fn create_new_repo_dir_and_init() -> Result<(), Box<dyn Error>> {
         .child(".gitlet/HEAD")
         .assert(predicate::path::exists());
 
-    let head = fs::File::open(&tmpdir.child("new_tmp/.gitlet/HEAD"))?;
+    let head = fs::File::open(tmpdir.child("new_tmp/.gitlet/HEAD"))?;
     let actual = std::io::read_to_string(head)?;
     assert_eq!(String::from("main"), actual);
 
@@ -131,3 +131,66 @@ fn init_fails_repo_already_exists() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn init_copies_template() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let template = tmpdir.child("template");
+    template
+        .child("hooks/pre-commit")
+        .write_str("#!/bin/sh\nexit 0\n")?;
+    template.child("HEAD").write_str("not main")?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("init")
+        .arg("--template")
+        .arg(template.path())
+        .arg("repo");
+    cmd.assert().success();
+
+    let hook = tmpdir.child("repo/.gitlet/hooks/pre-commit");
+    hook.assert("#!/bin/sh\nexit 0\n");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_ne!(0, fs::metadata(hook.path())?.permissions().mode() & 0o111);
+    }
+
+    // Template files do not replace the repository's own.
+    tmpdir.child("repo/.gitlet/HEAD").assert("main");
+
+    Ok(())
+}
+
+#[test]
+fn init_uses_default_template() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    tmpdir
+        .child("home/.gitlet/templates/default/description")
+        .write_str("A gitlet repository")?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .env("HOME", tmpdir.child("home").path())
+        .arg("init")
+        .arg("repo");
+    cmd.assert().success();
+
+    tmpdir
+        .child("repo/.gitlet/description")
+        .assert("A gitlet repository");
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("init")
+        .arg("--template")
+        .arg("missing")
+        .arg("other");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Template directory 'missing' does not exist",
+    ));
+    tmpdir.child("other").assert(predicate::path::missing());
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+//! Tests the interpret-trailers command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+#[test]
+fn add_trailers_from_stdin() -> Result<(), Box<dyn Error>> {
+    let mut cmd = assert_cmd::Command::cargo_bin("gitlet")?;
+    cmd.arg("interpret-trailers")
+        .arg("--trailer")
+        .arg("Signed-off-by: Alice <alice@example.com>")
+        .arg("--trailer")
+        .arg("Reviewed-by=Bob <bob@example.com>")
+        .write_stdin("Subject\n\nSigned-off-by: Alice <alice@example.com>\n");
+    cmd.assert().success().stdout(predicate::str::diff(
+        "Subject\n\nSigned-off-by: Alice <alice@example.com>\nReviewed-by: Bob <bob@example.com>\n",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn add_trailers_in_place() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let msg = tmpdir.join("COMMIT_EDITMSG");
+    std::fs::write(&msg, "Subject\n")?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.arg("interpret-trailers")
+        .arg("--in-place")
+        .arg("--trailer")
+        .arg("Signed-off-by: Alice <alice@example.com>")
+        .arg(&msg);
+    cmd.assert().success().stdout(predicate::str::is_empty());
+
+    assert_eq!(
+        "Subject\n\nSigned-off-by: Alice <alice@example.com>\n",
+        std::fs::read_to_string(&msg)?
+    );
+
+    // There is nothing to write back to without a file.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.arg("interpret-trailers").arg("--in-place");
+    cmd.assert().code(2);
+
+    Ok(())
+}
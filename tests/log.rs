@@ -0,0 +1,264 @@
+//! Tests the log command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+/// Initializes a repository in `dir` and makes one commit for each message, in order.
+fn repo_with_commits(dir: &std::path::Path, messages: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir).arg("init");
+    cmd.assert().success();
+
+    for (i, message) in messages.iter().enumerate() {
+        std::fs::write(dir.join("tmp.txt"), i.to_string())?;
+        let mut cmd = Command::cargo_bin("gitlet")?;
+        cmd.current_dir(dir).arg("add").arg("tmp.txt");
+        cmd.assert().success();
+        let mut cmd = Command::cargo_bin("gitlet")?;
+        cmd.current_dir(dir).arg("commit").arg("-m").arg(message);
+        cmd.assert().success();
+    }
+
+    Ok(())
+}
+
+#[test]
+fn log_format() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    repo_with_commits(&tmpdir, &["first\n\nbody"])?;
+
+    let hash = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("log")
+        .arg("--format")
+        .arg("%h %s%n%H 100%%");
+    cmd.assert().success().stdout(predicate::str::diff(format!(
+        "{} first\n{hash} 100%\n",
+        &hash[..7]
+    )));
+
+    Ok(())
+}
+
+#[test]
+fn log_skip_counts_from_head() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    repo_with_commits(&tmpdir, &["first", "second", "third"])?;
+
+    // The most recent commit is skipped first.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("log")
+        .arg("--skip")
+        .arg("1")
+        .arg("--format")
+        .arg("%s");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("second\nfirst\n"));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("log")
+        .arg("--skip")
+        .arg("3")
+        .arg("--format")
+        .arg("%s");
+    cmd.assert().success().stdout(predicate::str::is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn log_pretty() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    repo_with_commits(&tmpdir, &["first", "second"])?;
+
+    let hash = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("log")
+        .arg("--skip")
+        .arg("1")
+        .arg("--pretty=format:%s");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("first\n"));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("log")
+        .arg("--pretty")
+        .arg("short");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with(format!(
+            "commit {hash}\n\n    second\n\ncommit "
+        )));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("log").arg("--pretty=medium");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid pretty format"));
+
+    Ok(())
+}
+
+#[test]
+fn log_oneline() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    repo_with_commits(&tmpdir, &["first", "second\n\nbody"])?;
+
+    let second = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("log").arg("--oneline");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::is_match(format!(
+            "^{} second\n[0-9a-f]{{7}} first\n$",
+            &second[..7]
+        ))?);
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("log")
+        .arg("--oneline")
+        .arg("--pretty=full");
+    cmd.assert().failure();
+
+    Ok(())
+}
+
+#[test]
+fn log_max_count() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    repo_with_commits(&tmpdir, &["first", "second", "third"])?;
+
+    for args in [&["-n", "2"][..], &["--max-count=2"], &["-2"]] {
+        let mut cmd = Command::cargo_bin("gitlet")?;
+        cmd.current_dir(&tmpdir)
+            .arg("log")
+            .args(args)
+            .arg("--format=%s");
+        cmd.assert()
+            .success()
+            .stdout(predicate::str::diff("third\nsecond\n"));
+    }
+
+    // The count applies after skipping.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("log")
+        .arg("--skip=1")
+        .arg("-1")
+        .arg("--format=%s");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("second\n"));
+
+    Ok(())
+}
+
+#[test]
+fn log_all_refs() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let gitlet = |args: &[&str]| -> Result<(), Box<dyn Error>> {
+        let mut cmd = Command::cargo_bin("gitlet")?;
+        cmd.current_dir(&tmpdir).args(args);
+        cmd.assert().success();
+        Ok(())
+    };
+    let commit = |message: &str, date: &str| -> Result<(), Box<dyn Error>> {
+        std::fs::write(tmpdir.join(format!("{message}.txt")), message)?;
+        gitlet(&["add", &format!("{message}.txt")])?;
+        gitlet(&["commit", "-m", message, "--date", date])
+    };
+
+    gitlet(&["init"])?;
+    commit("base", "5 days ago")?;
+    gitlet(&["switch", "-c", "feature"])?;
+    commit("feature1", "4 days ago")?;
+    commit("feature2", "2 days ago")?;
+    gitlet(&["switch", "main"])?;
+    commit("main1", "3 days ago")?;
+    gitlet(&["switch", "-c", "tagged"])?;
+    commit("tagged", "1 day ago")?;
+    gitlet(&["tag", "v1"])?;
+    gitlet(&["switch", "main"])?;
+    gitlet(&["branch", "-D", "tagged"])?;
+
+    // Commits from every branch and tag are interleaved by date, each shown once.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("log")
+        .arg("--all")
+        .arg("--format=%s");
+    cmd.assert().success().stdout(predicate::str::diff(
+        "tagged\nfeature2\nmain1\nfeature1\nbase\n",
+    ));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("log").arg("--format=%s");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("main1\nbase\n"));
+
+    Ok(())
+}
+
+#[test]
+fn log_grep() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    repo_with_commits(
+        &tmpdir,
+        &[
+            "Add parser",
+            "Fix parser crash\n\nCloses #12",
+            "fix typo in README",
+        ],
+    )?;
+    let log = |args: &[&str]| -> Result<String, Box<dyn Error>> {
+        let output = Command::cargo_bin("gitlet")?
+            .current_dir(&tmpdir)
+            .arg("log")
+            .arg("--format=%s")
+            .args(args)
+            .output()?;
+        assert!(output.status.success());
+        Ok(String::from_utf8(output.stdout)?)
+    };
+
+    assert_eq!("Fix parser crash\n", log(&["--grep", "Fix"])?);
+    assert_eq!(
+        "fix typo in README\nFix parser crash\n",
+        log(&["--grep", "fix", "-i"])?
+    );
+    // The whole message is searched, and patterns are regular expressions.
+    assert_eq!("Fix parser crash\n", log(&["--grep", "#[0-9]+$"])?);
+    assert_eq!(
+        "Fix parser crash\nAdd parser\n",
+        log(&["--grep", "crash", "--grep", "^Add"])?
+    );
+    assert_eq!(
+        "Fix parser crash\n",
+        log(&["--grep", "parser", "--grep", "Fix", "--all-match"])?
+    );
+    // The count limits the matching commits.
+    assert_eq!("Fix parser crash\n", log(&["--grep", "parser", "-1"])?);
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("log").arg("--grep").arg("(");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid pattern for --grep"));
+
+    Ok(())
+}
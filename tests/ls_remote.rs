@@ -0,0 +1,72 @@
+//! Tests the ls-remote command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+#[test]
+fn list_remote_refs() -> Result<(), Box<dyn Error>> {
+    let remote = assert_fs::TempDir::new()?;
+    let local = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&remote).arg("init");
+    cmd.assert().success();
+
+    // A remote without commits has no refs to list.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&local).arg("ls-remote").arg(remote.path());
+    cmd.assert().code(2).stdout(predicate::str::is_empty());
+
+    // Add and commit new file.
+    let mut cmd = Command::new("touch");
+    cmd.current_dir(&remote).arg("tmp.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&remote).arg("add").arg("tmp.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&remote)
+        .arg("commit")
+        .arg("-m")
+        .arg("add tmp.txt");
+    cmd.assert().success();
+
+    let hash = std::fs::read_to_string(remote.join(".gitlet/refs/main"))?;
+    std::fs::create_dir_all(remote.join(".gitlet/refs/tags"))?;
+    std::fs::write(remote.join(".gitlet/refs/tags/v1.0"), &hash)?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&local).arg("ls-remote").arg(remote.path());
+    cmd.assert().success().stdout(predicate::str::diff(format!(
+        "{hash} refs/main\n{hash} refs/tags/v1.0\n"
+    )));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&local)
+        .arg("ls-remote")
+        .arg("--heads")
+        .arg(remote.path());
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff(format!("{hash} refs/main\n")));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&local)
+        .arg("ls-remote")
+        .arg(remote.path())
+        .arg("v1*");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff(format!("{hash} refs/tags/v1.0\n")));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&local).arg("ls-remote").arg(local.path());
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "does not appear to be a gitlet repository",
+    ));
+
+    Ok(())
+}
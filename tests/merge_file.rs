@@ -0,0 +1,77 @@
+//! Tests the merge-file command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+#[test]
+fn merge_file_without_conflicts() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    std::fs::write(tmpdir.join("current.txt"), "one\n2\nthree\n")?;
+    std::fs::write(tmpdir.join("base.txt"), "one\ntwo\nthree\n")?;
+    std::fs::write(tmpdir.join("other.txt"), "one\ntwo\nthree\nfour\n")?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("merge-file")
+        .arg("current.txt")
+        .arg("base.txt")
+        .arg("other.txt");
+    cmd.assert().success().stdout(predicate::str::is_empty());
+
+    assert_eq!(
+        "one\n2\nthree\nfour\n",
+        std::fs::read_to_string(tmpdir.join("current.txt"))?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn merge_file_with_conflicts() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    std::fs::write(tmpdir.join("current.txt"), "one\nTWO\nthree\n")?;
+    std::fs::write(tmpdir.join("base.txt"), "one\ntwo\nthree\n")?;
+    std::fs::write(tmpdir.join("other.txt"), "one\n2\nthree\n")?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("merge-file")
+        .arg("-p")
+        .arg("--marker-size")
+        .arg("3")
+        .arg("-L")
+        .arg("mine")
+        .arg("-L")
+        .arg("base")
+        .arg("-L")
+        .arg("yours")
+        .arg("current.txt")
+        .arg("base.txt")
+        .arg("other.txt");
+    cmd.assert()
+        .code(1)
+        .stdout(predicate::str::diff(
+            "one\n<<< mine\nTWO\n===\n2\n>>> yours\nthree\n",
+        ))
+        .stderr(predicate::str::contains("1 conflict(s)"));
+
+    // With --stdout, the current file is left alone.
+    assert_eq!(
+        "one\nTWO\nthree\n",
+        std::fs::read_to_string(tmpdir.join("current.txt"))?
+    );
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("merge-file")
+        .arg("--quiet")
+        .arg("current.txt")
+        .arg("missing.txt")
+        .arg("other.txt");
+    cmd.assert().code(2);
+
+    Ok(())
+}
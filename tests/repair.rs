@@ -0,0 +1,67 @@
+//! Tests the repair command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+#[test]
+fn repair_missing_head_and_main() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    // Add and commit new file.
+    let mut cmd = Command::new("touch");
+    cmd.current_dir(&tmpdir).arg("tmp.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("tmp.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("-m")
+        .arg("add tmp.txt");
+    cmd.assert().success();
+
+    let main_hash = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+    std::fs::remove_file(tmpdir.join(".gitlet/HEAD"))?;
+    std::fs::remove_file(tmpdir.join(".gitlet/refs/main"))?;
+    std::fs::write(tmpdir.join(".gitlet/index"), "not json")?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("repair").arg("--dry-run");
+    cmd.assert().success().stdout(predicate::str::diff(format!(
+        "Would repair: recreate missing HEAD pointing to 'main'\n\
+        Would repair: recreate missing branch 'main' pointing to {main_hash}\n\
+        Would repair: replace unreadable index with a clear one\n"
+    )));
+    assert!(!tmpdir.join(".gitlet/HEAD").exists());
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("repair");
+    cmd.assert().success();
+
+    assert_eq!(
+        main_hash,
+        std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?
+    );
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("status");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "On branch main\n\n=== Staged Files ===\n\n=== Removed Files ===\n",
+    ));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("repair");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("Nothing to repair\n"));
+
+    Ok(())
+}
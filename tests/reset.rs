@@ -0,0 +1,124 @@
+//! Tests the reset command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::{PredicateBooleanExt, predicate};
+
+/// Runs gitlet in `dir` with the given arguments, asserting that it succeeds.
+fn gitlet(dir: &std::path::Path, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir).args(args);
+    cmd.assert().success();
+    Ok(())
+}
+
+/// Initializes a repository in `dir` with two commits: the first adds a.txt, and the second
+/// changes it and adds b.txt. Returns the hash of the first.
+fn repo_with_two_commits(dir: &std::path::Path) -> Result<String, Box<dyn Error>> {
+    gitlet(dir, &["init"])?;
+    std::fs::write(dir.join("a.txt"), "one")?;
+    gitlet(dir, &["add", "a.txt"])?;
+    gitlet(dir, &["commit", "-m", "First"])?;
+    let first = std::fs::read_to_string(dir.join(".gitlet/refs/main"))?;
+
+    std::fs::write(dir.join("a.txt"), "two")?;
+    std::fs::write(dir.join("b.txt"), "b")?;
+    gitlet(dir, &["add", "a.txt", "b.txt"])?;
+    gitlet(dir, &["commit", "-m", "Second"])?;
+
+    Ok(first)
+}
+
+fn status(dir: &std::path::Path) -> Result<String, Box<dyn Error>> {
+    let output = Command::cargo_bin("gitlet")?
+        .current_dir(dir)
+        .arg("status")
+        .output()?;
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+#[test]
+fn soft_reset_keeps_changes_staged() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let first = repo_with_two_commits(&tmpdir)?;
+
+    gitlet(&tmpdir, &["reset", "--soft", "HEAD~1"])?;
+
+    assert_eq!(
+        first,
+        std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?
+    );
+    assert_eq!("two", std::fs::read_to_string(tmpdir.join("a.txt"))?);
+    let status = status(&tmpdir)?;
+    assert!(status.contains("=== Staged Files ===\n"));
+    assert!(status.contains("a.txt\n") && status.contains("b.txt\n"));
+    assert!(status.contains("=== Unstaged Modifications ===\n\n"));
+
+    Ok(())
+}
+
+#[test]
+fn mixed_reset_leaves_changes_unstaged() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let first = repo_with_two_commits(&tmpdir)?;
+
+    gitlet(&tmpdir, &["reset", &first[..7]])?;
+
+    assert_eq!(
+        first,
+        std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?
+    );
+    assert_eq!("two", std::fs::read_to_string(tmpdir.join("a.txt"))?);
+    assert!(status(&tmpdir)?.contains(
+        "=== Staged Files ===\n\n=== Removed Files ===\n\n\
+         === Unstaged Modifications ===\na.txt\n\n\
+         === Untracked Files ===\nb.txt\n"
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn hard_reset_discards_changes() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let first = repo_with_two_commits(&tmpdir)?;
+
+    // Unstaged modifications are only discarded with --force.
+    std::fs::write(tmpdir.join("a.txt"), "changed")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("reset")
+        .arg("--hard")
+        .arg("HEAD~1");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("a.txt").and(predicate::str::contains("--force")));
+    assert_eq!("changed", std::fs::read_to_string(tmpdir.join("a.txt"))?);
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("reset")
+        .arg("--hard")
+        .arg("--force")
+        .arg("HEAD~1");
+    cmd.assert().success().stdout(predicate::str::diff(format!(
+        "HEAD is now at {} First\n",
+        &first[..7]
+    )));
+
+    assert_eq!(
+        first,
+        std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?
+    );
+    assert_eq!("one", std::fs::read_to_string(tmpdir.join("a.txt"))?);
+    assert!(!tmpdir.join("b.txt").exists());
+    assert!(status(&tmpdir)?.contains(
+        "=== Staged Files ===\n\n=== Removed Files ===\n\n\
+         === Unstaged Modifications ===\n\n\
+         === Untracked Files ===\n\n"
+    ));
+
+    Ok(())
+}
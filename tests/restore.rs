@@ -0,0 +1,82 @@
+//! Tests the restore command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+/// Runs gitlet in `dir` with the given arguments, asserting that it succeeds.
+fn gitlet(dir: &std::path::Path, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir).args(args);
+    cmd.assert().success();
+    Ok(())
+}
+
+#[test]
+fn restore_working_tree_file() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let file = tmpdir.join("a.txt");
+    gitlet(&tmpdir, &["init"])?;
+    std::fs::write(&file, "first")?;
+    gitlet(&tmpdir, &["add", "a.txt"])?;
+    gitlet(&tmpdir, &["commit", "-m", "First"])?;
+    std::fs::write(&file, "second")?;
+    gitlet(&tmpdir, &["add", "a.txt"])?;
+    gitlet(&tmpdir, &["commit", "-m", "Second"])?;
+
+    // Unstaged changes are discarded in favour of HEAD, and deleted files come back.
+    std::fs::write(&file, "changed")?;
+    gitlet(&tmpdir, &["restore", "a.txt"])?;
+    assert_eq!("second", std::fs::read_to_string(&file)?);
+    std::fs::remove_file(&file)?;
+    gitlet(&tmpdir, &["restore", "a.txt"])?;
+    assert_eq!("second", std::fs::read_to_string(&file)?);
+
+    // A staged version takes precedence over HEAD.
+    std::fs::write(&file, "staged")?;
+    gitlet(&tmpdir, &["add", "a.txt"])?;
+    std::fs::write(&file, "changed")?;
+    gitlet(&tmpdir, &["restore", "a.txt"])?;
+    assert_eq!("staged", std::fs::read_to_string(&file)?);
+
+    // Unstaging leaves the working tree alone.
+    gitlet(&tmpdir, &["restore", "--staged", "a.txt"])?;
+    assert_eq!("staged", std::fs::read_to_string(&file)?);
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("status");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "=== Staged Files ===\n\n=== Removed Files ===\n\n=== Unstaged Modifications ===\na.txt\n",
+    ));
+
+    gitlet(&tmpdir, &["restore", "--source", "HEAD~1", "a.txt"])?;
+    assert_eq!("first", std::fs::read_to_string(&file)?);
+
+    std::fs::write(tmpdir.join("new.txt"), "new")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("restore").arg("new.txt");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "'new.txt' is not tracked by commit",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn restore_keeps_file_named_like_lock() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    gitlet(&tmpdir, &["init"])?;
+    std::fs::write(tmpdir.join("f"), "first")?;
+    gitlet(&tmpdir, &["add", "f"])?;
+    gitlet(&tmpdir, &["commit", "-m", "First"])?;
+
+    std::fs::write(tmpdir.join("f"), "changed")?;
+    std::fs::write(tmpdir.join("f.lock"), "untracked")?;
+    gitlet(&tmpdir, &["restore", "f"])?;
+
+    assert_eq!("first", std::fs::read_to_string(tmpdir.join("f"))?);
+    assert_eq!("untracked", std::fs::read_to_string(tmpdir.join("f.lock"))?);
+
+    Ok(())
+}
@@ -0,0 +1,132 @@
+//! Tests the rev-list command.
+
+use std::error::Error;
+use std::path::Path;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+/// Commits a file with the given contents on the given date and returns the new commit's hash.
+fn commit_file(dir: &Path, contents: &str, date: &str) -> Result<String, Box<dyn Error>> {
+    std::fs::write(dir.join(format!("{contents}.txt")), contents)?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir)
+        .arg("add")
+        .arg(format!("{contents}.txt"));
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir)
+        .arg("commit")
+        .arg("--date")
+        .arg(date)
+        .arg("-m")
+        .arg(contents);
+    cmd.assert().success();
+
+    let head = std::fs::read_to_string(dir.join(".gitlet/HEAD"))?;
+    Ok(std::fs::read_to_string(
+        dir.join(".gitlet/refs").join(head),
+    )?)
+}
+
+#[test]
+fn list_commit_ranges() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    // a <- b <- c  (main)
+    //  \-- d       (side)
+    let a = commit_file(&tmpdir, "a", "2024-01-01T00:00:00Z")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("branch").arg("side");
+    cmd.assert().success();
+    let b = commit_file(&tmpdir, "b", "2024-01-02T00:00:00Z")?;
+    let c = commit_file(&tmpdir, "c", "2024-01-04T00:00:00Z")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("switch").arg("side");
+    cmd.assert().success();
+    let d = commit_file(&tmpdir, "d", "2024-01-03T00:00:00Z")?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("rev-list").arg("main");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff(format!("{c}\n{b}\n{a}\n")));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("rev-list")
+        .arg("-n")
+        .arg("1")
+        .arg("HEAD");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff(format!("{d}\n")));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("rev-list")
+        .arg("--count")
+        .arg("side..main");
+    cmd.assert().success().stdout(predicate::str::diff("2\n"));
+
+    // Neither b nor c descends from d.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("rev-list")
+        .arg("--ancestry-path")
+        .arg("side..main");
+    cmd.assert().success().stdout(predicate::str::is_empty());
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("rev-list")
+        .arg("--ancestry-path")
+        .arg(format!("{b}..main"));
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff(format!("{c}\n")));
+
+    Ok(())
+}
+
+#[test]
+fn same_second_commits_are_listed_children_first() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    let mut hashes = Vec::new();
+    for n in 0..6 {
+        hashes.push(commit_file(
+            &tmpdir,
+            &format!("c{n}"),
+            "2024-01-01T00:00:00Z",
+        )?);
+    }
+    hashes.reverse();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("rev-list")
+        .arg("-n")
+        .arg("1")
+        .arg("HEAD");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff(format!("{}\n", hashes[0])));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("rev-list").arg("HEAD");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff(hashes.join("\n") + "\n"));
+
+    Ok(())
+}
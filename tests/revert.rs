@@ -0,0 +1,153 @@
+//! Tests the revert command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::{PredicateBooleanExt, predicate};
+
+/// Runs gitlet in `dir` with the given arguments, asserting that it succeeds.
+fn gitlet(dir: &std::path::Path, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir).args(args);
+    cmd.assert().success();
+    Ok(())
+}
+
+/// Initializes a repository in `dir` whose first commit adds a.txt, and whose second changes its
+/// first line and adds b.txt.
+fn repo_with_two_commits(dir: &std::path::Path) -> Result<(), Box<dyn Error>> {
+    gitlet(dir, &["init"])?;
+    std::fs::write(dir.join("a.txt"), "one\ntwo\nthree\nfour\n")?;
+    gitlet(dir, &["add", "a.txt"])?;
+    gitlet(dir, &["commit", "-m", "First"])?;
+
+    std::fs::write(dir.join("a.txt"), "ONE\ntwo\nthree\nfour\n")?;
+    std::fs::write(dir.join("b.txt"), "b\n")?;
+    gitlet(dir, &["add", "a.txt", "b.txt"])?;
+    gitlet(dir, &["commit", "-m", "Second"])?;
+
+    Ok(())
+}
+
+#[test]
+fn revert_merges_with_later_changes() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    repo_with_two_commits(&tmpdir)?;
+    let second = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+
+    std::fs::write(tmpdir.join("a.txt"), "ONE\ntwo\nthree\nFOUR\n")?;
+    gitlet(&tmpdir, &["add", "a.txt"])?;
+    gitlet(&tmpdir, &["commit", "-m", "Third"])?;
+
+    gitlet(&tmpdir, &["revert", "HEAD~1"])?;
+
+    assert_eq!(
+        "one\ntwo\nthree\nFOUR\n",
+        std::fs::read_to_string(tmpdir.join("a.txt"))?
+    );
+    assert!(!tmpdir.join("b.txt").exists());
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("log").arg("--format=%s%n%b");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with(format!(
+            "Revert \"Second\"\nThis reverts commit {second}.\n"
+        )));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("status");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "=== Staged Files ===\n\n=== Removed Files ===\n\n=== Unstaged Modifications ===\n\n",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn revert_conflict_changes_nothing() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    repo_with_two_commits(&tmpdir)?;
+    let head = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+
+    std::fs::write(tmpdir.join("a.txt"), "1\ntwo\nthree\nfour\n")?;
+    gitlet(&tmpdir, &["add", "a.txt"])?;
+    gitlet(&tmpdir, &["commit", "-m", "Third"])?;
+    let third = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+    assert_ne!(head, third);
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("revert").arg("HEAD~1");
+    cmd.assert().failure().stderr(
+        predicate::str::contains("a.txt").and(predicate::str::contains(format!(
+            "Could not revert {}",
+            &head[..7]
+        ))),
+    );
+
+    assert_eq!(
+        "1\ntwo\nthree\nfour\n",
+        std::fs::read_to_string(tmpdir.join("a.txt"))?
+    );
+    assert!(tmpdir.join("b.txt").exists());
+    assert_eq!(
+        third,
+        std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?
+    );
+
+    Ok(())
+}
+
+#[test]
+fn revert_without_commit() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    repo_with_two_commits(&tmpdir)?;
+    let head = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+
+    gitlet(&tmpdir, &["revert", "--no-commit", "HEAD"])?;
+
+    assert_eq!(
+        head,
+        std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?
+    );
+    assert_eq!(
+        "one\ntwo\nthree\nfour\n",
+        std::fs::read_to_string(tmpdir.join("a.txt"))?
+    );
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("status");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "=== Staged Files ===\na.txt\n\n=== Removed Files ===\nb.txt\n",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn revert_keeps_untracked_files() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    repo_with_two_commits(&tmpdir)?;
+    gitlet(&tmpdir, &["rm", "b.txt"])?;
+    gitlet(&tmpdir, &["commit", "-m", "Third"])?;
+    let third = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+
+    // Reverting the removal would bring back b.txt over the untracked file.
+    std::fs::write(tmpdir.join("b.txt"), "untracked precious\n")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("revert").arg("HEAD");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "The following untracked working tree files would be overwritten by revert:\n\t b.txt",
+    ));
+
+    assert_eq!(
+        "untracked precious\n",
+        std::fs::read_to_string(tmpdir.join("b.txt"))?
+    );
+    assert_eq!(
+        third,
+        std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?
+    );
+
+    Ok(())
+}
@@ -101,7 +101,10 @@ fn rm_from_working_tree() -> Result<(), Box<dyn Error>> {
     cmd.assert().success();
 
     let mut cmd = Command::cargo_bin("gitlet")?;
-    cmd.current_dir(&tmpdir).arg("commit").arg("test commit");
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("-m")
+        .arg("test commit");
     cmd.assert().success();
 
     let mut cmd = Command::cargo_bin("gitlet")?;
@@ -131,7 +134,10 @@ fn rm_already_removed() -> Result<(), Box<dyn Error>> {
     cmd.assert().success();
 
     let mut cmd = Command::cargo_bin("gitlet")?;
-    cmd.current_dir(&tmpdir).arg("commit").arg("test commit");
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("-m")
+        .arg("test commit");
     cmd.assert().success();
 
     // Delete file from working tree and add it to 'removals' in staging area.
@@ -0,0 +1,70 @@
+//! Tests the show command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::{PredicateBooleanExt, predicate};
+
+/// Runs gitlet in `dir` with the given arguments, asserting that it succeeds.
+fn gitlet(dir: &std::path::Path, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir).args(args);
+    cmd.assert().success();
+    Ok(())
+}
+
+#[test]
+fn show_commit_and_diff() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    gitlet(&tmpdir, &["init"])?;
+    std::fs::write(tmpdir.join("a.txt"), "one\ntwo\n")?;
+    gitlet(&tmpdir, &["add", "a.txt"])?;
+    gitlet(&tmpdir, &["commit", "-m", "First"])?;
+    let first = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+
+    std::fs::write(tmpdir.join("a.txt"), "one\nTWO\n")?;
+    std::fs::write(tmpdir.join("b.txt"), "bee\n")?;
+    gitlet(&tmpdir, &["add", "a.txt", "b.txt"])?;
+    gitlet(&tmpdir, &["commit", "-m", "Second"])?;
+    let second = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("show");
+    cmd.assert().success().stdout(
+        predicate::str::starts_with(format!("===\ncommit {second}\n"))
+            .and(predicate::str::contains("\nSecond\n\n"))
+            .and(predicate::str::ends_with(
+                "diff --gitlet a/a.txt b/a.txt\n\
+                 --- a/a.txt\n\
+                 +++ b/a.txt\n\
+                 @@ -1,2 +1,2 @@\n \
+                 one\n\
+                 -two\n\
+                 +TWO\n\
+                 diff --gitlet a/b.txt b/b.txt\n\
+                 new file mode 100644\n\
+                 --- /dev/null\n\
+                 +++ b/b.txt\n\
+                 @@ -0,0 +1 @@\n\
+                 +bee\n",
+            )),
+    );
+
+    // The first commit is compared against no files at all.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("show").arg(&first[..7]);
+    cmd.assert().success().stdout(
+        predicate::str::starts_with(format!("===\ncommit {first}\n")).and(
+            predicate::str::ends_with(
+                "--- /dev/null\n\
+                 +++ b/a.txt\n\
+                 @@ -0,0 +1,2 @@\n\
+                 +one\n\
+                 +two\n",
+            ),
+        ),
+    );
+
+    Ok(())
+}
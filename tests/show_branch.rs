@@ -0,0 +1,115 @@
+//! Tests the show-branch command.
+
+use std::error::Error;
+use std::path::Path;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+/// Commits a file with the given contents on the given date.
+fn commit_file(dir: &Path, contents: &str, date: &str) -> Result<(), Box<dyn Error>> {
+    std::fs::write(dir.join(format!("{contents}.txt")), contents)?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir)
+        .arg("add")
+        .arg(format!("{contents}.txt"));
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir)
+        .arg("commit")
+        .arg("--date")
+        .arg(date)
+        .arg("-m")
+        .arg(contents);
+    cmd.assert().success();
+    Ok(())
+}
+
+#[test]
+fn show_topic_branches() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    // base <- main1 <- main2 (main)
+    //     \-- topic1 (topic)
+    commit_file(&tmpdir, "base", "2024-01-01T00:00:00Z")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("branch").arg("topic");
+    cmd.assert().success();
+    commit_file(&tmpdir, "main1", "2024-01-02T00:00:00Z")?;
+    commit_file(&tmpdir, "main2", "2024-01-04T00:00:00Z")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("switch").arg("topic");
+    cmd.assert().success();
+    commit_file(&tmpdir, "topic1", "2024-01-03T00:00:00Z")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("switch").arg("main");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("show-branch");
+    cmd.assert().success().stdout(predicate::str::diff(
+        "* [main] main2\n \
+          ! [topic] topic1\n\
+         --\n\
+         *  [main] main2\n \
+          + [topic] topic1\n\
+         *  [main~1] main1\n\
+         -- [main~2] base\n",
+    ));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("show-branch")
+        .arg("--topics")
+        .arg("main")
+        .arg("topic");
+    cmd.assert().success().stdout(predicate::str::diff(
+        "* [main] main2\n \
+          ! [topic] topic1\n\
+         --\n \
+          + [topic] topic1\n\
+         -- [main~2] base\n",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn merge_base_of_same_second_commits() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    // base0 <- base1 <- main1 (main)
+    //              \-- topic1 (topic)
+    // All made in the same second, so only their ancestry tells the merge base.
+    let date = "2024-01-01T00:00:00Z";
+    commit_file(&tmpdir, "base0", date)?;
+    commit_file(&tmpdir, "base1", date)?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("branch").arg("topic");
+    cmd.assert().success();
+    commit_file(&tmpdir, "main1", date)?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("switch").arg("topic");
+    cmd.assert().success();
+    commit_file(&tmpdir, "topic1", date)?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("show-branch")
+        .arg("main")
+        .arg("topic");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::ends_with("-- [main~1] base1\n"));
+
+    Ok(())
+}
@@ -0,0 +1,78 @@
+//! Tests the show-ref command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+#[test]
+fn show_refs() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    // Add and commit new file.
+    let mut cmd = Command::new("touch");
+    cmd.current_dir(&tmpdir).arg("tmp.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("tmp.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("-m")
+        .arg("add tmp.txt");
+    cmd.assert().success();
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("branch").arg("test_branch");
+    cmd.assert().success();
+
+    let hash = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+    std::fs::create_dir_all(tmpdir.join(".gitlet/refs/remotes/origin"))?;
+    std::fs::write(tmpdir.join(".gitlet/refs/remotes/origin/main"), &hash)?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("show-ref");
+    cmd.assert().success().stdout(predicate::str::diff(format!(
+        "{hash} refs/main\n{hash} refs/remotes/origin/main\n{hash} refs/test_branch\n"
+    )));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("show-ref").arg("--heads");
+    cmd.assert().success().stdout(predicate::str::diff(format!(
+        "{hash} refs/main\n{hash} refs/test_branch\n"
+    )));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("show-ref")
+        .arg("--heads")
+        .arg("--hash")
+        .arg("--abbrev=7");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff(format!("{0}\n{0}\n", &hash[..7])));
+
+    Ok(())
+}
+
+#[test]
+fn show_ref_without_refs_fails() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    // The main branch has no commits yet.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("show-ref");
+    cmd.assert().code(1).stdout(predicate::str::is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,140 @@
+//! Tests the stash command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::{PredicateBooleanExt, predicate};
+
+/// Runs gitlet in `dir` with the given arguments, asserting that it succeeds.
+fn gitlet(dir: &std::path::Path, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir).args(args);
+    cmd.assert().success();
+    Ok(())
+}
+
+/// Initializes a repository in `dir` whose one commit adds a.txt and b.txt.
+fn repo_with_commit(dir: &std::path::Path) -> Result<(), Box<dyn Error>> {
+    gitlet(dir, &["init"])?;
+    std::fs::write(dir.join("a.txt"), "one\ntwo\nthree\n")?;
+    std::fs::write(dir.join("b.txt"), "b\n")?;
+    gitlet(dir, &["add", "a.txt", "b.txt"])?;
+    gitlet(dir, &["commit", "-m", "First"])?;
+    Ok(())
+}
+
+#[test]
+fn stash_and_pop() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    repo_with_commit(&tmpdir)?;
+
+    std::fs::write(tmpdir.join("a.txt"), "ONE\ntwo\nthree\n")?;
+    std::fs::remove_file(tmpdir.join("b.txt"))?;
+    std::fs::write(tmpdir.join("new.txt"), "new\n")?;
+    gitlet(&tmpdir, &["add", "new.txt"])?;
+    std::fs::write(tmpdir.join("untracked.txt"), "untracked\n")?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("stash");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Saved working directory and index state WIP on main:",
+    ));
+
+    // The working tree is back at the HEAD, apart from untracked files.
+    assert_eq!(
+        "one\ntwo\nthree\n",
+        std::fs::read_to_string(tmpdir.join("a.txt"))?
+    );
+    assert!(tmpdir.join("b.txt").exists());
+    assert!(!tmpdir.join("new.txt").exists());
+    assert!(tmpdir.join("untracked.txt").exists());
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("stash").arg("list");
+    cmd.assert().success().stdout(predicate::str::is_match(
+        "^stash@\\{0\\}: WIP on main: [0-9a-f]{7} First\n$",
+    )?);
+
+    // Popping merges the stash with later commits.
+    std::fs::write(tmpdir.join("a.txt"), "one\ntwo\nTHREE\n")?;
+    gitlet(&tmpdir, &["add", "a.txt"])?;
+    gitlet(&tmpdir, &["commit", "-m", "Second"])?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("stash").arg("pop");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("Dropped stash@{0}"));
+
+    assert_eq!(
+        "ONE\ntwo\nTHREE\n",
+        std::fs::read_to_string(tmpdir.join("a.txt"))?
+    );
+    assert!(!tmpdir.join("b.txt").exists());
+    assert_eq!("new\n", std::fs::read_to_string(tmpdir.join("new.txt"))?);
+    assert!(!tmpdir.join(".gitlet/stash").exists());
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("status");
+    cmd.assert().success().stdout(
+        predicate::str::is_match("=== Staged Files ===\n(a.txt\nnew.txt|new.txt\na.txt)\n")?
+            .and(predicate::str::contains("=== Removed Files ===\nb.txt\n")),
+    );
+
+    Ok(())
+}
+
+#[test]
+fn stash_stack() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    repo_with_commit(&tmpdir)?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("stash");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("No local changes to save\n"));
+
+    std::fs::write(tmpdir.join("a.txt"), "first stash\n")?;
+    gitlet(&tmpdir, &["stash", "push", "-m", "first"])?;
+    std::fs::write(tmpdir.join("a.txt"), "second stash\n")?;
+    gitlet(&tmpdir, &["stash", "push", "-m", "second"])?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("stash").arg("list");
+    cmd.assert().success().stdout(predicate::str::diff(
+        "stash@{0}: On main: second\nstash@{1}: On main: first\n",
+    ));
+
+    gitlet(&tmpdir, &["stash", "drop", "stash@{1}"])?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("stash").arg("list");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("stash@{0}: On main: second\n"));
+
+    // A conflicting stash is kept.
+    std::fs::write(tmpdir.join("a.txt"), "committed\n")?;
+    gitlet(&tmpdir, &["add", "a.txt"])?;
+    gitlet(&tmpdir, &["commit", "-m", "Second"])?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("stash").arg("pop");
+    cmd.assert().failure().stderr(
+        predicate::str::contains("a.txt")
+            .and(predicate::str::contains("Could not apply stash@{0}")),
+    );
+    assert_eq!(
+        "committed\n",
+        std::fs::read_to_string(tmpdir.join("a.txt"))?
+    );
+
+    gitlet(&tmpdir, &["stash", "drop"])?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("stash").arg("drop");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("No stash entries found"));
+
+    Ok(())
+}
@@ -49,3 +49,26 @@ fn staged_file_status() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn ignored_files_status() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    std::fs::write(tmpdir.join(".gitletignore"), "*.log\nbuild/\n")?;
+    std::fs::create_dir(tmpdir.join("build"))?;
+    std::fs::write(tmpdir.join("build/out.o"), "")?;
+    std::fs::write(tmpdir.join("debug.log"), "")?;
+    std::fs::write(tmpdir.join("notes.txt"), "")?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("status");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "=== Untracked Files ===\nnotes.txt\n\n",
+    ));
+
+    Ok(())
+}
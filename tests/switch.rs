@@ -5,7 +5,7 @@ use std::io::Read;
 use std::process::Command;
 
 use assert_cmd::prelude::*;
-use predicates::prelude::predicate;
+use predicates::prelude::{PredicateBooleanExt, predicate};
 
 #[test]
 fn already_on_branch() -> Result<(), Box<dyn Error>> {
@@ -101,3 +101,240 @@ fn switch_to_newly_created_branch() -> Result<(), Box<dyn Error>> {
 
     Ok(())
 }
+
+#[test]
+fn switch_guesses_remote_tracking_branch() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    // Add and commit new file.
+    let mut cmd = Command::new("touch");
+    cmd.current_dir(&tmpdir).arg("tmp.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("tmp.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("-m")
+        .arg("add tmp.txt");
+    cmd.assert().success();
+
+    // "Fetch" a remote tracking branch called 'origin/feature'
+    let main_hash = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+    std::fs::create_dir_all(tmpdir.join(".gitlet/refs/remotes/origin"))?;
+    std::fs::write(
+        tmpdir.join(".gitlet/refs/remotes/origin/feature"),
+        &main_hash,
+    )?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("switch").arg("feature");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Switched to branch 'feature'\nBranch 'feature' set up to track 'origin/feature'.",
+    ));
+
+    let feature_hash = std::fs::read_to_string(tmpdir.join(".gitlet/refs/feature"))?;
+    assert_eq!(main_hash, feature_hash);
+
+    // A remote ref ending in a newline is read just the same.
+    std::fs::write(
+        tmpdir.join(".gitlet/refs/remotes/origin/topic"),
+        format!("{main_hash}\n"),
+    )?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("switch").arg("topic");
+    cmd.assert().success();
+    let topic_hash = std::fs::read_to_string(tmpdir.join(".gitlet/refs/topic"))?;
+    assert_eq!(main_hash, topic_hash);
+
+    // One that doesn't name a commit creates no branch.
+    std::fs::write(tmpdir.join(".gitlet/refs/remotes/origin/broken"), "oops")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("switch").arg("broken");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "Remote tracking branch 'origin/broken' does not point to a commit",
+    ));
+    assert!(!tmpdir.join(".gitlet/refs/broken").exists());
+
+    Ok(())
+}
+
+#[test]
+fn switch_guess_is_ambiguous() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    for remote in ["origin", "upstream"] {
+        let remote_dir = tmpdir.join(".gitlet/refs/remotes").join(remote);
+        std::fs::create_dir_all(&remote_dir)?;
+        std::fs::File::create(remote_dir.join("feature"))?;
+    }
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("switch").arg("feature");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "'feature' matched multiple remote tracking branches: origin, upstream",
+    ));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("switch")
+        .arg("--no-guess")
+        .arg("feature");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid reference: 'feature'"));
+
+    Ok(())
+}
+
+/// Commits a file with the given contents and uses the contents as the commit message.
+fn commit_file(dir: &std::path::Path, contents: &str) -> Result<(), Box<dyn Error>> {
+    std::fs::write(dir.join("tmp.txt"), contents)?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir).arg("add").arg("tmp.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir).arg("commit").arg("-m").arg(contents);
+    cmd.assert().success();
+    Ok(())
+}
+
+#[test]
+fn switch_to_commit_detaches_head() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    for contents in ["first", "second", "third"] {
+        commit_file(&tmpdir, contents)?;
+    }
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("switch").arg("HEAD~2");
+    cmd.assert().success().stdout(predicate::str::is_match(
+        "^HEAD is now at [0-9a-f]{7} first\n$",
+    )?);
+    assert_eq!("first", std::fs::read_to_string(tmpdir.join("tmp.txt"))?);
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("status");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with("HEAD detached at "));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("branch");
+    cmd.assert().success().stdout(predicate::str::is_match(
+        "^\\* \\(HEAD detached at [0-9a-f]{7}\\)\n  main\n$",
+    )?);
+
+    // Committing moves the detached HEAD but leaves the branch alone.
+    let main_hash = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+    commit_file(&tmpdir, "detached")?;
+    assert_eq!(
+        main_hash,
+        std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?
+    );
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("switch").arg("main");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Switched to branch 'main'"));
+    assert_eq!("third", std::fs::read_to_string(tmpdir.join("tmp.txt"))?);
+
+    Ok(())
+}
+
+#[test]
+fn create_branch_at_start_point() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    commit_file(&tmpdir, "first")?;
+    let first_hash = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+    commit_file(&tmpdir, "second")?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("switch")
+        .arg("-c")
+        .arg("old")
+        .arg("main^");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Switched to branch 'old'"));
+
+    assert_eq!(
+        first_hash,
+        std::fs::read_to_string(tmpdir.join(".gitlet/refs/old"))?
+    );
+    assert_eq!("first", std::fs::read_to_string(tmpdir.join("tmp.txt"))?);
+
+    Ok(())
+}
+
+#[test]
+fn force_discards_local_changes() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+    commit_file(&tmpdir, "first")?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("branch").arg("other");
+    cmd.assert().success();
+    commit_file(&tmpdir, "second")?;
+
+    // A staged change that would be overwritten blocks the switch.
+    std::fs::write(tmpdir.join("tmp.txt"), "local")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("tmp.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("switch").arg("other");
+    cmd.assert().failure();
+
+    // Nothing is discarded when the target does not exist.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("switch")
+        .arg("--force")
+        .arg("nosuchbranch");
+    cmd.assert().failure();
+    assert_eq!("local", std::fs::read_to_string(tmpdir.join("tmp.txt"))?);
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("switch")
+        .arg("--force")
+        .arg("other");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Switched to branch 'other'"));
+    assert_eq!("first", std::fs::read_to_string(tmpdir.join("tmp.txt"))?);
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("status");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("tmp.txt").not());
+
+    Ok(())
+}
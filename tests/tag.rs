@@ -0,0 +1,139 @@
+//! Tests the tag command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate;
+
+/// Runs gitlet in `dir` with the given arguments, asserting that it succeeds.
+fn gitlet(dir: &std::path::Path, args: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(dir).args(args);
+    cmd.assert().success();
+    Ok(())
+}
+
+/// Initializes a repository in `dir` with two commits. Returns their hashes, oldest first.
+fn repo_with_two_commits(dir: &std::path::Path) -> Result<(String, String), Box<dyn Error>> {
+    gitlet(dir, &["init"])?;
+    std::fs::write(dir.join("a.txt"), "one")?;
+    gitlet(dir, &["add", "a.txt"])?;
+    gitlet(dir, &["commit", "-m", "First"])?;
+    let first = std::fs::read_to_string(dir.join(".gitlet/refs/main"))?;
+
+    std::fs::write(dir.join("a.txt"), "two")?;
+    gitlet(dir, &["add", "a.txt"])?;
+    gitlet(dir, &["commit", "-m", "Second"])?;
+    let second = std::fs::read_to_string(dir.join(".gitlet/refs/main"))?;
+
+    Ok((first, second))
+}
+
+#[test]
+fn create_list_and_delete_tags() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let (first, second) = repo_with_two_commits(&tmpdir)?;
+
+    gitlet(&tmpdir, &["tag", "v2.0"])?;
+    gitlet(&tmpdir, &["tag", "v1.0", "HEAD~1"])?;
+    assert_eq!(
+        second,
+        std::fs::read_to_string(tmpdir.join(".gitlet/refs/tags/v2.0"))?
+    );
+    assert_eq!(
+        first,
+        std::fs::read_to_string(tmpdir.join(".gitlet/refs/tags/v1.0"))?
+    );
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("tag");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("v1.0\nv2.0\n"));
+
+    // Tags name commits wherever a commit is expected.
+    gitlet(&tmpdir, &["restore", "--source", "v1.0", "a.txt"])?;
+    assert_eq!("one", std::fs::read_to_string(tmpdir.join("a.txt"))?);
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("tag").arg("v1.0");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "A tag named 'v1.0' already exists",
+    ));
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("tag").arg("-d").arg("v1.0");
+    cmd.assert().success().stdout(predicate::str::diff(format!(
+        "Deleted tag 'v1.0' (was {})\n",
+        &first[..7]
+    )));
+    assert!(!tmpdir.join(".gitlet/refs/tags/v1.0").exists());
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("tag").arg("-d").arg("v1.0");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Tag 'v1.0' not found"));
+
+    Ok(())
+}
+
+#[test]
+fn annotated_tag() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    let (_, second) = repo_with_two_commits(&tmpdir)?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .env("GITLET_AUTHOR_NAME", "A U Thor")
+        .env("GITLET_AUTHOR_EMAIL", "author@example.com")
+        .args(["tag", "-a", "v1.0", "-m", "First release"]);
+    cmd.assert().success();
+
+    assert_eq!(
+        second,
+        std::fs::read_to_string(tmpdir.join(".gitlet/refs/tags/v1.0"))?
+    );
+    let tag = std::fs::read_to_string(tmpdir.join(".gitlet/tags/v1.0"))?;
+    assert!(tag.contains(&format!("\"commit\":\"{second}\"")));
+    assert!(tag.contains("\"tagger\":\"A U Thor <author@example.com>\""));
+    assert!(tag.contains("\"message\":\"First release\""));
+
+    // An annotation requires a message.
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).args(["tag", "-a", "v2.0"]);
+    cmd.assert().failure();
+
+    gitlet(&tmpdir, &["tag", "-d", "v1.0"])?;
+    assert!(!tmpdir.join(".gitlet/tags/v1.0").exists());
+
+    Ok(())
+}
+
+#[test]
+fn delete_tag_rejects_invalid_names() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+    repo_with_two_commits(&tmpdir)?;
+    gitlet(&tmpdir, &["tag", "v1.0"])?;
+
+    for name in ["../main", "../../../a.txt"] {
+        let mut cmd = Command::cargo_bin("gitlet")?;
+        cmd.current_dir(&tmpdir).arg("tag").arg("-d").arg(name);
+        cmd.assert()
+            .failure()
+            .stderr(predicate::str::contains("ref names may not contain '..'"));
+    }
+    assert!(tmpdir.join(".gitlet/refs/main").exists());
+    assert!(tmpdir.join("a.txt").exists());
+
+    // A tag ref too short to be a hash is still deleted.
+    std::fs::write(tmpdir.join(".gitlet/refs/tags/short"), "abc")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("tag").arg("-d").arg("short");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::diff("Deleted tag 'short' (was abc)\n"));
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+//! Tests the update-server-info command.
+
+use std::error::Error;
+use std::process::Command;
+
+use assert_cmd::prelude::*;
+
+#[test]
+fn write_server_info() -> Result<(), Box<dyn Error>> {
+    let tmpdir = assert_fs::TempDir::new()?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("init");
+    cmd.assert().success();
+
+    std::fs::write(tmpdir.join("tmp.txt"), "text")?;
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("add").arg("tmp.txt");
+    cmd.assert().success();
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir)
+        .arg("commit")
+        .arg("-m")
+        .arg("add tmp.txt");
+    cmd.assert().success();
+
+    let hash = std::fs::read_to_string(tmpdir.join(".gitlet/refs/main"))?;
+    std::fs::create_dir_all(tmpdir.join(".gitlet/refs/tags"))?;
+    std::fs::write(tmpdir.join(".gitlet/refs/tags/v1.0"), &hash)?;
+    // Remote-tracking branches are not served.
+    std::fs::create_dir_all(tmpdir.join(".gitlet/refs/remotes/origin"))?;
+    std::fs::write(tmpdir.join(".gitlet/refs/remotes/origin/main"), &hash)?;
+
+    let mut cmd = Command::cargo_bin("gitlet")?;
+    cmd.current_dir(&tmpdir).arg("update-server-info");
+    cmd.assert().success();
+
+    assert_eq!(
+        format!("{hash} refs/main\n{hash} refs/tags/v1.0\n"),
+        std::fs::read_to_string(tmpdir.join(".gitlet/info/refs"))?
+    );
+    assert_eq!(
+        "\n",
+        std::fs::read_to_string(tmpdir.join(".gitlet/objects/info/packs"))?
+    );
+
+    Ok(())
+}